@@ -9,16 +9,20 @@ struct Options {
     flag_jobs: Option<u32>,
     flag_features: Vec<String>,
     flag_no_default_features: bool,
+    flag_all_features: bool,
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
+    flag_target_dir: Option<String>,
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
+    flag_message_format: ops::MessageFormat,
     flag_lib: bool,
     flag_bin: Vec<String>,
     flag_example: Vec<String>,
     flag_test: Vec<String>,
     flag_bench: Vec<String>,
+    flag_all_targets: bool,
     arg_args: Vec<String>,
 }
 
@@ -35,16 +39,20 @@ Options:
     --example NAME           Benchmark only the specified example
     --test NAME              Benchmark only the specified test target
     --bench NAME             Benchmark only the specified bench target
+    --all-targets            Benchmark all targets
     --no-run                 Compile, but don't run benchmarks
     -p SPEC, --package SPEC  Package to run benchmarks for
     -j N, --jobs N           The number of jobs to run in parallel
     --features FEATURES      Space-separated list of features to also build
     --no-default-features    Do not build the `default` feature
+    --all-features           Activate all available features
     --target TRIPLE          Build for the target triple
     --manifest-path PATH     Path to the manifest to build benchmarks for
+    --target-dir DIR         Directory for all generated artifacts
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
+    --message-format FMT     Output format: human, json [default: human]
 
 All of the trailing arguments are passed to the benchmark binaries generated
 for filtering benchmarks and generally providing options configuring how they
@@ -65,6 +73,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
     try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
     try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    if let Some(ref dir) = options.flag_target_dir {
+        config.set_target_dir(dir);
+    }
 
     let ops = ops::TestOptions {
         no_run: options.flag_no_run,
@@ -75,6 +86,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
             target: options.flag_target.as_ref().map(|s| &s[..]),
             features: &options.flag_features,
             no_default_features: options.flag_no_default_features,
+            all_features: options.flag_all_features,
             spec: options.flag_package.as_ref().map(|s| &s[..]),
             exec_engine: None,
             release: true,
@@ -83,8 +95,11 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
                                             &options.flag_bin,
                                             &options.flag_test,
                                             &options.flag_example,
-                                            &options.flag_bench),
+                                            &options.flag_bench,
+                                            options.flag_all_targets),
             target_rustc_args: None,
+            target_rustdoc_args: None,
+            message_format: options.flag_message_format,
         },
     };
 
@@ -11,17 +11,21 @@ struct Options {
     flag_jobs: Option<u32>,
     flag_features: Vec<String>,
     flag_no_default_features: bool,
+    flag_all_features: bool,
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
+    flag_target_dir: Option<String>,
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
+    flag_message_format: ops::MessageFormat,
     flag_release: bool,
     flag_lib: bool,
     flag_bin: Vec<String>,
     flag_example: Vec<String>,
     flag_test: Vec<String>,
     flag_bench: Vec<String>,
+    flag_all_targets: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -39,14 +43,18 @@ Options:
     --example NAME           Build only the specified example
     --test NAME              Build only the specified test target
     --bench NAME             Build only the specified benchmark target
+    --all-targets            Build all targets (lib and bin targets by default)
     --release                Build artifacts in release mode, with optimizations
     --features FEATURES      Space-separated list of features to also build
     --no-default-features    Do not build the `default` feature
+    --all-features           Activate all available features
     --target TRIPLE          Build for the target triple
     --manifest-path PATH     Path to the manifest to compile
+    --target-dir DIR         Directory for all generated artifacts
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
+    --message-format FMT     Output format: human, json [default: human]
 
 If the --package argument is given, then SPEC is a package id specification
 which indicates which package should be built. If it is not given, then the
@@ -63,6 +71,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
            env::args().collect::<Vec<_>>());
     try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
     try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    if let Some(ref dir) = options.flag_target_dir {
+        config.set_target_dir(dir);
+    }
 
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
 
@@ -72,6 +83,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
         target: options.flag_target.as_ref().map(|t| &t[..]),
         features: &options.flag_features,
         no_default_features: options.flag_no_default_features,
+        all_features: options.flag_all_features,
         spec: options.flag_package.as_ref().map(|s| &s[..]),
         exec_engine: None,
         mode: ops::CompileMode::Build,
@@ -80,8 +92,11 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
                                         &options.flag_bin,
                                         &options.flag_test,
                                         &options.flag_example,
-                                        &options.flag_bench),
+                                        &options.flag_bench,
+                                        options.flag_all_targets),
         target_rustc_args: None,
+        target_rustdoc_args: None,
+        message_format: options.flag_message_format,
     };
 
     ops::compile(&root, &opts).map(|_| None).map_err(|err| {
@@ -14,7 +14,7 @@ use std::process::Command;
 
 use cargo::{execute_main_without_stdin, handle_error, shell};
 use cargo::core::MultiShell;
-use cargo::util::{CliError, CliResult, lev_distance, Config};
+use cargo::util::{CliError, CliResult, closest, Config};
 
 #[derive(RustcDecodable)]
 struct Flags {
@@ -22,6 +22,10 @@ struct Flags {
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
+    flag_z: Vec<String>,
+    flag_offline: bool,
+    flag_locked: bool,
+    flag_frozen: bool,
     arg_command: String,
     arg_args: Vec<String>,
 }
@@ -30,7 +34,7 @@ const USAGE: &'static str = "
 Rust's package manager
 
 Usage:
-    cargo <command> [<args>...]
+    cargo [options] <command> [<args>...]
     cargo [options]
 
 Options:
@@ -40,9 +44,14 @@ Options:
     -v, --verbose       Use verbose output
     -q, --quiet         No output printed to stdout
     --color WHEN        Coloring: auto, always, never
+    --offline           Run without accessing the network
+    --locked            Require Cargo.lock to be up to date
+    --frozen            Require Cargo.lock to be up to date and run offline
+    -Z FLAG ...         Unstable flags, e.g. `minimal-versions`
 
 Some common cargo commands are:
     build       Compile the current project
+    check       Check the current project for errors without compiling it
     clean       Remove the target directory
     doc         Build this project's and its dependencies' documentation
     new         Create a new cargo project
@@ -63,14 +72,17 @@ fn main() {
 macro_rules! each_subcommand{ ($mac:ident) => ({
     $mac!(bench);
     $mac!(build);
+    $mac!(check);
     $mac!(clean);
     $mac!(doc);
     $mac!(fetch);
     $mac!(generate_lockfile);
     $mac!(git_checkout);
     $mac!(help);
+    $mac!(install);
     $mac!(locate_project);
     $mac!(login);
+    $mac!(metadata);
     $mac!(new);
     $mac!(owner);
     $mac!(package);
@@ -79,9 +91,13 @@ macro_rules! each_subcommand{ ($mac:ident) => ({
     $mac!(read_manifest);
     $mac!(run);
     $mac!(rustc);
+    $mac!(rustdoc);
     $mac!(search);
     $mac!(test);
+    $mac!(tree);
+    $mac!(uninstall);
     $mac!(update);
+    $mac!(vendor);
     $mac!(verify_project);
     $mac!(version);
     $mac!(yank);
@@ -95,6 +111,9 @@ macro_rules! each_subcommand{ ($mac:ident) => ({
 fn execute(flags: Flags, config: &Config) -> CliResult<Option<()>> {
     try!(config.shell().set_verbosity(flags.flag_verbose, flags.flag_quiet));
     try!(config.shell().set_color_config(flags.flag_color.as_ref().map(|s| &s[..])));
+    try!(config.configure_unstable_flags(&flags.flag_z));
+    config.set_offline(flags.flag_offline);
+    config.set_locked(flags.flag_locked, flags.flag_frozen);
 
     init_git_transports(config);
 
@@ -135,8 +154,9 @@ fn execute(flags: Flags, config: &Config) -> CliResult<Option<()>> {
 
         // For all other invocations, we're of the form `cargo foo args...`. We
         // use the exact environment arguments to preserve tokens like `--` for
-        // example.
-        _ => env::args().collect(),
+        // example. Any global flags in front of the subcommand have already
+        // been applied to the configuration above, so they're stripped here.
+        _ => strip_global_flags(env::args().collect()),
     };
 
     macro_rules! cmd{ ($name:ident) => (
@@ -157,20 +177,54 @@ fn execute(flags: Flags, config: &Config) -> CliResult<Option<()>> {
     Ok(None)
 }
 
+/// Removes the global flags, which `cargo` parsed itself, from between
+/// `cargo` and the subcommand in `args`.
+///
+/// Verbosity and coloring are set once more by built-in subcommands from
+/// their own flags, so those flags are moved behind the subcommand for them.
+fn strip_global_flags(mut args: Vec<String>) -> Vec<String> {
+    let mut shell_flags = Vec::new();
+    while args.len() > 1 {
+        let (len, shell) = match &args[1][..] {
+            "-Z" => (2, false),
+            "--color" => (2, true),
+            "--offline" | "--locked" | "--frozen" => (1, false),
+            "-v" | "--verbose" | "-q" | "--quiet" => (1, true),
+            s if s.starts_with("-Z") => (1, false),
+            s if s.starts_with("--color=") => (1, true),
+            _ => break,
+        };
+        for _ in 0..len {
+            if args.len() > 1 {
+                let arg = args.remove(1);
+                if shell {
+                    shell_flags.push(arg);
+                }
+            }
+        }
+    }
+
+    let mut builtin = Vec::new();
+    macro_rules! add_builtin{ ($name:ident) => (
+        builtin.push(stringify!($name).replace("_", "-"))
+    ) }
+    each_subcommand!(add_builtin);
+    let takes_shell_flags = args.len() > 1 && builtin.contains(&args[1]) &&
+                            !["help", "locate-project", "read-manifest",
+                              "version"].contains(&&args[1][..]);
+    if takes_shell_flags {
+        for (i, flag) in shell_flags.into_iter().enumerate() {
+            args.insert(2 + i, flag);
+        }
+    }
+    args
+}
+
 fn find_closest(cmd: &str) -> Option<String> {
     let cmds = list_commands();
     // Only consider candidates with a lev_distance of 3 or less so we don't
     // suggest out-of-the-blue options.
-    let mut filtered = cmds.iter().map(|c| (lev_distance(&c, cmd), c))
-                                  .filter(|&(d, _)| d < 4)
-                                  .collect::<Vec<_>>();
-    filtered.sort_by(|a, b| a.0.cmp(&b.0));
-
-    if filtered.len() == 0 {
-        None
-    } else {
-        Some(filtered[0].1.to_string())
-    }
+    closest(cmd, cmds.iter().map(|c| &c[..]), 3).map(|c| c.to_string())
 }
 
 fn execute_subcommand(cmd: &str, args: &[String], shell: &mut MultiShell) {
@@ -276,12 +330,14 @@ fn list_command_directory() -> Vec<PathBuf> {
 }
 
 fn init_git_transports(config: &Config) {
-    // Only use a custom transport if a proxy is configured, right now libgit2
-    // doesn't support proxies and we have to use a custom transport in this
-    // case. The custom transport, however, is not as well battle-tested.
-    match cargo::ops::http_proxy_exists(config) {
-        Ok(true) => {}
-        _ => return
+    // Only use a custom transport if a proxy or any TLS settings are
+    // configured, right now libgit2 doesn't support either and we have to use
+    // a custom transport in this case. The custom transport, however, is not
+    // as well battle-tested.
+    let proxy = cargo::ops::http_proxy_exists(config).unwrap_or(false);
+    let tls = cargo::ops::http_tls_configured(config).unwrap_or(false);
+    if !proxy && !tls {
+        return
     }
 
     let handle = match cargo::ops::http_handle(config) {
@@ -0,0 +1,107 @@
+use std::env;
+
+use cargo::ops::CompileOptions;
+use cargo::ops;
+use cargo::util::important_paths::{find_root_manifest_for_cwd};
+use cargo::util::{CliResult, CliError, Config};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_package: Option<String>,
+    flag_jobs: Option<u32>,
+    flag_features: Vec<String>,
+    flag_no_default_features: bool,
+    flag_all_features: bool,
+    flag_target: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_target_dir: Option<String>,
+    flag_verbose: bool,
+    flag_quiet: bool,
+    flag_color: Option<String>,
+    flag_message_format: ops::MessageFormat,
+    flag_release: bool,
+    flag_lib: bool,
+    flag_bin: Vec<String>,
+    flag_example: Vec<String>,
+    flag_test: Vec<String>,
+    flag_bench: Vec<String>,
+    flag_all_targets: bool,
+}
+
+pub const USAGE: &'static str = "
+Check a local package and all of its dependencies for errors
+
+Usage:
+    cargo check [options]
+
+Options:
+    -h, --help               Print this message
+    -p SPEC, --package SPEC  Package to check
+    -j N, --jobs N           The number of jobs to run in parallel
+    --lib                    Check only this package's library
+    --bin NAME               Check only the specified binary
+    --example NAME           Check only the specified example
+    --test NAME              Check only the specified test target
+    --bench NAME             Check only the specified benchmark target
+    --all-targets            Check all targets (lib and bin targets by default)
+    --release                Check artifacts in release mode
+    --features FEATURES      Space-separated list of features to also check
+    --no-default-features    Do not check the `default` feature
+    --all-features           Activate all available features
+    --target TRIPLE          Check for the target triple
+    --manifest-path PATH     Path to the manifest to check
+    --target-dir DIR         Directory for all generated artifacts
+    -v, --verbose            Use verbose output
+    -q, --quiet              No output printed to stdout
+    --color WHEN             Coloring: auto, always, never
+    --message-format FMT     Output format: human, json [default: human]
+
+If the --package argument is given, then SPEC is a package id specification
+which indicates which package should be checked. If it is not given, then the
+current package is checked. For more information on SPEC and its format, see
+the `cargo help pkgid` command.
+
+Checking runs the compiler over the package and its dependencies without
+generating any code, which reports errors much faster than a full build. Build
+scripts and compiler plugins are still compiled and run as they normally would.
+The metadata emitted by a check is kept apart from the artifacts of a build, so
+running one doesn't make the other start over.
+";
+
+pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
+    debug!("executing; cmd=cargo-check; args={:?}",
+           env::args().collect::<Vec<_>>());
+    try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
+    try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    if let Some(ref dir) = options.flag_target_dir {
+        config.set_target_dir(dir);
+    }
+
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+
+    let opts = CompileOptions {
+        config: config,
+        jobs: options.flag_jobs,
+        target: options.flag_target.as_ref().map(|t| &t[..]),
+        features: &options.flag_features,
+        no_default_features: options.flag_no_default_features,
+        all_features: options.flag_all_features,
+        spec: options.flag_package.as_ref().map(|s| &s[..]),
+        exec_engine: None,
+        mode: ops::CompileMode::Check,
+        release: options.flag_release,
+        filter: ops::CompileFilter::new(options.flag_lib,
+                                        &options.flag_bin,
+                                        &options.flag_test,
+                                        &options.flag_example,
+                                        &options.flag_bench,
+                                        options.flag_all_targets),
+        target_rustc_args: None,
+        target_rustdoc_args: None,
+        message_format: options.flag_message_format,
+    };
+
+    ops::compile(&root, &opts).map(|_| None).map_err(|err| {
+        CliError::from_boxed(err, 101)
+    })
+}
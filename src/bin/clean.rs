@@ -9,9 +9,11 @@ struct Options {
     flag_package: Option<String>,
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
+    flag_target_dir: Option<String>,
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
+    flag_release: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -24,7 +26,9 @@ Options:
     -h, --help               Print this message
     -p SPEC, --package SPEC  Package to clean artifacts for
     --manifest-path PATH     Path to the manifest to the package to clean
+    --target-dir DIR         Directory for all generated artifacts
     --target TRIPLE          Target triple to clean output for (default all)
+    --release                Whether or not to clean release artifacts
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
@@ -33,11 +37,17 @@ If the --package argument is given, then SPEC is a package id specification
 which indicates which package's artifacts should be cleaned out. If it is not
 given, then all packages' artifacts are removed. For more information on SPEC
 and its format, see the `cargo help pkgid` command.
+
+With --release only the artifacts of release builds are removed, the debug
+artifacts are left alone.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
     try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    if let Some(ref dir) = options.flag_target_dir {
+        config.set_target_dir(dir);
+    }
     debug!("executing; cmd=cargo-clean; args={:?}", env::args().collect::<Vec<_>>());
 
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
@@ -45,6 +55,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
         config: config,
         spec: options.flag_package.as_ref().map(|s| &s[..]),
         target: options.flag_target.as_ref().map(|s| &s[..]),
+        release: options.flag_release,
     };
     ops::clean(&root, &opts).map(|_| None).map_err(|err| {
       CliError::from_boxed(err, 101)
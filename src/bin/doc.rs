@@ -8,7 +8,9 @@ struct Options {
     flag_features: Vec<String>,
     flag_jobs: Option<u32>,
     flag_manifest_path: Option<String>,
+    flag_target_dir: Option<String>,
     flag_no_default_features: bool,
+    flag_all_features: bool,
     flag_no_deps: bool,
     flag_open: bool,
     flag_verbose: bool,
@@ -33,8 +35,10 @@ Options:
     --release                Build artifacts in release mode, with optimizations
     --features FEATURES      Space-separated list of features to also build
     --no-default-features    Do not build the `default` feature
+    --all-features           Activate all available features
     --target TRIPLE          Build for the target triple
     --manifest-path PATH     Path to the manifest to document
+    --target-dir DIR         Directory for all generated artifacts
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
@@ -51,6 +55,9 @@ the `cargo help pkgid` command.
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
     try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    if let Some(ref dir) = options.flag_target_dir {
+        config.set_target_dir(dir);
+    }
 
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
 
@@ -62,6 +69,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
             target: options.flag_target.as_ref().map(|t| &t[..]),
             features: &options.flag_features,
             no_default_features: options.flag_no_default_features,
+            all_features: options.flag_all_features,
             spec: options.flag_package.as_ref().map(|s| &s[..]),
             exec_engine: None,
             filter: ops::CompileFilter::Everything,
@@ -70,6 +78,8 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
                 deps: !options.flag_no_deps,
             },
             target_rustc_args: None,
+            target_rustdoc_args: None,
+            message_format: ops::MessageFormat::Human,
         },
     };
 
@@ -11,6 +11,7 @@ struct Options {
     flag_no_verify: bool,
     flag_no_metadata: bool,
     flag_list: bool,
+    flag_allow_dirty: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -24,6 +25,7 @@ Options:
     -l, --list              Print files included in a package without making one
     --no-verify             Don't verify the contents by building them
     --no-metadata           Ignore warnings about a lack of human-usable metadata
+    --allow-dirty           Allow packaging with uncommitted changes
     --manifest-path PATH    Path to the manifest to compile
     -v, --verbose           Use verbose output
     -q, --quiet             No output printed to stdout
@@ -38,7 +40,8 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     ops::package(&root, config,
                  !options.flag_no_verify,
                  options.flag_list,
-                 !options.flag_no_metadata).map(|_| None).map_err(|err| {
+                 !options.flag_no_metadata,
+                 options.flag_allow_dirty).map(|_| None).map_err(|err| {
         CliError::from_boxed(err, 101)
     })
 }
@@ -5,12 +5,15 @@ use cargo::util::important_paths::find_root_manifest_for_cwd;
 #[derive(RustcDecodable)]
 struct Options {
     flag_host: Option<String>,
+    flag_registry: Option<String>,
     flag_token: Option<String>,
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
     flag_no_verify: bool,
+    flag_allow_dirty: bool,
+    flag_dry_run: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -22,8 +25,12 @@ Usage:
 Options:
     -h, --help               Print this message
     --host HOST              Host to upload the package to
+    --registry REGISTRY      Registry to upload the package to, as named in
+                             the `registries` table of .cargo/config
     --token TOKEN            Token to use when uploading
     --no-verify              Don't verify package tarball before publish
+    --allow-dirty            Allow publishing with uncommitted changes
+    --dry-run                Perform all checks without uploading
     --manifest-path PATH     Path to the manifest to compile
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
@@ -37,13 +44,17 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     let Options {
         flag_token: token,
         flag_host: host,
+        flag_registry: registry,
         flag_manifest_path,
         flag_no_verify: no_verify,
+        flag_allow_dirty: allow_dirty,
+        flag_dry_run: dry_run,
         ..
     } = options;
 
     let root = try!(find_root_manifest_for_cwd(flag_manifest_path.clone()));
-    ops::publish(&root, config, token, host, !no_verify).map(|_| None).map_err(|err| {
+    ops::publish(&root, config, token, host, registry, !no_verify,
+                 allow_dirty, dry_run).map(|_| None).map_err(|err| {
         CliError::from_boxed(err, 101)
     })
 }
@@ -9,8 +9,10 @@ struct Options {
     flag_jobs: Option<u32>,
     flag_features: Vec<String>,
     flag_no_default_features: bool,
+    flag_all_features: bool,
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
+    flag_target_dir: Option<String>,
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
@@ -32,8 +34,10 @@ Options:
     --release               Build artifacts in release mode, with optimizations
     --features FEATURES     Space-separated list of features to also build
     --no-default-features   Do not build the `default` feature
+    --all-features          Activate all available features
     --target TRIPLE         Build for the target triple
     --manifest-path PATH    Path to the manifest to execute
+    --target-dir DIR        Directory for all generated artifacts
     -v, --verbose           Use verbose output
     -q, --quiet             No output printed to stdout
     --color WHEN            Coloring: auto, always, never
@@ -51,6 +55,9 @@ the ones before go to Cargo.
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
     try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    if let Some(ref dir) = options.flag_target_dir {
+        config.set_target_dir(dir);
+    }
 
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
 
@@ -68,6 +75,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
         target: options.flag_target.as_ref().map(|t| &t[..]),
         features: &options.flag_features,
         no_default_features: options.flag_no_default_features,
+        all_features: options.flag_all_features,
         spec: None,
         exec_engine: None,
         release: options.flag_release,
@@ -81,6 +89,8 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
             }
         },
         target_rustc_args: None,
+        target_rustdoc_args: None,
+        message_format: ops::MessageFormat::Human,
     };
 
     let err = try!(ops::run(&root,
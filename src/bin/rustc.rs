@@ -12,11 +12,14 @@ struct Options {
     flag_jobs: Option<u32>,
     flag_features: Vec<String>,
     flag_no_default_features: bool,
+    flag_all_features: bool,
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
+    flag_target_dir: Option<String>,
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
+    flag_message_format: ops::MessageFormat,
     flag_release: bool,
     flag_lib: bool,
     flag_bin: Vec<String>,
@@ -43,11 +46,14 @@ Options:
     --release                Build artifacts in release mode, with optimizations
     --features FEATURES      Features to compile for the package
     --no-default-features    Do not compile default features for the package
+    --all-features           Activate all available features
     --target TRIPLE          Target triple which compiles will be for
     --manifest-path PATH     Path to the manifest to fetch dependencies for
+    --target-dir DIR         Directory for all generated artifacts
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
+    --message-format FMT     Output format: human, json [default: human]
 
 The specified target for the current package (or package specified by SPEC if
 provided) will be compiled along with all of its dependencies. The specified
@@ -66,6 +72,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
            env::args().collect::<Vec<_>>());
     try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
     try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    if let Some(ref dir) = options.flag_target_dir {
+        config.set_target_dir(dir);
+    }
 
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
 
@@ -75,6 +84,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
         target: options.flag_target.as_ref().map(|t| &t[..]),
         features: &options.flag_features,
         no_default_features: options.flag_no_default_features,
+        all_features: options.flag_all_features,
         spec: options.flag_package.as_ref().map(|s| &s[..]),
         exec_engine: None,
         mode: ops::CompileMode::Build,
@@ -83,8 +93,11 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
                                         &options.flag_bin,
                                         &options.flag_test,
                                         &options.flag_example,
-                                        &options.flag_bench),
+                                        &options.flag_bench,
+                                        false),
         target_rustc_args: options.arg_opts.as_ref().map(|a| &a[..]),
+        target_rustdoc_args: None,
+        message_format: options.flag_message_format,
     };
 
     ops::compile(&root, &opts).map(|_| None).map_err(|err| {
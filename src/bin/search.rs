@@ -1,3 +1,5 @@
+use std::cmp;
+
 use cargo::ops;
 use cargo::util::{CliResult, CliError, Config};
 
@@ -7,6 +9,7 @@ struct Options {
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
+    flag_limit: Option<u32>,
     arg_query: String
 }
 
@@ -20,6 +23,7 @@ Usage:
 Options:
     -h, --help               Print this message
     --host HOST              Host of a registry to search in
+    --limit LIMIT            Limit the number of results (default: 10, max: 100)
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
@@ -30,11 +34,12 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
     let Options {
         flag_host: host,
+        flag_limit: limit,
         arg_query: query,
         ..
     } = options;
 
-    ops::search(&query, config, host)
+    ops::search(&query, config, host, cmp::min(100, limit.unwrap_or(10)))
         .map(|_| None)
         .map_err(|err| CliError::from_boxed(err, 101))
 }
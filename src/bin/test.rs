@@ -8,7 +8,9 @@ struct Options {
     flag_features: Vec<String>,
     flag_jobs: Option<u32>,
     flag_manifest_path: Option<String>,
+    flag_target_dir: Option<String>,
     flag_no_default_features: bool,
+    flag_all_features: bool,
     flag_no_run: bool,
     flag_package: Option<String>,
     flag_target: Option<String>,
@@ -17,9 +19,11 @@ struct Options {
     flag_example: Vec<String>,
     flag_test: Vec<String>,
     flag_bench: Vec<String>,
+    flag_all_targets: bool,
     flag_verbose: bool,
     flag_quiet: bool,
     flag_color: Option<String>,
+    flag_message_format: ops::MessageFormat,
     flag_release: bool,
     flag_no_fail_fast: bool,
 }
@@ -37,17 +41,21 @@ Options:
     --example NAME           Test only the specified example
     --test NAME              Test only the specified integration test target
     --bench NAME             Test only the specified benchmark target
+    --all-targets            Test all targets
     --no-run                 Compile, but don't run tests
     -p SPEC, --package SPEC  Package to run tests for
     -j N, --jobs N           The number of jobs to run in parallel
     --release                Build artifacts in release mode, with optimizations
     --features FEATURES      Space-separated list of features to also build
     --no-default-features    Do not build the `default` feature
+    --all-features           Activate all available features
     --target TRIPLE          Build for the target triple
     --manifest-path PATH     Path to the manifest to build tests for
+    --target-dir DIR         Directory for all generated artifacts
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
+    --message-format FMT     Output format: human, json [default: human]
     --no-fail-fast           Run all tests regardless of failure
 
 All of the trailing arguments are passed to the test binaries generated for
@@ -71,6 +79,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
     try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
     try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    if let Some(ref dir) = options.flag_target_dir {
+        config.set_target_dir(dir);
+    }
 
     let ops = ops::TestOptions {
         no_run: options.flag_no_run,
@@ -81,6 +92,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
             target: options.flag_target.as_ref().map(|s| &s[..]),
             features: &options.flag_features,
             no_default_features: options.flag_no_default_features,
+            all_features: options.flag_all_features,
             spec: options.flag_package.as_ref().map(|s| &s[..]),
             exec_engine: None,
             release: options.flag_release,
@@ -89,8 +101,11 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
                                             &options.flag_bin,
                                             &options.flag_test,
                                             &options.flag_example,
-                                            &options.flag_bench),
+                                            &options.flag_bench,
+                                            options.flag_all_targets),
             target_rustc_args: None,
+            target_rustdoc_args: None,
+            message_format: options.flag_message_format,
         },
     };
 
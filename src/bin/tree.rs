@@ -0,0 +1,59 @@
+use cargo::ops::{self, TreeOptions};
+use cargo::util::important_paths::find_root_manifest_for_cwd;
+use cargo::util::{CliResult, CliError, Config};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_color: Option<String>,
+    flag_duplicates: bool,
+    flag_features: Vec<String>,
+    flag_invert: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_no_default_features: bool,
+    flag_quiet: bool,
+    flag_target: Option<String>,
+    flag_verbose: bool,
+}
+
+pub const USAGE: &'static str = "
+Display the resolved dependency graph of a project as a tree
+
+Usage:
+    cargo tree [options]
+
+Options:
+    -h, --help               Print this message
+    --features FEATURES      Space-separated list of features to activate
+    --no-default-features    Do not include the `default` feature
+    --target TRIPLE          Only show dependencies used on the target triple
+    -i, --invert SPEC        Invert the tree, showing what depends on SPEC
+    -d, --duplicates         Show only packages present at multiple versions,
+                             along with what depends on each version
+    --manifest-path PATH     Path to the manifest
+    -v, --verbose            Use verbose output
+    -q, --quiet              No output printed to stdout
+    --color WHEN             Coloring: auto, always, never
+
+Packages which appear more than once in the graph only have their dependencies
+listed the first time, later occurrences are marked with `(*)`.
+";
+
+pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
+    try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
+    try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    let manifest = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+
+    let opts = TreeOptions {
+        features: options.flag_features,
+        no_default_features: options.flag_no_default_features,
+        manifest_path: &manifest,
+        target: options.flag_target.as_ref().map(|s| &s[..]),
+        invert: options.flag_invert.as_ref().map(|s| &s[..]),
+        duplicates: options.flag_duplicates,
+    };
+
+    try!(ops::tree(opts, config).map_err(|err| {
+        CliError::from_boxed(err, 101)
+    }));
+    Ok(None)
+}
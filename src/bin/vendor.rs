@@ -0,0 +1,51 @@
+use cargo::ops::{self, VendorOptions};
+use cargo::util::important_paths::find_root_manifest_for_cwd;
+use cargo::util::{CliResult, CliError, Config};
+
+#[derive(RustcDecodable)]
+struct Options {
+    arg_path: Option<String>,
+    flag_color: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_quiet: bool,
+    flag_verbose: bool,
+}
+
+pub const USAGE: &'static str = "
+Vendor all dependencies of a project into a local directory
+
+Usage:
+    cargo vendor [options] [<path>]
+
+Options:
+    -h, --help               Print this message
+    --manifest-path PATH     Path to the manifest to vendor dependencies for
+    -v, --verbose            Use verbose output
+    -q, --quiet              No output printed to stdout
+    --color WHEN             Coloring: auto, always, never
+
+This command copies the source of every dependency coming from a registry into
+<path>, which defaults to `vendor`, so that the project can be built without
+access to the network. Each package is placed in a directory of its own along
+with the checksums of its files.
+
+Once done, the configuration needed to build from the vendored sources is
+printed, to be added to the `.cargo/config` of the project.
+";
+
+pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
+    try!(config.shell().set_verbosity(options.flag_verbose, options.flag_quiet));
+    try!(config.shell().set_color_config(options.flag_color.as_ref().map(|s| &s[..])));
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+    let path = options.arg_path.unwrap_or("vendor".to_string());
+    let destination = config.cwd().join(&path);
+
+    let opts = VendorOptions {
+        manifest_path: &root,
+        destination: &destination,
+    };
+    try!(ops::vendor(opts, config).map_err(|e| {
+        CliError::from_boxed(e, 101)
+    }));
+    Ok(None)
+}
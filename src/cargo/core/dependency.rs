@@ -1,15 +1,24 @@
-use semver::VersionReq;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use semver::{Version, VersionReq};
 
 use core::{SourceId, Summary, PackageId};
-use std::rc::Rc;
-use util::CargoResult;
+use util::{CargoError, CargoResult, Cfg, CfgExpr, ChainError, human};
 
 /// The data underlying a Dependency.
 #[derive(PartialEq,Clone,Debug)]
 pub struct DependencyInner {
     name: String,
+    // The name this dependency is known by in the manifest and in the crate
+    // depending on it, if different from the name of the package.
+    rename: Option<String>,
     source_id: SourceId,
     req: VersionReq,
+    // The individual comparators of `req`, kept only when one of them names
+    // a prerelease as semver ignores prereleases when matching.
+    comparators: Vec<Comparator>,
     specified_req: Option<String>,
     kind: Kind,
     only_match_name: bool,
@@ -20,7 +29,18 @@ pub struct DependencyInner {
 
     // This dependency should be used only for this platform.
     // `None` means *all platforms*.
-    only_for_platform: Option<String>,
+    platform: Option<Platform>,
+}
+
+/// One comma-separated comparator of a version requirement, such as
+/// `>= 1.0.0-beta.2`.
+#[derive(PartialEq,Clone,Debug)]
+struct Comparator {
+    // The comparator with any prerelease stripped from its version, which is
+    // how semver evaluates it.
+    req: VersionReq,
+    // The operator and the full version, if the version is a prerelease.
+    pre: Option<(String, Version)>,
 }
 
 /// Information about a dependency requested by a Cargo manifest.
@@ -37,19 +57,28 @@ pub enum Kind {
     Build,
 }
 
+/// The platform a dependency is restricted to, either an exact target triple
+/// or a `cfg(...)` expression evaluated against the target's configuration.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Platform {
+    Name(String),
+    Cfg(CfgExpr),
+}
+
 impl DependencyInner {
     /// Attempt to create a `Dependency` from an entry in the manifest.
     pub fn parse(name: &str,
                  version: Option<&str>,
                  source_id: &SourceId) -> CargoResult<DependencyInner> {
-        let version_req = match version {
-            Some(v) => try!(VersionReq::parse(v)),
-            None => VersionReq::any()
+        let (version_req, comparators) = match version {
+            Some(v) => try!(parse_req(v)),
+            None => (VersionReq::any(), Vec::new()),
         };
 
         Ok(DependencyInner {
             only_match_name: false,
             req: version_req,
+            comparators: comparators,
             specified_req: version.map(|s| s.to_string()),
             .. DependencyInner::new_override(name, source_id)
         })
@@ -58,20 +87,30 @@ impl DependencyInner {
     pub fn new_override(name: &str, source_id: &SourceId) -> DependencyInner {
         DependencyInner {
             name: name.to_string(),
+            rename: None,
             source_id: source_id.clone(),
             req: VersionReq::any(),
+            comparators: Vec::new(),
             kind: Kind::Normal,
             only_match_name: true,
             optional: false,
             features: Vec::new(),
             default_features: true,
             specified_req: None,
-            only_for_platform: None,
+            platform: None,
         }
     }
 
     pub fn version_req(&self) -> &VersionReq { &self.req }
     pub fn name(&self) -> &str { &self.name }
+    pub fn rename(&self) -> Option<&str> {
+        self.rename.as_ref().map(|s| &s[..])
+    }
+    /// Returns the name this dependency is referred to by in the manifest
+    /// which declared it, taking a `package = "..."` rename into account.
+    pub fn name_in_toml(&self) -> &str {
+        self.rename().unwrap_or(&self.name)
+    }
     pub fn source_id(&self) -> &SourceId { &self.source_id }
     pub fn kind(&self) -> Kind { self.kind }
     pub fn specified_req(&self) -> Option<&str> {
@@ -80,8 +119,8 @@ impl DependencyInner {
 
     /// If none, this dependencies must be built for all platforms.
     /// If some, it must only be built for the specified platform.
-    pub fn only_for_platform(&self) -> Option<&str> {
-        self.only_for_platform.as_ref().map(|s| &s[..])
+    pub fn platform(&self) -> Option<&Platform> {
+        self.platform.as_ref()
     }
 
     pub fn set_kind(mut self, kind: Kind) -> DependencyInner {
@@ -116,21 +155,37 @@ impl DependencyInner {
     /// Set the version requirement for this dependency
     pub fn set_version_req(mut self, req: VersionReq) -> DependencyInner {
         self.req = req;
+        self.comparators = Vec::new();
         self
     }
 
-    pub fn set_only_for_platform(mut self, platform: Option<String>)
-                                 -> DependencyInner {
-        self.only_for_platform = platform;
+    pub fn set_platform(mut self, platform: Option<Platform>)
+                        -> DependencyInner {
+        self.platform = platform;
+        self
+    }
+
+    /// Sets the local name of this dependency, for when it differs from the
+    /// name of the package it depends on.
+    pub fn set_rename(mut self, rename: &str) -> DependencyInner {
+        self.rename = Some(rename.to_string());
         self
     }
 
     /// Lock this dependency to depending on the specified package id
     pub fn lock_to(self, id: &PackageId) -> DependencyInner {
         assert_eq!(self.source_id, *id.source_id());
-        assert!(self.req.matches(id.version()));
-        self.set_version_req(VersionReq::exact(id.version()))
-            .set_source_id(id.source_id().clone())
+        assert!(self.matches_version(id.version()));
+        let version = id.version();
+        let mut ret = self.set_version_req(VersionReq::exact(version))
+                          .set_source_id(id.source_id().clone());
+        if !version.pre.is_empty() {
+            ret.comparators = vec![Comparator {
+                req: ret.req.clone(),
+                pre: Some(("=".to_string(), version.clone())),
+            }];
+        }
+        ret
     }
 
     /// Returns false if the dependency is only used to build the local package.
@@ -157,10 +212,25 @@ impl DependencyInner {
     /// Returns true if the package (`id`) can fulfill this dependency request.
     pub fn matches_id(&self, id: &PackageId) -> bool {
         self.name == id.name() &&
-            (self.only_match_name || (self.req.matches(id.version()) &&
+            (self.only_match_name || (self.matches_version(id.version()) &&
                                       &self.source_id == id.source_id()))
     }
 
+    /// Returns true if `version` satisfies the version requirement.
+    ///
+    /// Prerelease versions only match a requirement which mentions a
+    /// prerelease of the same `major.minor.patch` (or a requirement of any
+    /// version at all, as path dependencies without a `version` have).
+    pub fn matches_version(&self, version: &Version) -> bool {
+        if self.comparators.is_empty() {
+            return self.req.matches(version) &&
+                   (version.pre.is_empty() || self.req == VersionReq::any())
+        }
+        let opted_in = version.pre.is_empty() ||
+                       self.comparators.iter().any(|c| c.allows_pre(version));
+        opted_in && self.comparators.iter().all(|c| c.matches(version))
+    }
+
     pub fn into_dependency(self) -> Dependency {
         Dependency {inner: Rc::new(self)}
     }
@@ -184,14 +254,16 @@ impl Dependency {
 
     pub fn version_req(&self) -> &VersionReq { self.inner.version_req() }
     pub fn name(&self) -> &str { self.inner.name() }
+    pub fn rename(&self) -> Option<&str> { self.inner.rename() }
+    pub fn name_in_toml(&self) -> &str { self.inner.name_in_toml() }
     pub fn source_id(&self) -> &SourceId { self.inner.source_id() }
     pub fn kind(&self) -> Kind { self.inner.kind() }
     pub fn specified_req(&self) -> Option<&str> { self.inner.specified_req() }
 
     /// If none, this dependencies must be built for all platforms.
     /// If some, it must only be built for the specified platform.
-    pub fn only_for_platform(&self) -> Option<&str> {
-        self.inner.only_for_platform()
+    pub fn platform(&self) -> Option<&Platform> {
+        self.inner.platform()
     }
 
     /// Lock this dependency to depending on the specified package id
@@ -199,6 +271,11 @@ impl Dependency {
         self.clone_inner().lock_to(id).into_dependency()
     }
 
+    /// Returns true if `version` satisfies the version requirement.
+    pub fn matches_version(&self, version: &Version) -> bool {
+        self.inner.matches_version(version)
+    }
+
     /// Returns false if the dependency is only used to build the local package.
     pub fn is_transitive(&self) -> bool { self.inner.is_transitive() }
     pub fn is_build(&self) -> bool { self.inner.is_build() }
@@ -217,6 +294,134 @@ impl Dependency {
     pub fn matches_id(&self, id: &PackageId) -> bool {
         self.inner.matches_id(id)
     }
+
+    /// Points this dependency at `replace_with` if it currently refers to
+    /// `to_replace`, keeping any precise revision it was locked to.
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Dependency {
+        if self.source_id() != to_replace {
+            return self
+        }
+        let precise = self.source_id().precise().map(|s| s.to_string());
+        self.clone_inner()
+            .set_source_id(replace_with.with_precise(precise))
+            .into_dependency()
+    }
+}
+
+impl Comparator {
+    /// Returns true if this comparator names a prerelease of the same
+    /// `major.minor.patch` as `version`.
+    fn allows_pre(&self, version: &Version) -> bool {
+        match self.pre {
+            Some((_, ref pre)) => same_release(pre, version),
+            None => false,
+        }
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        match self.pre {
+            Some((ref op, ref pre)) if same_release(pre, version) => {
+                match &op[..] {
+                    "=" => version == pre,
+                    ">" => version > pre,
+                    "<" => version < pre,
+                    "<=" => version <= pre,
+                    // `>=`, `^`, `~` and a bare version all only bound the
+                    // version from below within the same release.
+                    _ => version >= pre,
+                }
+            }
+            _ => self.req.matches(version),
+        }
+    }
+}
+
+fn same_release(a: &Version, b: &Version) -> bool {
+    a.major == b.major && a.minor == b.minor && a.patch == b.patch
+}
+
+/// Parses a version requirement, keeping track of the comparators which name
+/// a prerelease as semver itself does not understand them.
+#[allow(deprecated)] // connect => join in 1.3
+fn parse_req(req: &str) -> CargoResult<(VersionReq, Vec<Comparator>)> {
+    let mut stripped = Vec::new();
+    let mut comparators = Vec::new();
+    for part in req.split(',').map(|s| s.trim()) {
+        let start = part.find(|c: char| c.is_digit(10)).unwrap_or(part.len());
+        let (op, version) = (part[..start].trim(), &part[start..]);
+        let suffix = version.find(|c: char| c == '-' || c == '+');
+        let (part, pre) = match suffix {
+            Some(i) => {
+                let full = try!(Version::parse(version).map_err(|_| {
+                    human(format!("the version requirement `{}` is invalid, \
+                                   prerelease and build metadata can only \
+                                   follow a full `major.minor.patch` \
+                                   version", req))
+                }));
+                let pre = if full.pre.is_empty() {
+                    None
+                } else {
+                    Some((op.to_string(), full))
+                };
+                (format!("{}{}", op, &version[..i]), pre)
+            }
+            None => (part.to_string(), None),
+        };
+        comparators.push(Comparator {
+            req: try!(VersionReq::parse(&part)),
+            pre: pre,
+        });
+        stripped.push(part);
+    }
+    let version_req = try!(VersionReq::parse(&stripped.connect(", ")));
+    if comparators.iter().all(|c| c.pre.is_none()) {
+        comparators.clear();
+    }
+    Ok((version_req, comparators))
+}
+
+impl Platform {
+    /// Returns whether this platform is activated when compiling for the
+    /// target `name`, whose configuration is given by `cfg`.
+    ///
+    /// The `cfg` list may be unknown if the compiler is too old to print it,
+    /// in which case no `cfg(...)` expression is considered to match.
+    pub fn matches(&self, name: &str, cfg: Option<&[Cfg]>) -> bool {
+        match *self {
+            Platform::Name(ref p) => p == name,
+            Platform::Cfg(ref p) => {
+                match cfg {
+                    Some(cfg) => p.matches(cfg),
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Platform {
+    type Err = Box<CargoError>;
+
+    fn from_str(s: &str) -> CargoResult<Platform> {
+        if s.starts_with("cfg(") && s.ends_with(")") {
+            let s = &s[4..s.len()-1];
+            s.parse().map(Platform::Cfg).chain_error(|| {
+                human(format!("failed to parse `{}` as a cfg expression", s))
+            })
+        } else {
+            Ok(Platform::Name(s.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Platform::Name(ref n) => n.fmt(f),
+            Platform::Cfg(ref e) => write!(f, "cfg({})", e),
+        }
+    }
 }
 
 #[derive(PartialEq,Clone,RustcEncodable)]
@@ -3,8 +3,9 @@ use std::path::{PathBuf, Path};
 
 use semver::Version;
 use rustc_serialize::{Encoder,Encodable};
+use toml;
 
-use core::{Dependency, PackageId, Summary};
+use core::{Dependency, PackageId, PackageIdSpec, Summary};
 use core::package_id::Metadata;
 use core::dependency::SerializedDependency;
 use util::{CargoResult, human};
@@ -20,6 +21,41 @@ pub struct Manifest {
     include: Vec<String>,
     metadata: ManifestMetadata,
     profiles: Profiles,
+    workspace: WorkspaceConfig,
+    replace: Vec<(PackageIdSpec, Dependency)>,
+    custom_metadata: Option<toml::Value>,
+    rust_version: Option<Version>,
+    default_run: Option<String>,
+    publish: Option<Vec<String>>,
+}
+
+/// The result of parsing a `Cargo.toml`, which either describes a package or
+/// is a "virtual manifest" that only configures a workspace.
+pub enum EitherManifest {
+    Real(Manifest),
+    Virtual(VirtualManifest),
+}
+
+/// A manifest without a `[package]` section, which only serves as the root of
+/// a workspace.
+#[derive(Clone, Debug)]
+pub struct VirtualManifest {
+    profiles: Profiles,
+    workspace: WorkspaceConfig,
+    replace: Vec<(PackageIdSpec, Dependency)>,
+    warnings: Vec<String>,
+}
+
+/// Configuration of a workspace in a manifest.
+#[derive(Clone, Debug)]
+pub enum WorkspaceConfig {
+    /// This manifest is a workspace root, listing the given explicit members
+    /// (relative to the manifest's directory) in the `[workspace]` table.
+    Root { members: Option<Vec<String>> },
+
+    /// This manifest is a member of a workspace, optionally pointing at the
+    /// root of its workspace with the `package.workspace` key.
+    Member { root: Option<String> },
 }
 
 /// General metadata about a package which is just blindly uploaded to the
@@ -111,11 +147,13 @@ pub struct Profile {
     pub lto: bool,
     pub codegen_units: Option<u32>,    // None = use rustc default
     pub rustc_args: Option<Vec<String>>,
+    pub rustdoc_args: Option<Vec<String>>,
     pub debuginfo: bool,
     pub debug_assertions: bool,
     pub rpath: bool,
     pub test: bool,
     pub doc: bool,
+    pub check: bool,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -125,6 +163,10 @@ pub struct Profiles {
     pub test: Profile,
     pub bench: Profile,
     pub doc: Profile,
+    pub check: Profile,
+    pub check_test: Profile,
+    pub release_check: Profile,
+    pub release_check_test: Profile,
 }
 
 /// Informations about a binary, a library, an example, etc. that is part of the
@@ -141,6 +183,7 @@ pub struct Target {
     doctest: bool,
     harness: bool, // whether to use the test harness (--test)
     for_host: bool,
+    required_features: Option<Vec<String>>,
 }
 
 #[derive(RustcEncodable)]
@@ -179,7 +222,13 @@ impl Manifest {
                include: Vec<String>,
                links: Option<String>,
                metadata: ManifestMetadata,
-               profiles: Profiles) -> Manifest {
+               profiles: Profiles,
+               workspace: WorkspaceConfig,
+               replace: Vec<(PackageIdSpec, Dependency)>,
+               custom_metadata: Option<toml::Value>,
+               rust_version: Option<Version>,
+               default_run: Option<String>,
+               publish: Option<Vec<String>>) -> Manifest {
         Manifest {
             summary: summary,
             targets: targets,
@@ -189,6 +238,12 @@ impl Manifest {
             links: links,
             metadata: metadata,
             profiles: profiles,
+            workspace: workspace,
+            replace: replace,
+            custom_metadata: custom_metadata,
+            rust_version: rust_version,
+            default_run: default_run,
+            publish: publish,
         }
     }
 
@@ -203,6 +258,22 @@ impl Manifest {
     pub fn version(&self) -> &Version { self.package_id().version() }
     pub fn warnings(&self) -> &[String] { &self.warnings }
     pub fn profiles(&self) -> &Profiles { &self.profiles }
+    pub fn workspace_config(&self) -> &WorkspaceConfig { &self.workspace }
+    pub fn replace(&self) -> &[(PackageIdSpec, Dependency)] { &self.replace }
+    pub fn custom_metadata(&self) -> Option<&toml::Value> {
+        self.custom_metadata.as_ref()
+    }
+    pub fn rust_version(&self) -> Option<&Version> {
+        self.rust_version.as_ref()
+    }
+    pub fn default_run(&self) -> Option<&str> {
+        self.default_run.as_ref().map(|s| &s[..])
+    }
+    /// The registries this package may be published to, where `None` means
+    /// any registry.
+    pub fn publish(&self) -> Option<&[String]> {
+        self.publish.as_ref().map(|v| &v[..])
+    }
     pub fn links(&self) -> Option<&str> {
         self.links.as_ref().map(|s| &s[..])
     }
@@ -216,6 +287,27 @@ impl Manifest {
     }
 }
 
+impl VirtualManifest {
+    pub fn new(profiles: Profiles, workspace: WorkspaceConfig,
+               replace: Vec<(PackageIdSpec, Dependency)>) -> VirtualManifest {
+        VirtualManifest {
+            profiles: profiles,
+            workspace: workspace,
+            replace: replace,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn profiles(&self) -> &Profiles { &self.profiles }
+    pub fn workspace_config(&self) -> &WorkspaceConfig { &self.workspace }
+    pub fn replace(&self) -> &[(PackageIdSpec, Dependency)] { &self.replace }
+    pub fn warnings(&self) -> &[String] { &self.warnings }
+
+    pub fn add_warning(&mut self, s: String) {
+        self.warnings.push(s)
+    }
+}
+
 impl Target {
     fn blank() -> Target {
         Target {
@@ -229,6 +321,7 @@ impl Target {
             for_host: false,
             tested: true,
             benched: true,
+            required_features: None,
         }
     }
 
@@ -317,6 +410,9 @@ impl Target {
     pub fn documented(&self) -> bool { self.doc }
     pub fn for_host(&self) -> bool { self.for_host }
     pub fn benched(&self) -> bool { self.benched }
+    pub fn required_features(&self) -> Option<&[String]> {
+        self.required_features.as_ref().map(|v| &v[..])
+    }
 
     pub fn doctested(&self) -> bool {
         self.doctest && match self.kind {
@@ -403,6 +499,11 @@ impl Target {
         self.doc = doc;
         self
     }
+    pub fn set_required_features(&mut self, features: Option<Vec<String>>)
+                                 -> &mut Target {
+        self.required_features = features;
+        self
+    }
 }
 
 impl Profile {
@@ -442,6 +543,34 @@ impl Profile {
             ..Profile::default_dev()
         }
     }
+
+    pub fn default_check() -> Profile {
+        Profile {
+            check: true,
+            ..Profile::default_dev()
+        }
+    }
+
+    pub fn default_check_test() -> Profile {
+        Profile {
+            test: true,
+            ..Profile::default_check()
+        }
+    }
+
+    pub fn default_release_check() -> Profile {
+        Profile {
+            check: true,
+            ..Profile::default_release()
+        }
+    }
+
+    pub fn default_release_check_test() -> Profile {
+        Profile {
+            test: true,
+            ..Profile::default_release_check()
+        }
+    }
 }
 
 impl Default for Profile {
@@ -451,11 +580,13 @@ impl Default for Profile {
             lto: false,
             codegen_units: None,
             rustc_args: None,
+            rustdoc_args: None,
             debuginfo: false,
             debug_assertions: false,
             rpath: false,
             test: false,
             doc: false,
+            check: false,
         }
     }
 }
@@ -9,6 +9,7 @@ use ops;
 use core::dependency::SerializedDependency;
 use util::{CargoResult, graph, Config};
 use rustc_serialize::{Encoder,Encodable};
+use toml;
 use core::source::Source;
 
 /// Information about a package that is available somewhere in the file system.
@@ -27,9 +28,11 @@ pub struct Package {
 struct SerializedPackage {
     name: String,
     version: String,
+    id: PackageId,
     dependencies: Vec<SerializedDependency>,
     targets: Vec<Target>,
     manifest_path: String,
+    metadata: Option<toml::Value>,
 }
 
 impl Encodable for Package {
@@ -41,11 +44,13 @@ impl Encodable for Package {
         SerializedPackage {
             name: package_id.name().to_string(),
             version: package_id.version().to_string(),
+            id: package_id.clone(),
             dependencies: summary.dependencies().iter().map(|d| {
                 SerializedDependency::from_dependency(d)
             }).collect(),
             targets: manifest.targets().to_vec(),
-            manifest_path: self.manifest_path.display().to_string()
+            manifest_path: self.manifest_path.display().to_string(),
+            metadata: manifest.custom_metadata().cloned(),
         }.encode(s)
     }
 }
@@ -77,6 +82,32 @@ impl Package {
     pub fn targets(&self) -> &[Target] { self.manifest().targets() }
     pub fn version(&self) -> &Version { self.package_id().version() }
 
+    /// Moves this package from `to_replace` over to `replace_with`, see
+    /// `Summary::map_source`.
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Package {
+        let mut manifest = self.manifest;
+        let summary = manifest.summary().clone()
+                              .map_source(to_replace, replace_with);
+        manifest.set_summary(summary);
+        Package {
+            manifest: manifest,
+            manifest_path: self.manifest_path,
+        }
+    }
+
+    /// Records `cksum` as the checksum of the `.crate` file this package was
+    /// unpacked from, see `Summary::checksum`.
+    pub fn set_checksum(self, cksum: String) -> Package {
+        let mut manifest = self.manifest;
+        let summary = manifest.summary().clone().set_checksum(cksum);
+        manifest.set_summary(summary);
+        Package {
+            manifest: manifest,
+            manifest_path: self.manifest_path,
+        }
+    }
+
     pub fn has_custom_build(&self) -> bool {
         self.targets().iter().any(|t| t.is_custom_build())
     }
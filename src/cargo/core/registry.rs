@@ -2,6 +2,7 @@ use std::collections::HashSet;
 use std::collections::hash_map::HashMap;
 
 use core::{Source, SourceId, SourceMap, Summary, Dependency, PackageId, Package};
+use sources::SourceConfigMap;
 use util::{CargoResult, ChainError, Config, human, profile};
 
 /// Source of information about a group of packages.
@@ -171,7 +172,8 @@ impl<'cfg> PackageRegistry<'cfg> {
 
     fn load(&mut self, source_id: &SourceId, kind: Kind) -> CargoResult<()> {
         (|| {
-            let mut source = source_id.load(self.config);
+            let map = try!(SourceConfigMap::new(self.config));
+            let mut source = try!(map.load(source_id));
 
             // Ensure the source has fetched all necessary remote data.
             let p = profile::start(format!("updating: {}", source_id));
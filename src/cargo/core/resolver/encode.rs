@@ -17,6 +17,10 @@ pub struct EncodableResolve {
 
 pub type Metadata = BTreeMap<String, String>;
 
+// Checksums of packages are stored in the `[metadata]` table of the lock
+// file, keyed by this prefix followed by the package's id.
+static CHECKSUM_PREFIX: &'static str = "checksum ";
+
 impl EncodableResolve {
     pub fn to_resolve(&self, default: &SourceId) -> CargoResult<Resolve> {
         let mut g = Graph::new();
@@ -69,11 +73,31 @@ impl EncodableResolve {
             }
         }
 
+        let mut metadata = self.metadata.clone().unwrap_or(BTreeMap::new());
+        let mut checksums = HashMap::new();
+        for id in g.iter() {
+            let key = checksum_key(id);
+            let cksum = metadata.remove(&key);
+            // Packages from a registry are expected to have a checksum, if
+            // one isn't listed then it's just not known yet.
+            if cksum.is_some() || !id.source_id().is_registry() {
+                checksums.insert(id.clone(), cksum);
+            }
+        }
+        // Any checksums left over belong to packages which are gone.
+        let stale = metadata.keys().filter(|k| {
+            k.starts_with(CHECKSUM_PREFIX)
+        }).cloned().collect::<Vec<_>>();
+        for key in stale.iter() {
+            metadata.remove(key);
+        }
+
         Ok(Resolve {
             graph: g,
             root: try!(self.root.to_package_id(default)),
             features: HashMap::new(),
-            metadata: self.metadata.clone(),
+            metadata: if metadata.is_empty() {None} else {Some(metadata)},
+            checksums: checksums,
         })
     }
 }
@@ -154,14 +178,26 @@ impl Encodable for Resolve {
             Some(encodable_resolve_node(id, &self.root, &self.graph))
         }).collect::<Vec<EncodableDependency>>();
 
+        let mut metadata = self.metadata.clone().unwrap_or(BTreeMap::new());
+        for id in ids.iter() {
+            if let Some(&Some(ref cksum)) = self.checksums.get(*id) {
+                metadata.insert(checksum_key(id), cksum.clone());
+            }
+        }
+
         EncodableResolve {
             package: Some(encodable),
             root: encodable_resolve_node(&self.root, &self.root, &self.graph),
-            metadata: self.metadata.clone(),
+            metadata: if metadata.is_empty() {None} else {Some(metadata)},
         }.encode(s)
     }
 }
 
+fn checksum_key(id: &PackageId) -> String {
+    format!("{}{} {} ({})", CHECKSUM_PREFIX, id.name(), id.version(),
+            id.source_id().to_url())
+}
+
 fn encodable_resolve_node(id: &PackageId, root: &PackageId,
                           graph: &Graph<PackageId>) -> EncodableDependency {
     let deps = graph.edges(id).map(|edge| {
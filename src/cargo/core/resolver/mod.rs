@@ -54,8 +54,10 @@ use std::rc::Rc;
 use semver;
 
 use core::{PackageId, Registry, SourceId, Summary, Dependency};
+use core::dependency::Kind;
 use core::PackageIdSpec;
-use util::{CargoResult, Graph, human, ChainError, CargoError};
+use util::{CargoResult, Graph, human, ChainError, CargoError, Config};
+use util::closest;
 use util::profile;
 use util::graph::{Nodes, Edges};
 
@@ -75,6 +77,7 @@ pub struct Resolve {
     features: HashMap<PackageId, HashSet<String>>,
     root: PackageId,
     metadata: Option<Metadata>,
+    checksums: HashMap<PackageId, Option<String>>,
 }
 
 #[derive(Clone, Copy)]
@@ -101,11 +104,59 @@ impl Resolve {
     fn new(root: PackageId) -> Resolve {
         let mut g = Graph::new();
         g.add(root.clone(), &[]);
-        Resolve { graph: g, root: root, features: HashMap::new(), metadata: None }
+        Resolve {
+            graph: g,
+            root: root,
+            features: HashMap::new(),
+            metadata: None,
+            checksums: HashMap::new(),
+        }
     }
 
-    pub fn copy_metadata(&mut self, other: &Resolve) {
-        self.metadata = other.metadata.clone();
+    /// Carries the metadata of the `previous` resolve, typically loaded from
+    /// the lock file, over to this one.
+    ///
+    /// Packages present in both must have the same checksum, otherwise what
+    /// would be built is not what the lock file was generated with.
+    pub fn merge_from(&mut self, previous: &Resolve) -> CargoResult<()> {
+        for (id, cksum) in previous.checksums.iter() {
+            let mine = match self.checksums.get(id) {
+                Some(mine) => mine,
+                None => continue,
+            };
+            match (cksum, mine) {
+                (&Some(ref a), &Some(ref b)) if a == b => {}
+                (&Some(_), &Some(_)) => {
+                    return Err(human(format!("\
+checksum for `{}` changed between lock files
+
+this could be indicative of a few possible errors:
+
+    * the lock file is corrupt
+    * a replacement source in use (e.g. a mirror) returned a different checksum
+    * the source itself may be corrupt in one way or another
+
+unable to verify that `{0}` is the same as when the lockfile was generated
+", id)))
+                }
+                (&Some(_), &None) => {
+                    return Err(human(format!("\
+checksum for `{}` could not be calculated, but a checksum is listed in the \
+existing lock file
+
+this could be indicative of a few possible situations:
+
+    * the source `{}` was replaced with one which doesn't provide checksums
+    * the lock file is corrupt
+
+unable to verify that `{0}` is the same as when the lockfile was generated
+", id, id.source_id())))
+                }
+                (&None, _) => {}
+            }
+        }
+        self.metadata = previous.metadata.clone();
+        Ok(())
     }
 
     pub fn iter(&self) -> Nodes<PackageId> {
@@ -167,6 +218,12 @@ impl Resolve {
     pub fn features(&self, pkg: &PackageId) -> Option<&HashSet<String>> {
         self.features.get(pkg)
     }
+
+    /// The checksums of the packages in this resolve, as recorded in the lock
+    /// file. Packages which don't come from a registry have no checksum.
+    pub fn checksums(&self) -> &HashMap<PackageId, Option<String>> {
+        &self.checksums
+    }
 }
 
 impl fmt::Debug for Resolve {
@@ -185,21 +242,40 @@ struct Context {
     activations: HashMap<(String, SourceId), Vec<Rc<Summary>>>,
     resolve: Resolve,
     visited: HashSet<PackageId>,
+    replacements: Rc<Vec<(PackageIdSpec, Dependency)>>,
+    minimal_versions: bool,
 }
 
-/// Builds the list of all packages required to build the first argument.
-pub fn resolve(summary: &Summary, method: &Method,
-               registry: &mut Registry) -> CargoResult<Resolve> {
-    trace!("resolve; summary={}", summary.package_id());
-    let summary = Rc::new(summary.clone());
+/// Builds the list of all packages required to build the summaries given.
+///
+/// Each summary is resolved with its paired method, and all of them end up in
+/// the same dependency graph. The first summary becomes the root of the
+/// returned `Resolve`.
+///
+/// Any package matched by one of the `replacements` specs is swapped out for
+/// the package that the paired dependency resolves to.
+///
+/// If a `config` is given and `-Z minimal-versions` was passed, the lowest
+/// version satisfying each requirement is preferred instead of the highest.
+pub fn resolve(summaries: &[(Summary, Method)],
+               replacements: &[(PackageIdSpec, Dependency)],
+               registry: &mut Registry,
+               config: Option<&Config>) -> CargoResult<Resolve> {
+    let root = summaries[0].0.package_id().clone();
+    trace!("resolve; root={}", root);
 
     let cx = Context {
-        resolve: Resolve::new(summary.package_id().clone()),
+        resolve: Resolve::new(root.clone()),
         activations: HashMap::new(),
         visited: HashSet::new(),
+        replacements: Rc::new(replacements.to_vec()),
+        minimal_versions: config.map(|c| c.minimal_versions()).unwrap_or(false),
     };
-    let _p = profile::start(format!("resolving: {}", summary.package_id()));
-    activate_deps_loop(cx, registry, summary, method)
+    let summaries = summaries.iter().map(|&(ref summary, method)| {
+        (Rc::new(summary.clone()), method)
+    }).collect::<Vec<_>>();
+    let _p = profile::start(format!("resolving: {}", root));
+    activate_deps_loop(cx, registry, &summaries)
 }
 
 /// Attempts to activate the summary `parent` in the context `cx`.
@@ -208,6 +284,7 @@ pub fn resolve(summary: &Summary, method: &Method,
 /// the dependencies of the package will be determined by the `method` provided.
 /// If `parent` was activated, this function returns the dependency frame to
 /// iterate through next.
+#[allow(deprecated)] // connect => join in 1.3
 fn activate(cx: &mut Context,
             registry: &mut Registry,
             parent: Rc<Summary>,
@@ -217,8 +294,23 @@ fn activate(cx: &mut Context,
     // packages we're visiting and bail if we hit a dupe.
     let id = parent.package_id().clone();
     if !cx.visited.insert(id.clone()) {
-        return Err(human(format!("cyclic package dependency: package `{}` \
-                                  depends on itself", id)))
+        let mut msg = format!("cyclic package dependency: package `{}` \
+                               depends on itself", id);
+        let path = cx.cycle_path(&id);
+        if path.len() > 0 {
+            let desc = path.iter().map(describe).collect::<Vec<_>>();
+            msg.push_str(&format!("\ncycle: {}", desc.connect(" => ")));
+            // Cycles aren't allowed through dev-dependencies either, which
+            // is easily overlooked, so those edges are pointed out.
+            for pair in path.windows(2) {
+                if cx.is_dev_edge(&pair[0], &pair[1]) {
+                    msg.push_str(&format!("\nnote: `{}` is a dev-dependency \
+                                           of `{}`", describe(&pair[1]),
+                                          describe(&pair[0])));
+                }
+            }
+        }
+        return Err(human(msg))
     }
 
     // If we're already activated, then that was easy!
@@ -270,26 +362,32 @@ struct DepsFrame {
     id: PackageId,
 }
 
-struct BacktrackFrame {
+struct BacktrackFrame<'a> {
     context_backup: Context,
     deps_backup: Vec<DepsFrame>,
+    roots_backup: Vec<(Rc<Summary>, Method<'a>)>,
     remaining_candidates: RcVecIter<Rc<Summary>>,
     parent: Rc<Summary>,
     dep: Dependency,
 }
 
-/// Recursively activates the dependencies for `top`, in depth-first order,
-/// backtracking across possible candidates for each dependency as necessary.
+/// Recursively activates the dependencies for `summaries`, in depth-first
+/// order, backtracking across possible candidates for each dependency as
+/// necessary.
+///
+/// The summaries are activated one after another, each one only once the
+/// dependencies of the previous one have all been activated.
 ///
 /// If all dependencies can be activated and resolved to a version in the
 /// dependency graph, cx.resolve is returned.
-fn activate_deps_loop(mut cx: Context,
-                      registry: &mut Registry,
-                      top: Rc<Summary>,
-                      top_method: &Method) -> CargoResult<Resolve> {
+fn activate_deps_loop<'a>(mut cx: Context,
+                          registry: &mut Registry,
+                          summaries: &[(Rc<Summary>, Method<'a>)])
+                          -> CargoResult<Resolve> {
     let mut backtrack_stack = Vec::new();
     let mut remaining_deps = Vec::new();
-    remaining_deps.extend(try!(activate(&mut cx, registry, top, &top_method)));
+    let mut remaining_roots = summaries.iter().rev().cloned()
+                                       .collect::<Vec<_>>();
 
     // Main resolution loop, this is the workhorse of the resolution algorithm.
     //
@@ -304,7 +402,22 @@ fn activate_deps_loop(mut cx: Context,
     // its own dependencies in turn. The `backtrack_stack` is a side table of
     // backtracking states where if we hit an error we can return to in order to
     // attempt to continue resolving.
-    while let Some(mut deps_frame) = remaining_deps.pop() {
+    loop {
+        let mut deps_frame = match remaining_deps.pop() {
+            Some(frame) => frame,
+            None => {
+                match remaining_roots.pop() {
+                    Some((summary, method)) => {
+                        debug!("initial activation: {}", summary.package_id());
+                        remaining_deps.extend(try!(activate(&mut cx, registry,
+                                                            summary,
+                                                            &method)));
+                        continue
+                    }
+                    None => break,
+                }
+            }
+        };
         let frame = match deps_frame.remaining_siblings.next() {
             Some(sibling) => {
                 let parent = deps_frame.parent.clone();
@@ -367,6 +480,7 @@ fn activate_deps_loop(mut cx: Context,
                 backtrack_stack.push(BacktrackFrame {
                     context_backup: cx.clone(),
                     deps_backup: remaining_deps.clone(),
+                    roots_backup: remaining_roots.clone(),
                     remaining_candidates: remaining_candidates,
                     parent: parent.clone(),
                     dep: dep.clone(),
@@ -381,8 +495,8 @@ fn activate_deps_loop(mut cx: Context,
                 trace!("{}[{}]>{} -- no candidates", parent.name(), cur,
                        dep.name());
                 match find_candidate(&mut backtrack_stack, &mut cx,
-                                     &mut remaining_deps, &mut parent, &mut cur,
-                                     &mut dep) {
+                                     &mut remaining_deps, &mut remaining_roots,
+                                     &mut parent, &mut cur, &mut dep) {
                     None => return Err(activation_error(&cx, registry, &parent,
                                                         &dep,
                                                         &cx.prev_active(&dep),
@@ -405,21 +519,28 @@ fn activate_deps_loop(mut cx: Context,
         remaining_deps.extend(try!(activate(&mut cx, registry,
                                             candidate, &method)));
     }
+    for summary in cx.activations.values().flat_map(|v| v.iter()) {
+        let cksum = summary.checksum().map(|s| s.to_string());
+        cx.resolve.checksums.insert(summary.package_id().clone(), cksum);
+    }
     trace!("resolved: {:?}", cx.resolve);
     Ok(cx.resolve)
 }
 
 // Searches up `backtrack_stack` until it finds a dependency with remaining
-// candidates. Resets `cx` and `remaining_deps` to that level and returns the
-// next candidate. If all candidates have been exhausted, returns None.
-fn find_candidate(backtrack_stack: &mut Vec<BacktrackFrame>,
-                  cx: &mut Context, remaining_deps: &mut Vec<DepsFrame>,
-                  parent: &mut Rc<Summary>, cur: &mut usize,
-                  dep: &mut Dependency) -> Option<Rc<Summary>> {
+// candidates. Resets `cx`, `remaining_deps` and `remaining_roots` to that level
+// and returns the next candidate. If all candidates have been exhausted,
+// returns None.
+fn find_candidate<'a>(backtrack_stack: &mut Vec<BacktrackFrame<'a>>,
+                      cx: &mut Context, remaining_deps: &mut Vec<DepsFrame>,
+                      remaining_roots: &mut Vec<(Rc<Summary>, Method<'a>)>,
+                      parent: &mut Rc<Summary>, cur: &mut usize,
+                      dep: &mut Dependency) -> Option<Rc<Summary>> {
     while let Some(mut frame) = backtrack_stack.pop() {
         if let Some((_, candidate)) = frame.remaining_candidates.next() {
             *cx = frame.context_backup.clone();
             *remaining_deps = frame.deps_backup.clone();
+            *remaining_roots = frame.roots_backup.clone();
             *parent = frame.parent.clone();
             *cur = remaining_deps.last().unwrap().remaining_siblings.cur_index();
             *dep = frame.dep.clone();
@@ -430,6 +551,10 @@ fn find_candidate(backtrack_stack: &mut Vec<BacktrackFrame>,
     return None
 }
 
+fn describe(id: &PackageId) -> String {
+    format!("{} v{}", id.name(), id.version())
+}
+
 #[allow(deprecated)] // connect => join in 1.3
 fn activation_error(cx: &Context,
                     registry: &mut Registry,
@@ -444,22 +569,22 @@ fn activation_error(cx: &Context,
                                previously selected versions of `{}`",
                               dep.name(), parent.name(),
                               dep.name());
-        'outer: for v in prev_active.iter() {
-            for node in cx.resolve.graph.iter() {
-                let edges = match cx.resolve.graph.edges(node) {
-                    Some(edges) => edges,
-                    None => continue,
-                };
-                for edge in edges {
-                    if edge != v.package_id() { continue }
-
-                    msg.push_str(&format!("\n  version {} in use by {}",
-                                          v.version(), edge));
-                    continue 'outer;
-                }
+        for v in prev_active.iter() {
+            msg.push_str(&format!("\n  version {} in use by:", v.version()));
+            let chain = cx.requirement_chain(v.package_id());
+            if chain.len() == 0 {
+                msg.push_str("\n    ??");
+            }
+            for line in chain.iter() {
+                msg.push_str(&format!("\n    {}", line));
             }
-            msg.push_str(&format!("\n  version {} in use by ??",
-                                  v.version()));
+        }
+
+        msg.push_str(&format!("\n  but `{}` requires `{} {}`",
+                              describe(parent.package_id()), dep.name(),
+                              dep.version_req()));
+        for line in cx.requirement_chain(parent.package_id()).iter() {
+            msg.push_str(&format!("\n    {}", line));
         }
 
         msg.push_str(&format!("\n  possible versions to select: {}",
@@ -553,7 +678,7 @@ fn build_features(s: &Summary, method: &Method)
                 try!(add_feature(s, key, &mut deps, &mut used, &mut visited));
             }
             for dep in s.dependencies().iter().filter(|d| d.is_optional()) {
-                try!(add_feature(s, dep.name(), &mut deps, &mut used,
+                try!(add_feature(s, dep.name_in_toml(), &mut deps, &mut used,
                                  &mut visited));
             }
         }
@@ -666,11 +791,21 @@ impl Context {
         // Next, transform all dependencies into a list of possible candidates
         // which can satisfy that dependency.
         let mut deps = try!(deps.into_iter().map(|(dep, features)| {
-            let mut candidates = try!(registry.query(&dep));
+            let candidates = try!(registry.query(&dep));
+            let mut candidates = try!(candidates.into_iter().map(|summary| {
+                self.replace(registry, summary)
+            }).collect::<CargoResult<Vec<_>>>());
             // When we attempt versions for a package, we'll want to start at
-            // the maximum version and work our way down.
+            // the maximum version and work our way down, unless we've been
+            // asked for minimal versions in which case it's the other way
+            // around.
+            let minimal = self.minimal_versions;
             candidates.sort_by(|a, b| {
-                b.version().cmp(a.version())
+                if minimal {
+                    a.version().cmp(b.version())
+                } else {
+                    b.version().cmp(a.version())
+                }
             });
             let candidates = candidates.into_iter().map(Rc::new).collect();
             Ok((dep, candidates, features))
@@ -687,6 +822,143 @@ impl Context {
         Ok(deps)
     }
 
+    // Swaps out `summary` for its replacement if one was configured via
+    // `[replace]`, otherwise returns it unchanged.
+    fn replace(&self, registry: &mut Registry, summary: Summary)
+               -> CargoResult<Summary> {
+        let id = summary.package_id().clone();
+        let (spec, dep) = match self.replacements.iter().find(|&&(ref spec, _)| {
+            spec.matches(&id)
+        }) {
+            Some(&(ref spec, ref dep)) => (spec, dep),
+            None => return Ok(summary),
+        };
+
+        let mut summaries = try!(registry.query(dep)).into_iter();
+        let replacement = match summaries.next() {
+            Some(s) => s,
+            None => {
+                return Err(human(format!("no matching package for override \
+                                          `{}` found\n\
+                                          location searched: {}\n\
+                                          version required: {}",
+                                         spec, dep.source_id(),
+                                         dep.version_req())))
+            }
+        };
+        if let Some(s) = summaries.next() {
+            return Err(human(format!("the replacement specification `{}` \
+                                      matched multiple packages:\n  \
+                                      * {}\n  * {}",
+                                     spec, replacement.package_id(),
+                                     s.package_id())))
+        }
+
+        // The replacement must be a drop-in for the original package, so make
+        // sure it's got the same name and version.
+        let replacement_id = replacement.package_id().clone();
+        if replacement_id.name() != id.name() ||
+           replacement_id.version() != id.version() {
+            return Err(human(format!("replacement specification `{}` \
+                                      matched {} and tried to override it \
+                                      with {}\n\
+                                      avoid matching unrelated packages by \
+                                      being more specific",
+                                     spec, id, replacement_id)))
+        }
+        debug!("replacing {} with {}", id, replacement_id);
+        Ok(replacement)
+    }
+
+    /// Walks the resolve graph from `id` back up towards the root, returning
+    /// a line for each package along the way describing which requirement
+    /// pulled the previous package into the graph.
+    fn requirement_chain(&self, id: &PackageId) -> Vec<String> {
+        let mut ret = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cur = id.clone();
+        while let Some(parent) = self.parent_of(&cur) {
+            if !seen.insert(parent.clone()) {
+                break
+            }
+            let req = self.summary(&parent).and_then(|s| {
+                s.dependencies().iter().find(|d| d.name() == cur.name())
+            }).map(|d| d.version_req().to_string());
+            ret.push(format!("`{}` requires `{} {}`", describe(&parent),
+                             cur.name(),
+                             req.unwrap_or_else(|| "*".to_string())));
+            cur = parent;
+        }
+        ret
+    }
+
+    /// Finds a path through the packages currently being visited which leads
+    /// from `id` back to itself, used to explain a dependency cycle.
+    fn cycle_path(&self, id: &PackageId) -> Vec<PackageId> {
+        let mut path = vec![id.clone()];
+        if self.find_cycle(id, id, &mut path, &mut HashSet::new()) {
+            path
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns whether `from` only depends on `to` as a dev-dependency.
+    fn is_dev_edge(&self, from: &PackageId, to: &PackageId) -> bool {
+        let summary = match self.summary(from) {
+            Some(summary) => summary,
+            None => return false,
+        };
+        let mut deps = summary.dependencies().iter().filter(|d| {
+            d.name() == to.name()
+        }).peekable();
+        deps.peek().is_some() && deps.all(|d| d.kind() == Kind::Development)
+    }
+
+    fn find_cycle(&self, target: &PackageId, cur: &PackageId,
+                  path: &mut Vec<PackageId>,
+                  seen: &mut HashSet<PackageId>) -> bool {
+        let edges = match self.resolve.graph.edges(cur) {
+            Some(edges) => edges,
+            None => return false,
+        };
+        for edge in edges {
+            if edge == target {
+                path.push(edge.clone());
+                return true
+            }
+            if !self.visited.contains(edge) || !seen.insert(edge.clone()) {
+                continue
+            }
+            path.push(edge.clone());
+            if self.find_cycle(target, edge, path, seen) {
+                return true
+            }
+            path.pop();
+        }
+        false
+    }
+
+    fn parent_of(&self, id: &PackageId) -> Option<PackageId> {
+        for node in self.resolve.graph.iter() {
+            let mut edges = match self.resolve.graph.edges(node) {
+                Some(edges) => edges,
+                None => continue,
+            };
+            if edges.any(|e| e == id) {
+                return Some(node.clone())
+            }
+        }
+        None
+    }
+
+    fn summary(&self, id: &PackageId) -> Option<&Rc<Summary>> {
+        let key = (id.name().to_string(), id.source_id().clone());
+        self.activations.get(&key).and_then(|v| {
+            v.iter().find(|s| s.package_id() == id)
+        })
+    }
+
     fn prev_active(&self, dep: &Dependency) -> &[Rc<Summary>] {
         let key = (dep.name().to_string(), dep.source_id().clone());
         self.activations.get(&key).map(|v| &v[..]).unwrap_or(&[])
@@ -712,10 +984,12 @@ impl Context {
         // requested features that correspond to optional dependencies
         for dep in deps {
             // weed out optional dependencies, but not those required
-            if dep.is_optional() && !feature_deps.contains_key(dep.name()) {
+            if dep.is_optional() &&
+               !feature_deps.contains_key(dep.name_in_toml()) {
                 continue
             }
-            let mut base = feature_deps.remove(dep.name()).unwrap_or(vec![]);
+            let mut base = feature_deps.remove(dep.name_in_toml())
+                                       .unwrap_or(vec![]);
             for feature in dep.features().iter() {
                 base.push(feature.clone());
                 if feature.contains("/") {
@@ -737,9 +1011,22 @@ impl Context {
                                       .collect::<Vec<&str>>();
             if unknown.len() > 0 {
                 let features = unknown.connect(", ");
-                return Err(human(format!("Package `{}` does not have these \
-                                          features: `{}`", parent.package_id(),
-                                         features)))
+                let mut msg = format!("Package `{}` does not have these \
+                                       features: `{}`", parent.package_id(),
+                                      features);
+                let optional = parent.dependencies().iter().filter(|d| {
+                    d.is_optional()
+                }).map(|d| d.name_in_toml());
+                let known = parent.features().keys().map(|s| &s[..])
+                                  .chain(optional).collect::<Vec<_>>();
+                for feature in unknown.iter() {
+                    let known = known.iter().map(|s| *s);
+                    if let Some(suggestion) = closest(feature, known, 2) {
+                        msg.push_str(&format!("\n`{}` may be a misspelling \
+                                               of `{}`", feature, suggestion));
+                    }
+                }
+                return Err(human(msg))
             }
         }
 
@@ -123,7 +123,7 @@ impl MultiShell {
     }
 
     pub fn set_color_config(&mut self, color: Option<&str>) -> CargoResult<()> {
-        self.out.set_color_config(match color {
+        let config = match color {
             Some("auto") => Auto,
             Some("always") => Always,
             Some("never") => Never,
@@ -133,7 +133,9 @@ impl MultiShell {
             Some(arg) => return Err(human(format!("argument for --color must be auto, always, or \
                                                    never, but found `{}`",
                                                   arg))),
-        });
+        };
+        self.out.set_color_config(config);
+        self.err.set_color_config(config);
         Ok(())
     }
 
@@ -230,7 +232,7 @@ impl Shell {
         }
     }
 
-    fn colored(&self) -> bool {
+    pub fn colored(&self) -> bool {
         self.config.tty && Auto == self.config.color_config
             || Always == self.config.color_config
     }
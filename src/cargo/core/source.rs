@@ -10,7 +10,7 @@ use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
 use url::Url;
 
 use core::{Summary, Package, PackageId, Registry, Dependency};
-use sources::{PathSource, GitSource, RegistrySource};
+use sources::{PathSource, GitSource, RegistrySource, DirectorySource};
 use sources::git;
 use util::{human, Config, CargoResult, ToUrl};
 
@@ -52,6 +52,11 @@ enum Kind {
     Path,
     /// represents the central registry
     Registry,
+    /// represents a local filesystem-based registry
+    LocalRegistry,
+    /// represents a directory of unpacked packages, such as `cargo vendor`
+    /// creates
+    Directory,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -130,6 +135,15 @@ impl SourceId {
                          .with_precise(Some("locked".to_string()))
             }
             "path" => SourceId::for_path(Path::new(&url[5..])).unwrap(),
+            "local-registry" => {
+                let url = url.to_url().unwrap();
+                SourceId::new(Kind::LocalRegistry, url)
+                         .with_precise(Some("locked".to_string()))
+            }
+            "directory" => {
+                let url = url.to_url().unwrap();
+                SourceId::new(Kind::Directory, url)
+            }
             _ => panic!("Unsupported serialized SourceId")
         }
     }
@@ -156,6 +170,12 @@ impl SourceId {
             SourceIdInner { kind: Kind::Registry, ref url, .. } => {
                 format!("registry+{}", url)
             }
+            SourceIdInner { kind: Kind::LocalRegistry, ref url, .. } => {
+                format!("local-registry+{}", url)
+            }
+            SourceIdInner { kind: Kind::Directory, ref url, .. } => {
+                format!("directory+{}", url)
+            }
         }
     }
 
@@ -173,6 +193,21 @@ impl SourceId {
         SourceId::new(Kind::Registry, url.clone())
     }
 
+    /// Returns the `SourceId` of a registry stored in the local directory
+    /// `path`, which contains an `index` directory alongside the `.crate`
+    /// files of the packages it lists.
+    pub fn for_local_registry(path: &Path) -> CargoResult<SourceId> {
+        let url = try!(path.to_url().map_err(human));
+        Ok(SourceId::new(Kind::LocalRegistry, url))
+    }
+
+    /// Returns the `SourceId` of the directory `path`, each subdirectory of
+    /// which is an unpacked package.
+    pub fn for_directory(path: &Path) -> CargoResult<SourceId> {
+        let url = try!(path.to_url().map_err(human));
+        Ok(SourceId::new(Kind::Directory, url))
+    }
+
     /// Returns the `SourceId` corresponding to the main repository.
     ///
     /// This is the main cargo registry by default, but it can be overridden in
@@ -181,9 +216,36 @@ impl SourceId {
         Ok(SourceId::for_registry(&try!(RegistrySource::url(config))))
     }
 
+    /// Returns the `SourceId` of the registry called `name`, whose index is
+    /// configured in the `[registries]` table of a `.cargo/config`. The main
+    /// repository is always available as `crates-io`.
+    pub fn for_alt_registry(name: &str, config: &Config)
+                            -> CargoResult<SourceId> {
+        if name == "crates-io" {
+            return SourceId::for_central(config)
+        }
+        let key = format!("registries.{}.index", name);
+        let index = match try!(config.get_string(&key)) {
+            Some((index, _)) => index,
+            None => {
+                return Err(human(format!("no index found for registry \
+                                          `{}`, it can be configured with \
+                                          `{}` in .cargo/config", name, key)))
+            }
+        };
+        let url = try!(index.to_url().map_err(human));
+        Ok(SourceId::for_registry(&url))
+    }
+
     pub fn url(&self) -> &Url { &self.inner.url }
     pub fn is_path(&self) -> bool { self.inner.kind == Kind::Path }
-    pub fn is_registry(&self) -> bool { self.inner.kind == Kind::Registry }
+    pub fn is_local_registry(&self) -> bool {
+        self.inner.kind == Kind::LocalRegistry
+    }
+
+    pub fn is_registry(&self) -> bool {
+        self.inner.kind == Kind::Registry || self.is_local_registry()
+    }
 
     pub fn is_git(&self) -> bool {
         match self.inner.kind {
@@ -205,6 +267,20 @@ impl SourceId {
                 Box::new(PathSource::new(&path, self, config))
             }
             Kind::Registry => Box::new(RegistrySource::new(self, config)),
+            Kind::LocalRegistry => {
+                let path = match self.inner.url.to_file_path() {
+                    Ok(p) => p,
+                    Err(()) => panic!("local registry sources cannot be remote"),
+                };
+                Box::new(RegistrySource::local(self, &path, config))
+            }
+            Kind::Directory => {
+                let path = match self.inner.url.to_file_path() {
+                    Ok(p) => p,
+                    Err(()) => panic!("directory sources cannot be remote"),
+                };
+                Box::new(DirectorySource::new(&path, self, config))
+            }
         }
     }
 
@@ -303,6 +379,12 @@ impl fmt::Display for SourceId {
             SourceIdInner { kind: Kind::Registry, ref url, .. } => {
                 write!(f, "registry {}", url)
             }
+            SourceIdInner { kind: Kind::LocalRegistry, ref url, .. } => {
+                write!(f, "local registry {}", url)
+            }
+            SourceIdInner { kind: Kind::Directory, ref url, .. } => {
+                write!(f, "directory source {}", url)
+            }
         }
     }
 }
@@ -15,6 +15,7 @@ pub struct Summary {
     package_id: PackageId,
     dependencies: Vec<Dependency>,
     features: HashMap<String, Vec<String>>,
+    checksum: Option<String>,
 }
 
 impl Summary {
@@ -22,14 +23,15 @@ impl Summary {
                dependencies: Vec<Dependency>,
                features: HashMap<String, Vec<String>>) -> CargoResult<Summary> {
         for dep in dependencies.iter() {
-            if features.get(dep.name()).is_some() {
+            if features.get(dep.name_in_toml()).is_some() {
                 return Err(human(format!("Features and dependencies cannot have \
-                                          the same name: `{}`", dep.name())))
+                                          the same name: `{}`",
+                                         dep.name_in_toml())))
             }
             if dep.is_optional() && !dep.is_transitive() {
                 return Err(human(format!("Dev-dependencies are not allowed \
                                           to be optional: `{}`",
-                                          dep.name())))
+                                          dep.name_in_toml())))
             }
         }
         for (feature, list) in features.iter() {
@@ -38,7 +40,7 @@ impl Summary {
                 let dep = parts.next().unwrap();
                 let is_reexport = parts.next().is_some();
                 if !is_reexport && features.get(dep).is_some() { continue }
-                match dependencies.iter().find(|d| d.name() == dep) {
+                match dependencies.iter().find(|d| d.name_in_toml() == dep) {
                     Some(d) => {
                         if d.is_optional() || is_reexport { continue }
                         return Err(human(format!("Feature `{}` depends on `{}` \
@@ -65,6 +67,7 @@ impl Summary {
             package_id: pkg_id,
             dependencies: dependencies,
             features: features,
+            checksum: None,
         })
     }
 
@@ -75,17 +78,41 @@ impl Summary {
     pub fn dependencies(&self) -> &[Dependency] { &self.dependencies }
     pub fn features(&self) -> &HashMap<String, Vec<String>> { &self.features }
 
+    /// The SHA-256 checksum of the `.crate` file this package is distributed
+    /// as, if it came from a registry.
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_ref().map(|s| &s[..])
+    }
+
     pub fn override_id(mut self, id: PackageId) -> Summary {
         self.package_id = id;
         self
     }
 
+    pub fn set_checksum(mut self, cksum: String) -> Summary {
+        self.checksum = Some(cksum);
+        self
+    }
+
     pub fn map_dependencies<F>(mut self, f: F) -> Summary
                                where F: FnMut(Dependency) -> Dependency {
         let deps = mem::replace(&mut self.dependencies, Vec::new());
         self.dependencies = deps.into_iter().map(f).collect();
         self
     }
+
+    /// Moves this summary, and any of its dependencies, from `to_replace` over
+    /// to `replace_with`.
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Summary {
+        let me = if self.package_id().source_id() == to_replace {
+            let new_id = self.package_id().with_source_id(replace_with);
+            self.override_id(new_id)
+        } else {
+            self
+        };
+        me.map_dependencies(|dep| dep.map_source(to_replace, replace_with))
+    }
 }
 
 impl PartialEq for Summary {
@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::slice;
+
+use core::{Package, Profiles, SourceId, VirtualManifest, WorkspaceConfig};
+use core::{EitherManifest, PackageIdSpec, Dependency};
+use ops;
+use util::{CargoResult, ChainError, Config, human, normalize_path};
+use util::toml::project_layout;
+
+/// The core abstraction in Cargo for working with a workspace of crates.
+///
+/// A workspace is often created very early on and then threaded through all
+/// other functions. It's typically through this object that the current
+/// package is loaded and/or learned about.
+///
+/// All members of a workspace share one `Cargo.lock` and one output directory,
+/// both located at the root of the workspace.
+pub struct Workspace<'cfg> {
+    config: &'cfg Config,
+
+    // The path to the manifest that this workspace was created from, and the
+    // package that is the target of the current command.
+    current_manifest: PathBuf,
+
+    // All manifests loaded so far, keyed by their path. This also contains
+    // manifests which were inspected while searching for the root of the
+    // workspace but which aren't members.
+    packages: HashMap<PathBuf, MaybePackage>,
+
+    // The path to the manifest of the workspace root, or `None` if the current
+    // package doesn't belong to a workspace and is just on its own.
+    root_manifest: Option<PathBuf>,
+
+    // The manifest paths of all members of this workspace. The root package
+    // (if it is a package) always comes first.
+    members: Vec<PathBuf>,
+
+    // A target directory overriding the one inferred from the workspace root
+    // and the configuration.
+    target_dir: Option<PathBuf>,
+
+    // Whether this workspace only exists for the duration of one command, in
+    // which case no `Cargo.lock` is written for it.
+    ephemeral: bool,
+}
+
+// A loaded `Cargo.toml`, which is either a package or a virtual manifest that
+// only configures a workspace.
+enum MaybePackage {
+    Package(Package),
+    Virtual(VirtualManifest),
+}
+
+/// Iterator over all the packages which are members of a workspace.
+pub struct Members<'a, 'cfg: 'a> {
+    ws: &'a Workspace<'cfg>,
+    iter: slice::Iter<'a, PathBuf>,
+}
+
+impl<'cfg> Workspace<'cfg> {
+    /// Creates a new workspace given the target manifest pointed to by
+    /// `manifest_path`.
+    ///
+    /// This function will construct the entire workspace by determining the
+    /// root and all member packages. It will then validate the workspace
+    /// before returning it, so `Ok` is only returned for valid workspaces.
+    pub fn new(manifest_path: &Path, config: &'cfg Config)
+               -> CargoResult<Workspace<'cfg>> {
+        let manifest_path = normalize_path(manifest_path);
+        let mut ws = Workspace {
+            config: config,
+            current_manifest: manifest_path.clone(),
+            packages: HashMap::new(),
+            root_manifest: None,
+            members: Vec::new(),
+            target_dir: None,
+            ephemeral: false,
+        };
+        ws.root_manifest = try!(ws.find_root(&manifest_path));
+        try!(ws.find_members());
+        try!(ws.validate());
+        Ok(ws)
+    }
+
+    /// Creates a "temporary workspace" from one package which only contains
+    /// that package.
+    ///
+    /// This constructor will not touch the filesystem and only creates an
+    /// in-memory workspace. That is, all configuration is ignored, it's just
+    /// intended for that one package. The workspace is ephemeral, so
+    /// resolving it doesn't write a `Cargo.lock` next to the package.
+    ///
+    /// This is currently only used in niche situations like `cargo package`
+    /// verifying the tarball it just created or `cargo install` building a
+    /// package in place.
+    pub fn one(package: Package, config: &'cfg Config,
+               target_dir: Option<PathBuf>) -> Workspace<'cfg> {
+        let manifest = package.manifest_path().to_path_buf();
+        let mut packages = HashMap::new();
+        packages.insert(manifest.clone(), MaybePackage::Package(package));
+        Workspace {
+            config: config,
+            current_manifest: manifest.clone(),
+            packages: packages,
+            root_manifest: None,
+            members: vec![manifest],
+            target_dir: target_dir,
+            ephemeral: true,
+        }
+    }
+
+    /// Returns the current package of this workspace, the one whose manifest
+    /// this workspace was created from.
+    ///
+    /// Returns an error if the workspace was created from a virtual manifest,
+    /// as there's no package to operate on then.
+    pub fn current(&self) -> CargoResult<&Package> {
+        match self.packages[&self.current_manifest] {
+            MaybePackage::Package(ref p) => Ok(p),
+            MaybePackage::Virtual(..) => {
+                Err(human(format!("manifest path `{}` is a virtual manifest, \
+                                   but this command requires running against \
+                                   an actual package in this workspace",
+                                  self.current_manifest.display())))
+            }
+        }
+    }
+
+    /// Prints the warnings collected while parsing the current package and,
+    /// if it's a virtual manifest, the root of the workspace, along with a
+    /// warning for each member other than the root with a `[replace]`
+    /// section.
+    pub fn emit_warnings(&self) -> CargoResult<()> {
+        let mut manifests: Vec<&Path> = vec![&self.current_manifest];
+        if let MaybePackage::Virtual(..) = self.packages[self.root_manifest()] {
+            if self.root_manifest() != &*self.current_manifest {
+                manifests.push(self.root_manifest());
+            }
+        }
+        for path in manifests {
+            let warnings = match self.packages[path] {
+                MaybePackage::Package(ref p) => p.manifest().warnings(),
+                MaybePackage::Virtual(ref m) => m.warnings(),
+            };
+            for warning in warnings {
+                try!(self.config.shell().warn(warning));
+            }
+        }
+
+        // Only the root's `[replace]` section is used, point out the ones
+        // which would otherwise be ignored without a word.
+        for pkg in self.members() {
+            if pkg.manifest_path() != self.root_manifest() &&
+               !pkg.manifest().replace().is_empty() {
+                try!(self.config.shell().warn(format!(
+                    "the `[replace]` section of `{}` is ignored as it's not \
+                     the root of its workspace, it belongs in `{}`",
+                    pkg.manifest_path().display(),
+                    self.root_manifest().display())));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the root path of this workspace.
+    ///
+    /// That is, this returns the path of the directory containing the
+    /// `Cargo.toml` which is the root of this workspace.
+    pub fn root(&self) -> &Path {
+        self.root_manifest().parent().unwrap()
+    }
+
+    /// Returns the path to the `Cargo.toml` which is the root of this
+    /// workspace.
+    pub fn root_manifest(&self) -> &Path {
+        self.root_manifest.as_ref().unwrap_or(&self.current_manifest)
+    }
+
+    /// Returns the profiles to compile with, which are always those of the
+    /// root manifest of the workspace.
+    pub fn profiles(&self) -> &Profiles {
+        match self.packages[self.root_manifest()] {
+            MaybePackage::Package(ref p) => p.manifest().profiles(),
+            MaybePackage::Virtual(ref m) => m.profiles(),
+        }
+    }
+
+    /// Returns the `[replace]` entries of the root manifest, which apply to
+    /// the whole workspace.
+    pub fn root_replace(&self) -> &[(PackageIdSpec, Dependency)] {
+        match self.packages[self.root_manifest()] {
+            MaybePackage::Package(ref p) => p.manifest().replace(),
+            MaybePackage::Virtual(ref m) => m.replace(),
+        }
+    }
+
+    /// Returns the directory where all build output of this workspace lands.
+    pub fn target_dir(&self) -> PathBuf {
+        self.target_dir.clone().or_else(|| {
+            self.config.target_dir()
+        }).unwrap_or_else(|| {
+            self.root().join("target")
+        })
+    }
+
+    /// Returns the path of the `Cargo.lock` shared by all members.
+    pub fn lock_path(&self) -> PathBuf {
+        self.root().join("Cargo.lock")
+    }
+
+    /// Returns whether this workspace was created with `Workspace::one`, in
+    /// which case its `Cargo.lock` is never written.
+    pub fn is_ephemeral(&self) -> bool {
+        self.ephemeral
+    }
+
+    /// Returns an iterator over all packages in this workspace.
+    pub fn members<'a>(&'a self) -> Members<'a, 'cfg> {
+        Members {
+            ws: self,
+            iter: self.members.iter(),
+        }
+    }
+
+    pub fn config(&self) -> &'cfg Config {
+        self.config
+    }
+
+    /// Finds the root of a workspace for the crate whose manifest is located
+    /// at `manifest_path`.
+    ///
+    /// This will parse the `Cargo.toml` at `manifest_path` and then interpret
+    /// the workspace configuration, optionally walking up the filesystem
+    /// looking for other workspace roots.
+    ///
+    /// Returns an error if `manifest_path` isn't actually a valid manifest or
+    /// if some other transient error happens.
+    fn find_root(&mut self, manifest_path: &Path)
+                 -> CargoResult<Option<PathBuf>> {
+        {
+            let current = try!(self.load(manifest_path));
+            match *current.workspace_config() {
+                WorkspaceConfig::Root { .. } => {
+                    debug!("find_root - is root {}", manifest_path.display());
+                    return Ok(Some(manifest_path.to_path_buf()))
+                }
+                WorkspaceConfig::Member { root: Some(ref path_to_root) } => {
+                    let path = manifest_path.parent().unwrap()
+                                            .join(path_to_root)
+                                            .join("Cargo.toml");
+                    debug!("find_root - pointer {}", path.display());
+                    return Ok(Some(normalize_path(&path)))
+                }
+                WorkspaceConfig::Member { root: None } => {}
+            }
+        }
+
+        let mut cur = manifest_path.parent().and_then(|p| p.parent());
+        while let Some(path) = cur {
+            let manifest = path.join("Cargo.toml");
+            debug!("find_root - trying {}", manifest.display());
+            if fs::metadata(&manifest).is_ok() {
+                let pkg = try!(self.load(&manifest));
+                match *pkg.workspace_config() {
+                    WorkspaceConfig::Root { .. } => {
+                        debug!("find_root - found");
+                        return Ok(Some(manifest))
+                    }
+                    WorkspaceConfig::Member { .. } => {}
+                }
+            }
+            cur = path.parent();
+        }
+
+        Ok(None)
+    }
+
+    /// After the root of a workspace has been located, probes for all members
+    /// of a workspace.
+    ///
+    /// The members are the root package itself, everything listed in the
+    /// `workspace.members` array, and all `path` dependencies of those which
+    /// live underneath the workspace root, transitively.
+    fn find_members(&mut self) -> CargoResult<()> {
+        let root_manifest = match self.root_manifest {
+            Some(ref path) => path.clone(),
+            None => {
+                debug!("find_members - only me as a member");
+                self.members.push(self.current_manifest.clone());
+                return Ok(())
+            }
+        };
+        let members = {
+            let root = try!(self.load(&root_manifest));
+            match *root.workspace_config() {
+                WorkspaceConfig::Root { ref members } => {
+                    members.clone().unwrap_or(Vec::new())
+                }
+                _ => return Err(human(format!("root of a workspace inferred \
+                                               but wasn't a root: {}",
+                                              root_manifest.display()))),
+            }
+        };
+
+        let root_dir = root_manifest.parent().unwrap().to_path_buf();
+        if let MaybePackage::Package(..) = self.packages[&root_manifest] {
+            try!(self.find_path_deps(&root_manifest, &root_dir));
+        }
+        for path in members {
+            let manifest_path = root_dir.join(path).join("Cargo.toml");
+            try!(self.find_path_deps(&manifest_path, &root_dir));
+        }
+        Ok(())
+    }
+
+    fn find_path_deps(&mut self, manifest_path: &Path, root: &Path)
+                      -> CargoResult<()> {
+        let manifest_path = normalize_path(manifest_path);
+        if self.members.iter().any(|p| *p == manifest_path) {
+            return Ok(())
+        }
+
+        debug!("find_members - {}", manifest_path.display());
+        self.members.push(manifest_path.clone());
+
+        let candidates = {
+            let pkg = try!(self.load(&manifest_path).chain_error(|| {
+                human(format!("failed to load workspace member `{}`",
+                              manifest_path.display()))
+            }));
+            let pkg = match *pkg {
+                MaybePackage::Package(ref p) => p,
+                MaybePackage::Virtual(..) => {
+                    return Err(human(format!("workspace member `{}` is a \
+                                              virtual manifest",
+                                             manifest_path.display())))
+                }
+            };
+            pkg.dependencies().iter().map(|d| d.source_id())
+               .filter(|d| d.is_path())
+               .filter_map(|d| d.url().to_file_path().ok())
+               .map(|p| p.join("Cargo.toml"))
+               .collect::<Vec<_>>()
+        };
+        for candidate in candidates {
+            if candidate.starts_with(root) {
+                try!(self.find_path_deps(&candidate, root));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates a workspace, ensuring that a number of invariants are upheld:
+    ///
+    /// 1. A workspace only has one root.
+    /// 2. All workspace members agree on this one root as the root.
+    /// 3. The current crate is a member of this workspace.
+    fn validate(&mut self) -> CargoResult<()> {
+        let root = match self.root_manifest {
+            Some(ref root) => root.clone(),
+            None => return Ok(()),
+        };
+
+        for member in self.members.clone() {
+            let member_root = try!(self.find_root(&member));
+            match member_root {
+                Some(ref member_root) if *member_root == root => {}
+                Some(ref member_root) => {
+                    return Err(human(format!("package `{}` is a member of the \
+                                              wrong workspace\n\
+                                              expected: {}\n\
+                                              actual:   {}",
+                                             member.display(),
+                                             root.display(),
+                                             member_root.display())))
+                }
+                None => {
+                    return Err(human(format!("workspace member `{}` is not \
+                                              hierarchically below the \
+                                              workspace root `{}`",
+                                             member.display(),
+                                             root.display())))
+                }
+            }
+        }
+
+        if self.current_manifest != root &&
+           !self.members.contains(&self.current_manifest) {
+            return Err(human(format!("current package believes it's in a \
+                                      workspace when it's not:\n\
+                                      current:   {}\n\
+                                      workspace: {}\n\n\
+                                      this may be fixable by adding `{}` to \
+                                      the `workspace.members` array of the \
+                                      manifest located at: {}",
+                                     self.current_manifest.display(),
+                                     root.display(),
+                                     self.current_manifest.parent().unwrap()
+                                         .display(),
+                                     root.display())))
+        }
+
+        Ok(())
+    }
+
+    fn load(&mut self, manifest_path: &Path) -> CargoResult<&MaybePackage> {
+        if !self.packages.contains_key(manifest_path) {
+            let package = try!(read(manifest_path, self.config));
+            self.packages.insert(manifest_path.to_path_buf(), package);
+        }
+        Ok(&self.packages[manifest_path])
+    }
+}
+
+fn read(manifest_path: &Path, config: &Config) -> CargoResult<MaybePackage> {
+    let root = manifest_path.parent().unwrap();
+    let source_id = try!(SourceId::for_path(root));
+    let mut data = Vec::new();
+    try!(try!(File::open(manifest_path)).read_to_end(&mut data));
+    let (manifest, _) = try!(ops::read_manifest(&data, project_layout(root),
+                                                &source_id, config));
+    Ok(match manifest {
+        EitherManifest::Real(manifest) => {
+            MaybePackage::Package(Package::new(manifest, manifest_path))
+        }
+        EitherManifest::Virtual(manifest) => MaybePackage::Virtual(manifest),
+    })
+}
+
+impl MaybePackage {
+    fn workspace_config(&self) -> &WorkspaceConfig {
+        match *self {
+            MaybePackage::Package(ref p) => p.manifest().workspace_config(),
+            MaybePackage::Virtual(ref m) => m.workspace_config(),
+        }
+    }
+}
+
+impl<'a, 'cfg> Iterator for Members<'a, 'cfg> {
+    type Item = &'a Package;
+
+    fn next(&mut self) -> Option<&'a Package> {
+        let ws = self.ws;
+        loop {
+            let next = self.iter.next().map(|path| &ws.packages[path]);
+            match next {
+                Some(&MaybePackage::Package(ref p)) => return Some(p),
+                Some(&MaybePackage::Virtual(..)) => {}
+                None => return None,
+            }
+        }
+    }
+}
@@ -5,6 +5,7 @@
 #[macro_use] extern crate log;
 extern crate crates_io as registry;
 extern crate curl;
+extern crate curl_sys;
 extern crate docopt;
 extern crate filetime;
 extern crate flate2;
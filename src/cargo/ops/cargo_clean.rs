@@ -3,33 +3,57 @@ use std::fs;
 use std::io::prelude::*;
 use std::path::Path;
 
-use core::{Package, PackageSet, Profiles, Profile};
+use core::{PackageSet, Profiles, Profile, Workspace};
 use core::source::{Source, SourceMap};
-use util::{CargoResult, human, ChainError, Config};
+use util::{CargoResult, human, ChainError, Config, FileLock};
 use ops::{self, Layout, Context, BuildConfig, Kind};
 
 pub struct CleanOptions<'a> {
     pub spec: Option<&'a str>,
     pub target: Option<&'a str>,
     pub config: &'a Config,
+    pub release: bool,
 }
 
 /// Cleans the project from build artifacts.
 pub fn clean(manifest_path: &Path, opts: &CleanOptions) -> CargoResult<()> {
-    let root = try!(Package::for_path(manifest_path, opts.config));
-    let target_dir = opts.config.target_dir(&root);
+    let ws = try!(Workspace::new(manifest_path, opts.config));
+    let target_dir = ws.target_dir();
+
+    let dest = if opts.release {"release"} else {"debug"};
+
+    // Builds in the directories about to be cleaned are waited for, and new
+    // ones kept out until we're done. Like builds, this only happens after
+    // any sources have been loaded, which take locks of their own, and the
+    // host directory is locked before the one of the target.
+    let lock = |dest: &str| -> CargoResult<Vec<FileLock>> {
+        let mut locks = vec![try!(Layout::new(&ws, None, dest)
+                                         .lock(opts.config))];
+        if let Some(target) = opts.target {
+            locks.push(try!(Layout::new(&ws, Some(target), dest)
+                                   .lock(opts.config)));
+        }
+        Ok(locks)
+    };
 
     // If we have a spec, then we need to delete some packages, otherwise, just
-    // remove the whole target directory and be done with it!
+    // remove the whole target directory (or only the release artifacts) and be
+    // done with it!
     let spec = match opts.spec {
         Some(spec) => spec,
-        None => return rm_rf(&target_dir),
+        None if opts.release => {
+            let _lock = try!(lock(dest));
+            return rm_rf(Layout::new(&ws, opts.target, dest).dest())
+        }
+        None => {
+            let _debug = try!(lock("debug"));
+            let _release = try!(lock("release"));
+            return rm_rf(&target_dir)
+        }
     };
 
     // Load the lockfile (if one's available), and resolve spec to a pkgid
-    let lockfile = root.root().join("Cargo.lock");
-    let source_id = root.package_id().source_id();
-    let resolve = match try!(ops::load_lockfile(&lockfile, source_id)) {
+    let resolve = match try!(ops::load_pkg_lockfile(&ws)) {
         Some(resolve) => resolve,
         None => return Err(human("A Cargo.lock must exist before cleaning"))
     };
@@ -41,6 +65,7 @@ pub fn clean(manifest_path: &Path, opts: &CleanOptions) -> CargoResult<()> {
         try!(source.update());
         (try!(source.get(&[pkgid.clone()]))).into_iter().next().unwrap()
     };
+    let _lock = try!(lock(dest));
 
     // Create a compilation context to have access to information like target
     // filenames and such
@@ -53,11 +78,14 @@ pub fn clean(manifest_path: &Path, opts: &CleanOptions) -> CargoResult<()> {
                                &profiles));
 
     // And finally, clean everything out!
+    let layout = Layout::new(&ws, opts.target, dest);
+    try!(rm_rf(&layout.fingerprint(&pkg)));
+    let profiles = if opts.release {
+        [Profile::default_release(), Profile::default_bench()]
+    } else {
+        [Profile::default_dev(), Profile::default_test()]
+    };
     for target in pkg.targets().iter() {
-        // TODO: `cargo clean --release`
-        let layout = Layout::new(opts.config, &root, opts.target, "debug");
-        try!(rm_rf(&layout.fingerprint(&pkg)));
-        let profiles = [Profile::default_dev(), Profile::default_test()];
         for profile in profiles.iter() {
             for filename in try!(cx.target_filenames(&pkg, target, profile,
                                                      Kind::Target)).iter() {
@@ -22,18 +22,21 @@
 //!       previously compiled dependency
 //!
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
+use std::env;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use semver::Version;
+
 use core::registry::PackageRegistry;
-use core::{Source, SourceId, PackageSet, Package, Target};
+use core::{Source, SourceId, PackageSet, Package, Target, Workspace};
 use core::{Profile, TargetKind};
 use core::resolver::Method;
 use ops::{self, BuildOutput, ExecEngine};
 use util::config::{ConfigValue, Config};
-use util::{CargoResult, internal, human, ChainError, profile};
+use util::{CargoError, CargoResult, internal, human, ChainError, profile};
 
 /// Contains information about how a package should be compiled.
 pub struct CompileOptions<'a> {
@@ -46,6 +49,8 @@ pub struct CompileOptions<'a> {
     pub features: &'a [String],
     /// Flag if the default feature should be built for the root package
     pub no_default_features: bool,
+    /// Flag if all features of the root package should be built
+    pub all_features: bool,
     /// Root package to build (if None it's the current one)
     pub spec: Option<&'a str>,
     /// Filter to apply to the root package to select which targets will be
@@ -60,18 +65,36 @@ pub struct CompileOptions<'a> {
     /// The specified target will be compiled with all the available arguments,
     /// note that this only accounts for the *final* invocation of rustc
     pub target_rustc_args: Option<&'a [String]>,
+    /// The specified target will be documented with all the available
+    /// arguments, note that this only accounts for the *final* invocation of
+    /// rustdoc
+    pub target_rustdoc_args: Option<&'a [String]>,
+    /// How compiler diagnostics and the outcome of the build are reported
+    pub message_format: MessageFormat,
 }
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum CompileMode {
     Test,
     Build,
+    Check,
     Bench,
     Doc { deps: bool },
 }
 
+/// The format of the output of a build, `Json` prints one JSON object per
+/// line on stdout for each compiler message, artifact and build script.
+#[derive(RustcDecodable, Clone, Copy, PartialEq, Debug)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
 pub enum CompileFilter<'a> {
     Everything,
+    /// Every target of the package: libraries, binaries, examples, tests and
+    /// benchmarks.
+    AllTargets,
     Only {
         lib: bool,
         bins: &'a [String],
@@ -86,29 +109,34 @@ pub fn compile<'a>(manifest_path: &Path,
                    -> CargoResult<ops::Compilation<'a>> {
     debug!("compile; manifest-path={}", manifest_path.display());
 
-    let package = try!(Package::for_path(manifest_path, options.config));
-    debug!("loaded package; package={}", package);
-
-    for key in package.manifest().warnings().iter() {
-        try!(options.config.shell().warn(key))
-    }
-    compile_pkg(&package, options)
+    let ws = try!(Workspace::new(manifest_path, options.config));
+    try!(ws.emit_warnings());
+    compile_ws(&ws, options)
 }
 
-pub fn compile_pkg<'a>(package: &Package,
-                       options: &CompileOptions<'a>)
-                       -> CargoResult<ops::Compilation<'a>> {
+/// Compiles the current package of the workspace `ws`.
+pub fn compile_ws<'a>(ws: &Workspace<'a>,
+                      options: &CompileOptions<'a>)
+                      -> CargoResult<ops::Compilation<'a>> {
+    let package = try!(ws.current());
     let CompileOptions { config, jobs, target, spec, features,
-                         no_default_features, release, mode,
+                         no_default_features, all_features, release, mode,
                          ref filter, ref exec_engine,
-                         ref target_rustc_args } = *options;
+                         ref target_rustc_args,
+                         ref target_rustdoc_args, message_format } = *options;
+
+    // Status messages would be mixed up with the JSON printed to stdout
+    if message_format == MessageFormat::Json {
+        try!(config.shell().set_verbosity(false, true));
+    }
 
     let target = target.map(|s| s.to_string());
-    let features = features.iter().flat_map(|s| {
+    let mut features = features.iter().flat_map(|s| {
         s.split(' ')
     }).map(|s| s.to_string()).collect::<Vec<String>>();
 
-    if spec.is_some() && (no_default_features || features.len() > 0) {
+    if spec.is_some() && (no_default_features || all_features ||
+                          features.len() > 0) {
         return Err(human("features cannot be modified when the main package \
                           is not being built"))
     }
@@ -116,6 +144,13 @@ pub fn compile_pkg<'a>(package: &Package,
         return Err(human("jobs must be at least 1"))
     }
 
+    if all_features {
+        features.extend(package.summary().features().keys().cloned());
+        features.extend(package.dependencies().iter().filter(|d| {
+            d.is_optional()
+        }).map(|d| d.name_in_toml().to_string()));
+    }
+
     let override_ids = try!(source_ids_from_config(config, package.root()));
 
     let (packages, resolve_with_overrides, sources) = {
@@ -123,7 +158,7 @@ pub fn compile_pkg<'a>(package: &Package,
 
         // First, resolve the package's *listed* dependencies, as well as
         // downloading and updating all remotes and such.
-        let resolve = try!(ops::resolve_pkg(&mut registry, package));
+        let resolve = try!(ops::resolve_ws(&mut registry, ws));
 
         // Second, resolve with precisely what we're doing. Filter out
         // transitive dependencies if necessary, specify features, handle
@@ -139,7 +174,7 @@ pub fn compile_pkg<'a>(package: &Package,
         };
 
         let resolved_with_overrides =
-                try!(ops::resolve_with_previous(&mut registry, package, method,
+                try!(ops::resolve_with_previous(&mut registry, ws, method,
                                                 Some(&resolve), None));
 
         let packages = try!(ops::get_resolved_packages(&resolved_with_overrides, &mut registry));
@@ -147,27 +182,36 @@ pub fn compile_pkg<'a>(package: &Package,
         (packages, resolved_with_overrides, registry.move_sources())
     };
 
+    try!(check_rust_version(&packages, config));
+
     let pkgid = match spec {
         Some(spec) => try!(resolve_with_overrides.query(spec)),
         None => package.package_id(),
     };
     let to_build = packages.iter().find(|p| p.package_id() == pkgid).unwrap();
     let targets = try!(generate_targets(to_build, mode, filter, release));
-
-    let target_with_args = match *target_rustc_args {
-        Some(args) if targets.len() == 1 => {
+    let targets = try!(filter_required_features(targets, filter,
+                                                resolve_with_overrides
+                                                    .features(pkgid),
+                                                config));
+
+    let target_with_args = match (*target_rustc_args, *target_rustdoc_args) {
+        (None, None) => None,
+        (rustc_args, rustdoc_args) if targets.len() == 1 => {
             let (target, profile) = targets[0];
             let mut profile = profile.clone();
-            profile.rustc_args = Some(args.to_vec());
+            profile.rustc_args = rustc_args.map(|a| a.to_vec());
+            profile.rustdoc_args = rustdoc_args.map(|a| a.to_vec());
             Some((target, profile))
         }
-        Some(_) => {
-            return Err(human("extra arguments to `rustc` can only be passed to \
-                              one target, consider filtering\nthe package by \
-                              passing e.g. `--lib` or `--bin NAME` to specify \
-                              a single target"))
+        (rustc_args, _) => {
+            let tool = if rustc_args.is_some() {"rustc"} else {"rustdoc"};
+            return Err(human(format!("extra arguments to `{}` can only be \
+                                      passed to one target, consider \
+                                      filtering\nthe package by passing e.g. \
+                                      `--lib` or `--bin NAME` to specify a \
+                                      single target", tool)))
         }
-        None => None,
     };
 
     let targets = target_with_args.as_ref().map(|&(t, ref p)| vec![(t, p)])
@@ -178,6 +222,7 @@ pub fn compile_pkg<'a>(package: &Package,
         let mut build_config = try!(scrape_build_config(config, jobs, target));
         build_config.exec_engine = exec_engine.clone();
         build_config.release = release;
+        build_config.json_messages = message_format == MessageFormat::Json;
         if let CompileMode::Doc { deps } = mode {
             build_config.doc_all = deps;
         }
@@ -186,21 +231,91 @@ pub fn compile_pkg<'a>(package: &Package,
                                   &PackageSet::new(&packages),
                                   &resolve_with_overrides,
                                   &sources,
-                                  config,
+                                  ws,
                                   build_config,
-                                  to_build.manifest().profiles()))
+                                  ws.profiles()))
     };
 
     return Ok(ret);
 }
 
+/// Removes the targets whose `required-features` aren't all enabled. Targets
+/// selected explicitly on the command line are an error instead.
+#[allow(deprecated)] // connect => join in 1.3
+fn filter_required_features<'a>(targets: Vec<(&'a Target, &'a Profile)>,
+                                filter: &CompileFilter,
+                                enabled: Option<&HashSet<String>>,
+                                config: &Config)
+                                -> CargoResult<Vec<(&'a Target, &'a Profile)>> {
+    let mut ret = Vec::new();
+    for (target, profile) in targets {
+        let missing = target.required_features().unwrap_or(&[]).iter().filter(|f| {
+            !enabled.map(|e| e.contains(*f)).unwrap_or(false)
+        }).map(|f| format!("`{}`", f)).collect::<Vec<_>>();
+        if missing.is_empty() || target.is_lib() {
+            ret.push((target, profile));
+            continue
+        }
+        match *filter {
+            CompileFilter::Everything | CompileFilter::AllTargets => {
+                try!(config.shell().warn(format!("note: skipping target `{}` \
+                                                  as it requires the \
+                                                  features: {}",
+                                                 target.name(),
+                                                 missing.connect(", "))));
+            }
+            CompileFilter::Only { .. } => {
+                return Err(human(format!("target `{}` requires the features: \
+                                          {}\nconsider enabling them by \
+                                          passing e.g. `--features`",
+                                         target.name(),
+                                         missing.connect(", "))))
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Makes sure that the active compiler is at least as new as the
+/// `rust-version` of every package being compiled.
+fn check_rust_version(packages: &[Package], config: &Config)
+                      -> CargoResult<()> {
+    let current = match config.rustc_info().version {
+        // Nightlies and betas of a release are good enough to build packages
+        // requiring it, so ignore the pre-release part.
+        Some(ref v) => Version {
+            major: v.major,
+            minor: v.minor,
+            patch: v.patch,
+            pre: Vec::new(),
+            build: Vec::new(),
+        },
+        None => return Ok(()),
+    };
+    for pkg in packages {
+        if let Some(required) = pkg.manifest().rust_version() {
+            if *required > current {
+                return Err(human(format!("package `{}` requires rustc {} or \
+                                          newer, while the currently active \
+                                          rustc version is {}",
+                                         pkg.package_id(), required,
+                                         current)))
+            }
+        }
+    }
+    Ok(())
+}
+
 impl<'a> CompileFilter<'a> {
     pub fn new(lib_only: bool,
                bins: &'a [String],
                tests: &'a [String],
                examples: &'a [String],
-               benches: &'a [String]) -> CompileFilter<'a> {
-        if lib_only || !bins.is_empty() || !tests.is_empty() ||
+               benches: &'a [String],
+               all_targets: bool) -> CompileFilter<'a> {
+        if all_targets {
+            CompileFilter::AllTargets
+        } else if lib_only || !bins.is_empty() || !tests.is_empty() ||
            !examples.is_empty() || !benches.is_empty() {
             CompileFilter::Only {
                 lib: lib_only, bins: bins, examples: examples, benches: benches,
@@ -214,6 +329,7 @@ impl<'a> CompileFilter<'a> {
     pub fn matches(&self, target: &Target) -> bool {
         match *self {
             CompileFilter::Everything => true,
+            CompileFilter::AllTargets => !target.is_custom_build(),
             CompileFilter::Only { lib, bins, examples, tests, benches } => {
                 let list = match *target.kind() {
                     TargetKind::Bin => bins,
@@ -239,12 +355,28 @@ fn generate_targets<'a>(pkg: &'a Package,
     let profiles = pkg.manifest().profiles();
     let build = if release {&profiles.release} else {&profiles.dev};
     let test = if release {&profiles.bench} else {&profiles.test};
+    let (check, check_test) = if release {
+        (&profiles.release_check, &profiles.release_check_test)
+    } else {
+        (&profiles.check, &profiles.check_test)
+    };
     let profile = match mode {
         CompileMode::Test => test,
         CompileMode::Bench => &profiles.bench,
         CompileMode::Build => build,
+        CompileMode::Check => check,
         CompileMode::Doc { .. } => &profiles.doc,
     };
+    // Profiles for the examples, tests and benches selected along with the
+    // other targets: `cargo check` only checks all of them, and `cargo bench`
+    // builds tests with the bench profile as well.
+    let (example, test, bench) = match mode {
+        CompileMode::Check => {
+            (profile, check_test, check_test)
+        }
+        CompileMode::Bench => (build, profile, profile),
+        _ => (build, test, &profiles.bench),
+    };
     return match *filter {
         CompileFilter::Everything => {
             match mode {
@@ -269,7 +401,7 @@ fn generate_targets<'a>(pkg: &'a Package,
                     }
                     Ok(base)
                 }
-                CompileMode::Build => {
+                CompileMode::Build | CompileMode::Check => {
                     Ok(pkg.targets().iter().filter(|t| {
                         t.is_bin() || t.is_lib()
                     }).map(|t| (t, profile)).collect())
@@ -280,6 +412,17 @@ fn generate_targets<'a>(pkg: &'a Package,
                 }
             }
         }
+        CompileFilter::AllTargets => {
+            Ok(pkg.targets().iter().filter_map(|t| {
+                match *t.kind() {
+                    TargetKind::Lib(..) | TargetKind::Bin => Some((t, profile)),
+                    TargetKind::Example => Some((t, example)),
+                    TargetKind::Test => Some((t, test)),
+                    TargetKind::Bench => Some((t, bench)),
+                    TargetKind::CustomBuild => None,
+                }
+            }).collect())
+        }
         CompileFilter::Only { lib, bins, examples, tests, benches } => {
             let mut targets = Vec::new();
 
@@ -299,9 +442,8 @@ fn generate_targets<'a>(pkg: &'a Package,
                         });
                         let t = match target {
                             Some(t) => t,
-                            None => return Err(human(format!("no {} target \
-                                                              named `{}`",
-                                                             desc, name))),
+                            None => return Err(missing_target(pkg, desc,
+                                                              &kind, name)),
                         };
                         debug!("found {} `{}`", desc, name);
                         targets.push((t, profile));
@@ -309,15 +451,31 @@ fn generate_targets<'a>(pkg: &'a Package,
                     Ok(())
                 };
                 try!(find(bins, "bin", TargetKind::Bin, profile));
-                try!(find(examples, "example", TargetKind::Example, build));
+                try!(find(examples, "example", TargetKind::Example, example));
                 try!(find(tests, "test", TargetKind::Test, test));
-                try!(find(benches, "bench", TargetKind::Bench, &profiles.bench));
+                try!(find(benches, "bench", TargetKind::Bench, bench));
             }
             Ok(targets)
         }
     };
 }
 
+/// The error for a target named on the command line which doesn't exist,
+/// listing the targets of that kind which do.
+fn missing_target(pkg: &Package, desc: &str, kind: &TargetKind, name: &str)
+                  -> Box<CargoError> {
+    let mut msg = format!("no {} target named `{}`", desc, name);
+    let available = pkg.targets().iter().filter(|t| t.kind() == kind)
+                       .map(|t| t.name()).collect::<Vec<_>>();
+    if !available.is_empty() {
+        msg.push_str(&format!("\n\navailable {} targets:", desc));
+        for name in available {
+            msg.push_str(&format!("\n    {}", name));
+        }
+    }
+    human(msg)
+}
+
 /// Read the `paths` configuration variable to discover all path overrides that
 /// have been configured.
 fn source_ids_from_config(config: &Config, cur_path: &Path)
@@ -371,9 +529,14 @@ fn scrape_build_config(config: &Config,
         None => None,
     };
     let jobs = jobs.or(cfg_jobs).unwrap_or(::num_cpus::get() as u32);
+    let artifact_cache = match try!(config.get_bool("build.artifact-cache")) {
+        Some((true, _)) => Some(config.home().join("artifacts")),
+        _ => None,
+    };
     let mut base = ops::BuildConfig {
         jobs: jobs,
         requested_target: target.clone(),
+        artifact_cache: artifact_cache,
         ..Default::default()
     };
     base.host = try!(scrape_target_config(config, &config.rustc_info().host));
@@ -384,6 +547,26 @@ fn scrape_build_config(config: &Config,
     Ok(base)
 }
 
+/// Extra flags for rustc come from the `RUSTFLAGS` environment variable if
+/// it's set, otherwise from `rustflags` in the target's configuration
+/// (`target_key`) or from `build.rustflags`, in that order.
+fn scrape_rustflags(config: &Config, target_key: &str)
+                    -> CargoResult<Vec<String>> {
+    if let Ok(flags) = env::var("RUSTFLAGS") {
+        return Ok(flags.split(' ').map(|s| s.trim()).filter(|s| {
+            !s.is_empty()
+        }).map(|s| s.to_string()).collect())
+    }
+    let keys = [format!("{}.rustflags", target_key),
+                "build.rustflags".to_string()];
+    for key in keys.iter() {
+        if let Some((flags, _)) = try!(config.get_list(key)) {
+            return Ok(flags.into_iter().map(|(flag, _)| flag).collect())
+        }
+    }
+    Ok(Vec::new())
+}
+
 fn scrape_target_config(config: &Config, triple: &str)
                         -> CargoResult<ops::TargetConfig> {
 
@@ -391,6 +574,7 @@ fn scrape_target_config(config: &Config, triple: &str)
     let mut ret = ops::TargetConfig {
         ar: try!(config.get_path(&format!("{}.ar", key))),
         linker: try!(config.get_path(&format!("{}.linker", key))),
+        rustflags: try!(scrape_rustflags(config, &key)),
         overrides: HashMap::new(),
     };
     let table = match try!(config.get_table(&key)) {
@@ -398,7 +582,9 @@ fn scrape_target_config(config: &Config, triple: &str)
         None => return Ok(ret),
     };
     for (lib_name, _) in table.into_iter() {
-        if lib_name == "ar" || lib_name == "linker" { continue }
+        if lib_name == "ar" || lib_name == "linker" || lib_name == "rustflags" {
+            continue
+        }
 
         let mut output = BuildOutput {
             library_paths: Vec::new(),
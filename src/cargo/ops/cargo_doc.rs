@@ -3,7 +3,7 @@ use std::fs;
 use std::path::Path;
 use std::process::Command;
 
-use core::{Package, PackageIdSpec};
+use core::{PackageIdSpec, Workspace};
 use ops;
 use util::{CargoResult, human};
 
@@ -14,7 +14,8 @@ pub struct DocOptions<'a> {
 
 pub fn doc(manifest_path: &Path,
            options: &DocOptions) -> CargoResult<()> {
-    let package = try!(Package::for_path(manifest_path, options.compile_opts.config));
+    let ws = try!(Workspace::new(manifest_path, options.compile_opts.config));
+    let package = try!(ws.current());
 
     let mut lib_names = HashSet::new();
     let mut bin_names = HashSet::new();
@@ -49,8 +50,7 @@ pub fn doc(manifest_path: &Path,
             }
         };
 
-        let target_dir = options.compile_opts.config.target_dir(&package);
-        let path = target_dir.join("doc").join(&name).join("index.html");
+        let path = ws.target_dir().join("doc").join(&name).join("index.html");
         if fs::metadata(&path).is_ok() {
             open_docs(&path);
         }
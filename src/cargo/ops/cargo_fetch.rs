@@ -1,15 +1,15 @@
 use std::path::Path;
 
 use core::registry::PackageRegistry;
-use core::{Package, PackageId, Resolve};
+use core::{Package, PackageId, Resolve, Workspace};
 use ops;
 use util::{CargoResult, Config, human, ChainError};
 
 /// Executes `cargo fetch`.
 pub fn fetch(manifest_path: &Path, config: &Config) -> CargoResult<()> {
-    let package = try!(Package::for_path(manifest_path, config));
+    let ws = try!(Workspace::new(manifest_path, config));
     let mut registry = PackageRegistry::new(config);
-    let resolve = try!(ops::resolve_pkg(&mut registry, &package));
+    let resolve = try!(ops::resolve_ws(&mut registry, &ws));
     let _ = try!(get_resolved_packages(&resolve, &mut registry));
     Ok(())
 }
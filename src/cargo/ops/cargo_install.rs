@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use toml;
+
+use core::{Dependency, Package, PackageId, PackageIdSpec, Registry, Source};
+use core::{SourceId, Workspace};
+use ops;
+use sources::{PathSource, SourceConfigMap};
+use util::{CargoResult, ChainError, Config, human, internal};
+use util::toml as cargo_toml;
+
+/// Record of what `cargo install` put into an installation root, stored as
+/// `.crates.toml` in that root:
+///
+/// ```toml
+/// [v1]
+/// "foo 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)" = ["foo"]
+/// ```
+#[derive(RustcDecodable, RustcEncodable)]
+struct CrateListing {
+    v1: BTreeMap<PackageId, BTreeSet<String>>,
+}
+
+/// Builds the package `krate` (or the package at `source_id` itself, if it's
+/// a path source) in release mode and copies its binaries into the `bin`
+/// directory of the installation root.
+pub fn install(root: Option<&str>,
+               krate: Option<&str>,
+               source_id: &SourceId,
+               vers: Option<&str>,
+               opts: &ops::CompileOptions,
+               force: bool) -> CargoResult<()> {
+    let config = opts.config;
+    let root = try!(resolve_root(root, config));
+    let pkg = if source_id.is_path() {
+        let path = source_id.url().to_file_path().unwrap();
+        let mut src = PathSource::new(&path, source_id, config);
+        try!(src.root_package())
+    } else {
+        let map = try!(SourceConfigMap::new(config));
+        let mut src = try!(map.load(source_id));
+        try!(select_pkg(&mut *src, source_id, krate, vers))
+    };
+
+    if !pkg.targets().iter().any(|t| t.is_bin()) {
+        return Err(human(format!("specified package `{}` has no binaries",
+                                 pkg.package_id())))
+    }
+
+    let dst = root.join("bin");
+    let mut list = try!(read_crate_list(&root));
+    if !force {
+        try!(check_overwrites(&dst, &pkg, &list));
+    }
+
+    try!(config.shell().status("Installing", pkg.package_id()));
+
+    // Packages from elsewhere are built in a scratch directory which is
+    // removed afterwards, path packages use their usual target directory.
+    let scratch = if source_id.is_path() {
+        None
+    } else {
+        Some(root.join(".install-target"))
+    };
+    let ws = Workspace::one(pkg.clone(), config, scratch.clone());
+    let compile = try!(ops::compile_ws(&ws, opts).chain_error(|| {
+        human(format!("failed to compile `{}`, intermediate artifacts can be \
+                       found at `{}`", pkg.package_id(),
+                      ws.target_dir().display()))
+    }));
+
+    try!(fs::create_dir_all(&dst));
+    let mut installed = BTreeSet::new();
+    for bin in compile.binaries.iter() {
+        let name = bin.file_name().unwrap();
+        let target = dst.join(name);
+        try!(config.shell().status("Installing", target.display()));
+        try!(fs::copy(bin, &target).chain_error(|| {
+            human(format!("failed to copy `{}` to `{}`", bin.display(),
+                          target.display()))
+        }));
+        installed.insert(name.to_str().unwrap().to_string());
+    }
+
+    // Binaries which were just overwritten no longer belong to whichever
+    // package installed them, and a previous install of this very package
+    // is superseded entirely.
+    for (id, bins) in list.v1.iter_mut() {
+        if id.name() == pkg.name() &&
+           id.source_id() == pkg.package_id().source_id() {
+            bins.clear();
+        }
+        for bin in installed.iter() {
+            bins.remove(bin);
+        }
+    }
+    list.v1 = list.v1.into_iter().filter(|&(_, ref bins)| {
+        !bins.is_empty()
+    }).collect();
+    list.v1.insert(pkg.package_id().clone(), installed);
+    try!(write_crate_list(&root, list));
+
+    if let Some(scratch) = scratch {
+        let _ = fs::remove_dir_all(&scratch);
+    }
+    Ok(())
+}
+
+/// Prints every package installed into the installation root along with the
+/// binaries it provides.
+pub fn install_list(root: Option<&str>, config: &Config) -> CargoResult<()> {
+    let root = try!(resolve_root(root, config));
+    let list = try!(read_crate_list(&root));
+    for (id, bins) in list.v1.iter() {
+        println!("{}:", id);
+        for bin in bins.iter() {
+            println!("    {}", bin);
+        }
+    }
+    Ok(())
+}
+
+/// Removes the binaries of the installed package matching `spec`, or only
+/// those named in `bins` if any are given.
+pub fn uninstall(root: Option<&str>,
+                 spec: &str,
+                 bins: &[String],
+                 config: &Config) -> CargoResult<()> {
+    let root = try!(resolve_root(root, config));
+    let mut list = try!(read_crate_list(&root));
+    let pkgid = {
+        let spec = try!(PackageIdSpec::parse(spec));
+        let mut ids = list.v1.keys().filter(|id| spec.matches(id));
+        let pkgid = match ids.next() {
+            Some(id) => id.clone(),
+            None => {
+                return Err(human(format!("package id specification `{}` \
+                                          matched no packages", spec)))
+            }
+        };
+        if let Some(other) = ids.next() {
+            return Err(human(format!("package id specification `{}` is \
+                                      ambiguous, it matches both `{}` and \
+                                      `{}`", spec, pkgid, other)))
+        }
+        pkgid
+    };
+
+    let dst = root.join("bin");
+    let to_remove = {
+        let installed = list.v1.get_mut(&pkgid).unwrap();
+        let to_remove = if bins.is_empty() {
+            installed.iter().cloned().collect::<Vec<_>>()
+        } else {
+            let mut to_remove = Vec::new();
+            for bin in bins.iter() {
+                let name = if bin.ends_with(env::consts::EXE_SUFFIX) {
+                    bin.clone()
+                } else {
+                    format!("{}{}", bin, env::consts::EXE_SUFFIX)
+                };
+                if !installed.contains(&name) {
+                    return Err(human(format!("binary `{}` is not installed \
+                                              as part of `{}`", name, pkgid)))
+                }
+                to_remove.push(name);
+            }
+            to_remove
+        };
+        for bin in to_remove.iter() {
+            installed.remove(bin);
+        }
+        to_remove
+    };
+    if list.v1[&pkgid].is_empty() {
+        list.v1.remove(&pkgid);
+    }
+    try!(write_crate_list(&root, list));
+
+    for bin in to_remove.iter() {
+        let path = dst.join(bin);
+        try!(config.shell().status("Removing", path.display()));
+        try!(fs::remove_file(&path).or_else(|e| {
+            // Already being gone is as good as having been removed
+            if fs::metadata(&path).is_err() {Ok(())} else {Err(e)}
+        }).chain_error(|| {
+            human(format!("failed to remove `{}`", path.display()))
+        }));
+    }
+    Ok(())
+}
+
+fn select_pkg(source: &mut Source,
+              source_id: &SourceId,
+              name: Option<&str>,
+              vers: Option<&str>) -> CargoResult<Package> {
+    let name = match name {
+        Some(name) => name,
+        None => {
+            return Err(human(format!("must specify a crate to install from \
+                                      {}", source_id)))
+        }
+    };
+    try!(source.update());
+    let dep = try!(Dependency::parse(name, vers, source_id));
+    let summaries = try!(source.query(&dep));
+    let pkgid = match summaries.iter().map(|s| s.package_id()).max() {
+        Some(pkgid) => pkgid.clone(),
+        None => {
+            let vers = vers.map(|v| format!(" with version `{}`", v))
+                           .unwrap_or(String::new());
+            return Err(human(format!("could not find `{}` in {}{}", name,
+                                     source_id, vers)))
+        }
+    };
+    try!(source.download(&[pkgid.clone()]));
+    let mut pkgs = try!(source.get(&[pkgid.clone()]));
+    pkgs.pop().chain_error(|| {
+        internal(format!("failed to get `{}` after downloading it", pkgid))
+    })
+}
+
+fn check_overwrites(dst: &Path, pkg: &Package, list: &CrateListing)
+                    -> CargoResult<()> {
+    for target in pkg.targets().iter().filter(|t| t.is_bin()) {
+        let name = format!("{}{}", target.name(), env::consts::EXE_SUFFIX);
+        if fs::metadata(dst.join(&name)).is_err() {
+            continue
+        }
+        let owner = list.v1.iter().find(|&(_, bins)| bins.contains(&name));
+        let msg = match owner {
+            Some((id, _)) => {
+                format!("binary `{}` already exists in destination as part \
+                         of `{}`", name, id)
+            }
+            None => format!("binary `{}` already exists in destination", name),
+        };
+        return Err(human(msg + "\nAdd --force to overwrite"))
+    }
+    Ok(())
+}
+
+fn read_crate_list(root: &Path) -> CargoResult<CrateListing> {
+    let path = root.join(".crates.toml");
+    let mut contents = String::new();
+    match File::open(&path) {
+        Ok(mut f) => { try!(f.read_to_string(&mut contents)); }
+        Err(..) => return Ok(CrateListing { v1: BTreeMap::new() }),
+    }
+    (|| {
+        let table = try!(cargo_toml::parse(&contents, &path));
+        toml::decode(toml::Value::Table(table)).chain_error(|| {
+            human("invalid format of the listing of installed crates")
+        })
+    }).chain_error(|| {
+        human(format!("failed to parse crate metadata at `{}`", path.display()))
+    })
+}
+
+fn write_crate_list(root: &Path, listing: CrateListing) -> CargoResult<()> {
+    let path = root.join(".crates.toml");
+    (|| {
+        let toml = toml::encode(&listing);
+        try!(try!(File::create(&path)).write_all(toml.to_string().as_bytes()));
+        Ok(())
+    }).chain_error(|| {
+        human(format!("failed to write crate metadata at `{}`", path.display()))
+    })
+}
+
+/// The installation root is, in order of preference, the `--root` flag,
+/// `CARGO_INSTALL_ROOT`, the `install.root` configuration key and finally
+/// the Cargo home directory.
+fn resolve_root(flag: Option<&str>, config: &Config) -> CargoResult<PathBuf> {
+    if let Some(flag) = flag {
+        return Ok(config.cwd().join(flag))
+    }
+    if let Some(root) = env::var_os("CARGO_INSTALL_ROOT") {
+        return Ok(config.cwd().join(root))
+    }
+    Ok(try!(config.get_path("install.root")).unwrap_or_else(|| {
+        config.home().to_path_buf()
+    }))
+}
@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use core::registry::PackageRegistry;
+use core::resolver::Method;
+use core::{Package, PackageId, Resolve, Workspace};
+use ops;
+use util::{CargoResult, Config, human};
+
+const VERSION: u32 = 1;
+
+pub struct OutputMetadataOptions<'a> {
+    pub features: Vec<String>,
+    pub no_default_features: bool,
+    pub manifest_path: &'a Path,
+    pub no_deps: bool,
+    pub version: u32,
+}
+
+/// Everything `cargo metadata` knows about a workspace, serialized as the
+/// command's JSON output.
+#[derive(RustcEncodable)]
+pub struct ExportInfo {
+    packages: Vec<Package>,
+    workspace_members: Vec<PackageId>,
+    resolve: Option<MetadataResolve>,
+    version: u32,
+}
+
+#[derive(RustcEncodable)]
+struct MetadataResolve {
+    // The package `cargo metadata` was run in, `None` at a virtual root.
+    root: Option<PackageId>,
+    nodes: Vec<Node>,
+}
+
+#[derive(RustcEncodable)]
+struct Node {
+    id: PackageId,
+    dependencies: Vec<PackageId>,
+}
+
+/// Loads the workspace at `opt.manifest_path` and, unless `opt.no_deps` is
+/// set, resolves its dependency graph, returning a description of all the
+/// packages involved.
+///
+/// All members of the workspace are resolved with all of their features,
+/// unless features are selected explicitly, in which case only the current
+/// package (or every member at a virtual root) is resolved with those
+/// features.
+pub fn output_metadata(opt: OutputMetadataOptions, config: &Config)
+                       -> CargoResult<ExportInfo> {
+    if opt.version != VERSION {
+        return Err(human(format!("metadata version {} not supported, only \
+                                  {} is currently supported",
+                                 opt.version, VERSION)))
+    }
+
+    let ws = try!(Workspace::new(opt.manifest_path, config));
+    let members = ws.members().map(|p| p.package_id().clone()).collect();
+
+    if opt.no_deps {
+        return Ok(ExportInfo {
+            packages: ws.members().cloned().collect(),
+            workspace_members: members,
+            resolve: None,
+            version: VERSION,
+        })
+    }
+
+    let features = opt.features.iter().flat_map(|s| {
+        s.split(' ')
+    }).map(|s| s.to_string()).collect::<Vec<String>>();
+
+    let mut registry = PackageRegistry::new(config);
+    let resolve = try!(ops::resolve_ws(&mut registry, &ws));
+    let resolve = if !features.is_empty() || opt.no_default_features {
+        let method = Method::Required {
+            dev_deps: true,
+            features: &features,
+            uses_default_features: !opt.no_default_features,
+        };
+        try!(ops::resolve_with_previous(&mut registry, &ws, method,
+                                        Some(&resolve), None))
+    } else {
+        resolve
+    };
+    let packages = try!(ops::get_resolved_packages(&resolve, &mut registry));
+
+    Ok(ExportInfo {
+        packages: packages,
+        workspace_members: members,
+        resolve: Some(serialize_resolve(&ws, &resolve)),
+        version: VERSION,
+    })
+}
+
+fn serialize_resolve(ws: &Workspace, resolve: &Resolve) -> MetadataResolve {
+    let mut nodes = resolve.iter().map(|id| {
+        let mut dependencies = resolve.deps(id).into_iter().flat_map(|deps| {
+            deps
+        }).cloned().collect::<Vec<_>>();
+        dependencies.sort();
+        Node { id: id.clone(), dependencies: dependencies }
+    }).collect::<Vec<_>>();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    MetadataResolve {
+        root: ws.current().ok().map(|p| p.package_id().clone()),
+        nodes: nodes,
+    }
+}
@@ -1,16 +1,25 @@
+use std::collections::BTreeMap;
 use std::io::prelude::*;
 use std::fs::{self, File};
 use std::path::{self, Path, PathBuf};
 
-use tar::Archive;
+use tar::{Archive, Header};
 use flate2::{GzBuilder, Compression};
 use flate2::read::GzDecoder;
+use git2;
+use toml;
 
-use core::{SourceId, Package, PackageId};
+use core::{Dependency, SourceId, Package, Workspace};
+use core::dependency::Kind;
 use sources::PathSource;
 use util::{self, CargoResult, human, internal, ChainError, Config};
+use util::toml as cargo_toml;
 use ops;
 
+// Modification time given to every file in the tarball, so that packaging the
+// same sources always yields the same tarball.
+const ARCHIVE_MTIME: u64 = 1;
+
 struct Bomb { path: Option<PathBuf> }
 
 impl Drop for Bomb {
@@ -26,7 +35,8 @@ pub fn package(manifest_path: &Path,
                config: &Config,
                verify: bool,
                list: bool,
-               metadata: bool) -> CargoResult<Option<PathBuf>> {
+               metadata: bool,
+               allow_dirty: bool) -> CargoResult<Option<PathBuf>> {
     let mut src = try!(PathSource::for_path(manifest_path.parent().unwrap(),
                                             config));
     let pkg = try!(src.root_package());
@@ -34,10 +44,11 @@ pub fn package(manifest_path: &Path,
     if metadata {
         try!(check_metadata(&pkg, config));
     }
+    try!(check_files(&pkg));
 
     if list {
         let root = pkg.root();
-        let mut list: Vec<_> = try!(src.list_files(&pkg)).iter().map(|file| {
+        let mut list: Vec<_> = try!(list_files(&pkg, &src)).iter().map(|file| {
             util::without_prefix(&file, &root).unwrap().to_path_buf()
         }).collect();
         list.sort();
@@ -47,9 +58,13 @@ pub fn package(manifest_path: &Path,
         return Ok(None)
     }
 
+    if !allow_dirty {
+        try!(check_not_dirty(&pkg, &src));
+    }
+
+    let ws = try!(Workspace::new(manifest_path, config));
     let filename = format!("package/{}-{}.crate", pkg.name(), pkg.version());
-    let target_dir = config.target_dir(&pkg);
-    let dst = target_dir.join(&filename);
+    let dst = ws.target_dir().join(&filename);
     if fs::metadata(&dst).is_ok() { return Ok(Some(dst)) }
 
     let mut bomb = Bomb { path: Some(dst.clone()) };
@@ -102,6 +117,199 @@ fn check_metadata(pkg: &Package, config: &Config) -> CargoResult<()> {
     Ok(())
 }
 
+// check that the files referenced by the manifest's metadata exist, as they're
+// shipped along with the package.
+fn check_files(pkg: &Package) -> CargoResult<()> {
+    let md = pkg.manifest().metadata();
+    for (key, file) in vec![("readme", &md.readme),
+                            ("license-file", &md.license_file)] {
+        if let Some(ref file) = *file {
+            if fs::metadata(&pkg.root().join(file)).is_err() {
+                return Err(human(format!("the file `{}` specified by `{}` \
+                                          does not exist", file, key)))
+            }
+        }
+    }
+    Ok(())
+}
+
+// check that none of the files which are about to be packaged have changes
+// which aren't committed yet, as the package would then contain something
+// other than what's in version control.
+#[allow(deprecated)] // connect => join in 1.3
+fn check_not_dirty(pkg: &Package, src: &PathSource) -> CargoResult<()> {
+    let repo = match git2::Repository::discover(pkg.root()) {
+        Ok(repo) => repo,
+        Err(..) => return Ok(()),
+    };
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir.to_path_buf(),
+        None => return Ok(()),
+    };
+    let status = |path: &Path| {
+        util::without_prefix(path, &workdir).and_then(|relative| {
+            repo.status_file(relative).ok()
+        })
+    };
+
+    // Packages which aren't tracked by the repository they happen to be in,
+    // such as those in an ignored directory, are not checked.
+    match status(pkg.manifest_path()) {
+        Some(s) if !s.intersects(git2::STATUS_IGNORED | git2::STATUS_WT_NEW) => {}
+        _ => return Ok(()),
+    }
+
+    let root = pkg.root();
+    let mut dirty = try!(list_files(pkg, src)).into_iter().filter(|file| {
+        status(file).map(|s| s != git2::STATUS_CURRENT).unwrap_or(false)
+    }).map(|file| {
+        util::without_prefix(&file, &root).unwrap().display().to_string()
+    }).collect::<Vec<_>>();
+    if dirty.is_empty() {
+        return Ok(())
+    }
+    dirty.sort();
+    Err(human(format!("{} files in the working directory contain changes \
+                       that were not yet committed into git:\n\n{}\n\n\
+                       to proceed despite this, pass the `--allow-dirty` flag",
+                      dirty.len(), dirty.connect("\n"))))
+}
+
+// the files of the package to include in the tarball, which always contains
+// the readme and license file even if they're excluded.
+fn list_files(pkg: &Package, src: &PathSource) -> CargoResult<Vec<PathBuf>> {
+    let mut files = try!(src.list_files(pkg));
+    let md = pkg.manifest().metadata();
+    for file in md.readme.iter().chain(md.license_file.iter()) {
+        let path = util::normalize_path(&pkg.root().join(file));
+        if path.starts_with(pkg.root()) && !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+// The manifest shipped in the tarball is normalized, as whatever it refers to
+// outside of the package is gone once the package leaves this tree: the
+// workspace it's part of and the `[replace]` section go away, and the
+// dependencies are written out from what was parsed, so inherited fields
+// are filled in, paths are dropped and every dependency names its version
+// and, unless it comes from the default registry, the index it comes from.
+fn normalized_manifest(pkg: &Package, config: &Config) -> CargoResult<String> {
+    let path = pkg.manifest_path();
+    let mut contents = String::new();
+    try!(try!(File::open(path)).read_to_string(&mut contents));
+    let mut toml = try!(cargo_toml::parse(&contents, path));
+
+    toml.remove("workspace");
+    toml.remove("replace");
+    for key in ["package", "project"].iter() {
+        if let Some(&mut toml::Value::Table(ref mut project)) = toml.get_mut(*key) {
+            project.remove("workspace");
+        }
+    }
+
+    remove_dependencies(&mut toml);
+    let mut targets = match toml.remove("target") {
+        Some(toml::Value::Table(targets)) => targets,
+        _ => toml::Table::new(),
+    };
+    for target in targets.values_mut() {
+        if let toml::Value::Table(ref mut target) = *target {
+            remove_dependencies(target);
+        }
+    }
+
+    let central = try!(SourceId::for_central(config));
+    let mut tables = BTreeMap::new();
+    for dep in pkg.dependencies() {
+        let kind = match dep.kind() {
+            Kind::Normal => "dependencies",
+            Kind::Development => "dev-dependencies",
+            Kind::Build => "build-dependencies",
+        };
+        let platform = dep.platform().map(|p| p.to_string());
+        tables.entry((platform, kind)).or_insert(toml::Table::new())
+              .insert(dep.name_in_toml().to_string(),
+                      normalized_dependency(dep, &central));
+    }
+    for ((platform, kind), deps) in tables {
+        let deps = toml::Value::Table(deps);
+        match platform {
+            Some(platform) => {
+                let target = targets.entry(platform).or_insert_with(|| {
+                    toml::Value::Table(toml::Table::new())
+                });
+                if let toml::Value::Table(ref mut target) = *target {
+                    target.insert(kind.to_string(), deps);
+                }
+            }
+            None => { toml.insert(kind.to_string(), deps); }
+        }
+    }
+    let targets = targets.into_iter().filter(|&(_, ref target)| {
+        match *target {
+            toml::Value::Table(ref target) => !target.is_empty(),
+            _ => true,
+        }
+    }).collect::<toml::Table>();
+    if !targets.is_empty() {
+        toml.insert("target".to_string(), toml::Value::Table(targets));
+    }
+    Ok(toml::Value::Table(toml).to_string())
+}
+
+fn remove_dependencies(table: &mut toml::Table) {
+    let kinds = ["dependencies", "dev-dependencies", "dev_dependencies",
+                 "build-dependencies", "build_dependencies"];
+    for kind in kinds.iter() {
+        table.remove(*kind);
+    }
+}
+
+// Dependencies on anything but a registry are taken from the default
+// registry, like publishing does.
+fn normalized_dependency(dep: &Dependency, central: &SourceId) -> toml::Value {
+    let mut table = toml::Table::new();
+    let version = match dep.specified_req() {
+        Some(req) => req.to_string(),
+        None => dep.version_req().to_string(),
+    };
+    table.insert("version".to_string(), toml::Value::String(version));
+    if dep.rename().is_some() {
+        table.insert("package".to_string(),
+                     toml::Value::String(dep.name().to_string()));
+    }
+    if dep.source_id().is_registry() && dep.source_id() != central {
+        table.insert("registry-index".to_string(),
+                     toml::Value::String(dep.source_id().url().to_string()));
+    }
+    if !dep.features().is_empty() {
+        let features = dep.features().iter().map(|f| {
+            toml::Value::String(f.clone())
+        }).collect();
+        table.insert("features".to_string(), toml::Value::Array(features));
+    }
+    if dep.is_optional() {
+        table.insert("optional".to_string(), toml::Value::Boolean(true));
+    }
+    if !dep.uses_default_features() {
+        table.insert("default-features".to_string(),
+                     toml::Value::Boolean(false));
+    }
+    toml::Value::Table(table)
+}
+
+#[cfg(unix)]
+fn archive_mode(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::prelude::*;
+    if meta.permissions().mode() & 0o111 != 0 {0o755} else {0o644}
+}
+#[cfg(windows)]
+fn archive_mode(_meta: &fs::Metadata) -> u32 {
+    0o644
+}
+
 fn tar(pkg: &Package, src: &PathSource, config: &Config,
        dst: &Path) -> CargoResult<()> {
 
@@ -119,23 +327,39 @@ fn tar(pkg: &Package, src: &PathSource, config: &Config,
     let encoder = GzBuilder::new().filename(try!(util::path2bytes(filename)))
                                   .write(tmpfile, Compression::Best);
 
-    // Put all package files into a compressed archive
+    // Put all package files into a compressed archive. The files are added
+    // in a fixed order and without any details of the local file system,
+    // such as owners or modification times, so the result is reproducible.
     let ar = Archive::new(encoder);
     let root = pkg.root();
-    for file in try!(src.list_files(pkg)).iter() {
+    let mut files = try!(list_files(pkg, src));
+    files.sort();
+    for file in files.iter() {
         if &**file == dst { continue }
         let relative = util::without_prefix(&file, &root).unwrap();
         let relative = try!(relative.to_str().chain_error(|| {
             human(format!("non-utf8 path in source directory: {}",
                           relative.display()))
         }));
-        let mut file = try!(File::open(file));
         try!(config.shell().verbose(|shell| {
             shell.status("Archiving", &relative)
         }));
+        let contents = if &**file == pkg.manifest_path() {
+            try!(normalized_manifest(pkg, config)).into_bytes()
+        } else {
+            let mut contents = Vec::new();
+            try!(try!(File::open(file)).read_to_end(&mut contents));
+            contents
+        };
         let path = format!("{}-{}{}{}", pkg.name(), pkg.version(),
                            path::MAIN_SEPARATOR, relative);
-        try!(ar.append_file(&path, &mut file).chain_error(|| {
+        let mut header = Header::new();
+        try!(header.set_path(&path));
+        header.set_size(contents.len() as u64);
+        header.set_mode(archive_mode(&try!(fs::metadata(file))));
+        header.set_mtime(ARCHIVE_MTIME);
+        header.set_cksum();
+        try!(ar.append(&header, &mut &contents[..]).chain_error(|| {
             internal(format!("could not archive source file `{}`", relative))
         }));
     }
@@ -157,39 +381,27 @@ fn run_verify(config: &Config, pkg: &Package, tar: &Path)
     try!(archive.unpack(dst.parent().unwrap()));
     let manifest_path = dst.join("Cargo.toml");
 
-    // When packages are uploaded to the registry, all path dependencies are
-    // implicitly converted to registry-based dependencies, so we rewrite those
-    // dependencies here.
-    //
-    // We also make sure to point all paths at `dst` instead of the previous
-    // location that the package was originally read from. In locking the
-    // `SourceId` we're telling it that the corresponding `PathSource` will be
-    // considered updated and we won't actually read any packages.
-    let registry = try!(SourceId::for_central(config));
-    let precise = Some("locked".to_string());
-    let new_src = try!(SourceId::for_path(&dst)).with_precise(precise);
-    let new_pkgid = try!(PackageId::new(pkg.name(), pkg.version(), &new_src));
-    let new_summary = pkg.summary().clone().map_dependencies(|d| {
-        if !d.source_id().is_path() { return d }
-        d.clone_inner().set_source_id(registry.clone()).into_dependency()
-    });
-    let mut new_manifest = pkg.manifest().clone();
-    new_manifest.set_summary(new_summary.override_id(new_pkgid));
-    let new_pkg = Package::new(new_manifest, &manifest_path);
-
-    // Now that we've rewritten all our path dependencies, compile it!
-    try!(ops::compile_pkg(&new_pkg, &ops::CompileOptions {
+    // The package is built from the manifest in the tarball, which has all
+    // of its dependencies on registries. It's built on its own rather than as
+    // part of the workspace `dst` happens to be in.
+    let new_pkg = try!(Package::for_path(&manifest_path, config));
+
+    let ws = Workspace::one(new_pkg, config, None);
+    try!(ops::compile_ws(&ws, &ops::CompileOptions {
         config: config,
         jobs: None,
         target: None,
         features: &[],
         no_default_features: false,
+        all_features: false,
         spec: None,
         filter: ops::CompileFilter::Everything,
         exec_engine: None,
         release: false,
         mode: ops::CompileMode::Build,
         target_rustc_args: None,
+        target_rustdoc_args: None,
+        message_format: ops::MessageFormat::Human,
     }));
 
     Ok(())
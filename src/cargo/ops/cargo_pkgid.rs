@@ -1,24 +1,21 @@
 use std::path::Path;
 
 use ops;
-use core::{PackageIdSpec, Package};
+use core::{PackageIdSpec, Workspace};
 use util::{CargoResult, human, Config};
 
 pub fn pkgid(manifest_path: &Path,
              spec: Option<&str>,
              config: &Config) -> CargoResult<PackageIdSpec> {
-    let package = try!(Package::for_path(manifest_path, config));
-
-    let lockfile = package.root().join("Cargo.lock");
-    let source_id = package.package_id().source_id();
-    let resolve = match try!(ops::load_lockfile(&lockfile, source_id)) {
+    let ws = try!(Workspace::new(manifest_path, config));
+    let resolve = match try!(ops::load_pkg_lockfile(&ws)) {
         Some(resolve) => resolve,
         None => return Err(human("A Cargo.lock must exist for this command"))
     };
 
     let pkgid = match spec {
         Some(spec) => try!(resolve.query(spec)),
-        None => package.package_id(),
+        None => try!(ws.current()).package_id(),
     };
     Ok(PackageIdSpec::from_package_id(pkgid))
 }
@@ -4,14 +4,14 @@ use std::io::prelude::*;
 use std::io;
 use std::path::{Path, PathBuf};
 
-use core::{Package, Manifest, SourceId, PackageId};
+use core::{Package, SourceId, PackageId, EitherManifest};
 use util::{self, CargoResult, human, Config, ChainError};
 use util::important_paths::find_project_manifest_exact;
 use util::toml::{Layout, project_layout};
 
 pub fn read_manifest(contents: &[u8], layout: Layout, source_id: &SourceId,
                      config: &Config)
-                     -> CargoResult<(Manifest, Vec<PathBuf>)> {
+                     -> CargoResult<(EitherManifest, Vec<PathBuf>)> {
     let root = layout.root.clone();
     util::toml::to_manifest(contents, source_id, layout, config).chain_error(|| {
         human(format!("failed to parse manifest at `{}`",
@@ -29,6 +29,14 @@ pub fn read_package(path: &Path, source_id: &SourceId, config: &Config)
     let layout = project_layout(path.parent().unwrap());
     let (manifest, nested) =
         try!(read_manifest(&data, layout, source_id, config));
+    let manifest = match manifest {
+        EitherManifest::Real(manifest) => manifest,
+        EitherManifest::Virtual(..) => {
+            return Err(human(format!("found a virtual manifest at `{}` \
+                                      instead of a package manifest",
+                                     path.display())))
+        }
+    };
 
     Ok((Package::new(manifest, path), nested))
 }
@@ -110,9 +118,22 @@ fn read_nested_packages(path: &Path,
                         visited: &mut HashSet<PathBuf>) -> CargoResult<()> {
     if !visited.insert(path.to_path_buf()) { return Ok(()) }
 
-    let manifest = try!(find_project_manifest_exact(path, "Cargo.toml"));
+    let manifest_path = try!(find_project_manifest_exact(path, "Cargo.toml"));
+
+    let mut file = try!(File::open(&manifest_path));
+    let mut data = Vec::new();
+    try!(file.read_to_end(&mut data));
+    let layout = project_layout(path);
+    let (manifest, nested) =
+        try!(read_manifest(&data, layout, source_id, config));
 
-    let (pkg, nested) = try!(read_package(&manifest, source_id, config));
+    // Virtual manifests only configure a workspace, so there's no package to
+    // register here. The members below it are found by the directory walk.
+    let manifest = match manifest {
+        EitherManifest::Real(manifest) => manifest,
+        EitherManifest::Virtual(..) => return Ok(()),
+    };
+    let pkg = Package::new(manifest, &manifest_path);
     let pkg_id = pkg.package_id().clone();
     if !all_packages.contains_key(&pkg_id) {
         all_packages.insert(pkg_id, pkg);
@@ -10,9 +10,45 @@ pub fn run(manifest_path: &Path,
     let config = options.config;
     let root = try!(Package::for_path(manifest_path, config));
 
+    // Without any filter, run the `default-run` binary if there is one.
+    let default_bins = match (&options.filter, root.manifest().default_run()) {
+        (&CompileFilter::Everything, Some(name)) => Some(vec![name.to_string()]),
+        _ => None,
+    };
+    let default_options;
+    let options = match default_bins {
+        Some(ref bins) => {
+            default_options = ops::CompileOptions {
+                config: options.config,
+                jobs: options.jobs,
+                target: options.target,
+                features: options.features,
+                no_default_features: options.no_default_features,
+                all_features: options.all_features,
+                spec: options.spec,
+                filter: CompileFilter::Only {
+                    lib: false,
+                    bins: bins,
+                    examples: &[],
+                    tests: &[],
+                    benches: &[],
+                },
+                exec_engine: options.exec_engine.clone(),
+                release: options.release,
+                mode: options.mode,
+                target_rustc_args: options.target_rustc_args,
+                target_rustdoc_args: options.target_rustdoc_args,
+                message_format: options.message_format,
+            };
+            &default_options
+        }
+        None => options,
+    };
+
     let mut bins = root.manifest().targets().iter().filter(|a| {
         !a.is_lib() && !a.is_custom_build() && match options.filter {
             CompileFilter::Everything => a.is_bin(),
+            CompileFilter::AllTargets |
             CompileFilter::Only { .. } => options.filter.matches(a),
         }
     });
@@ -22,6 +58,7 @@ pub fn run(manifest_path: &Path,
                 return Err(human("a bin target must be available for \
                                   `cargo run`"))
             }
+            CompileFilter::AllTargets |
             CompileFilter::Only { .. } => {
                 // this will be verified in cargo_compile
             }
@@ -34,6 +71,7 @@ pub fn run(manifest_path: &Path,
                                   one executable; use the `--bin` option to \
                                   specify which one to run"))
             }
+            CompileFilter::AllTargets |
             CompileFilter::Only { .. } => {
                 return Err(human("`cargo run` can run at most one executable, \
                                   but multiple were specified"))
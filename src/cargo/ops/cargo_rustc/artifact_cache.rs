@@ -0,0 +1,152 @@
+//! A cache of compiled dependencies which is shared by all projects of a
+//! user, enabled with `build.artifact-cache` in a configuration file.
+//!
+//! Libraries from registries and git repositories never change once
+//! published, so their fingerprint is known before they're compiled and only
+//! depends on the package, its features, its dependencies, the profile and
+//! the compiler. After a library has been built its output files are stored
+//! in the cache under that fingerprint (and the platform it was built for),
+//! and any later build of another project needing exactly the same library
+//! picks the files up from there rather than invoking the compiler.
+//!
+//! Packages with a build script, or depending on one with a build script
+//! anywhere in their dependency graph, are never cached as what the script
+//! produces isn't tracked by Cargo.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use core::{Package, PackageId, Target, Profile};
+use util::{self, CargoResult};
+
+use super::Kind;
+use super::context::Context;
+use super::fingerprint;
+use super::job::Work;
+
+/// Wraps `work`, which compiles `target`, such that a cached copy of its
+/// outputs is used if available and the outputs are added to the cache
+/// otherwise. The work is returned as-is if the target can't be cached.
+pub fn prepare<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
+                         pkg: &'a Package,
+                         target: &'a Target,
+                         profile: &'a Profile,
+                         kind: Kind,
+                         work: Work) -> CargoResult<Work> {
+    let entry = match try!(entry(cx, pkg, target, profile, kind)) {
+        Some(entry) => entry,
+        None => return Ok(work),
+    };
+    let root = cx.out_dir(pkg, kind, target);
+    let filenames = try!(cx.target_filenames(pkg, target, profile, kind));
+
+    Ok(Work::new(move |desc_tx| {
+        if restore(&entry, &root, &filenames).is_ok() {
+            debug!("restored {:?} from {}", filenames, entry.display());
+            return Ok(())
+        }
+        try!(work.call(desc_tx));
+
+        // Failing to fill the cache only means that the next project needing
+        // this library will have to compile it too.
+        if let Err(e) = store(&root, &entry, &filenames) {
+            info!("failed to cache {:?} in {}: {}", filenames,
+                  entry.display(), e);
+        }
+        Ok(())
+    }))
+}
+
+/// Returns the directory of the cache holding the outputs of `target`, or
+/// `None` if it's not eligible to be cached.
+fn entry<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
+                   pkg: &'a Package,
+                   target: &'a Target,
+                   profile: &'a Profile,
+                   kind: Kind) -> CargoResult<Option<PathBuf>> {
+    let cache = match cx.build_config.artifact_cache {
+        Some(ref cache) => cache.clone(),
+        None => return Ok(None),
+    };
+    if pkg.package_id().source_id().is_path() || !target.is_lib() ||
+       profile.test || profile.doc || profile.rustc_args.is_some() ||
+       runs_build_script(cx, pkg, target, profile, kind, &mut HashSet::new()) {
+        return Ok(None)
+    }
+    let fingerprint = match try!(fingerprint::precalculated(cx, pkg, target,
+                                                            profile, kind)) {
+        Some(fingerprint) => fingerprint,
+        None => return Ok(None),
+    };
+    let triple = match kind {
+        Kind::Host => &cx.config.rustc_info().host[..],
+        Kind::Target => cx.target_triple(),
+    };
+    let key = util::short_hash(&(&fingerprint, pkg.package_id(), triple));
+    Ok(Some(cache.join(format!("{}-{}", pkg.name(), key))))
+}
+
+/// Returns whether `pkg` or any package it transitively depends on has a
+/// build script.
+fn runs_build_script<'a, 'cfg>(cx: &Context<'a, 'cfg>,
+                               pkg: &'a Package,
+                               target: &'a Target,
+                               profile: &'a Profile,
+                               kind: Kind,
+                               visited: &mut HashSet<&'a PackageId>) -> bool {
+    if !visited.insert(pkg.package_id()) {
+        return false
+    }
+    if pkg.has_custom_build() {
+        return true
+    }
+    cx.dep_targets(pkg, target, kind, profile).into_iter().any(|(p, t, pr)| {
+        let kind = match kind {
+            Kind::Host => Kind::Host,
+            Kind::Target if t.for_host() => Kind::Host,
+            Kind::Target => Kind::Target,
+        };
+        runs_build_script(cx, p, t, pr, kind, visited)
+    })
+}
+
+fn restore(entry: &Path, root: &Path, filenames: &[String])
+           -> CargoResult<()> {
+    try!(fs::metadata(entry));
+    for filename in filenames {
+        let dst = root.join(filename);
+        if fs::metadata(&dst).is_ok() {
+            try!(fs::remove_file(&dst));
+        }
+        try!(fs::copy(&entry.join(filename), &dst));
+    }
+    Ok(())
+}
+
+/// Entries are filled in a scratch directory first which is then renamed
+/// into place, so they are only ever seen complete. If another process got
+/// to store the same entry in the meantime its copy is kept.
+///
+/// Files are always copied rather than hard linked, in either direction, as
+/// anything writing to an output under `target/` in place would otherwise
+/// corrupt the cached copy for every other project.
+fn store(root: &Path, entry: &Path, filenames: &[String]) -> CargoResult<()> {
+    let scratch = entry.with_file_name(format!("{}.tmp-{}",
+        entry.file_name().unwrap().to_str().unwrap(), util::short_hash(&root)));
+    if fs::metadata(&scratch).is_ok() {
+        try!(fs::remove_dir_all(&scratch));
+    }
+    try!(fs::create_dir_all(&scratch));
+    let res = (|| {
+        for filename in filenames {
+            try!(fs::copy(&root.join(filename), &scratch.join(filename)));
+        }
+        try!(fs::rename(&scratch, entry));
+        Ok(())
+    })();
+    if res.is_err() {
+        let _ = fs::remove_dir_all(&scratch);
+    }
+    res
+}
@@ -91,6 +91,7 @@ impl<'cfg> Compilation<'cfg> {
     ///
     /// The package argument is also used to configure environment variables as
     /// well as the working directory of the child process.
+    #[allow(deprecated)] // connect => join in 1.3
     pub fn process(&self, cmd: CommandType, pkg: &Package)
                    -> CargoResult<CommandPrototype> {
         let mut search_path = util::dylib_path();
@@ -107,7 +108,10 @@ impl<'cfg> Compilation<'cfg> {
             cmd.env(k, v);
         }
 
+        let metadata = pkg.manifest().metadata();
         cmd.env("CARGO_MANIFEST_DIR", pkg.root())
+           .env("CARGO_PKG_NAME", &pkg.name())
+           .env("CARGO_PKG_AUTHORS", &metadata.authors.connect(":"))
            .env("CARGO_PKG_VERSION_MAJOR", &pkg.version().major.to_string())
            .env("CARGO_PKG_VERSION_MINOR", &pkg.version().minor.to_string())
            .env("CARGO_PKG_VERSION_PATCH", &pkg.version().patch.to_string())
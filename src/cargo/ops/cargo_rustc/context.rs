@@ -9,8 +9,9 @@ use regex::Regex;
 use core::{SourceMap, Package, PackageId, PackageSet, Resolve, Target, Profile};
 use core::{TargetKind, LibKind, Profiles, Metadata, Dependency};
 use core::dependency::Kind as DepKind;
-use util::{self, CargoResult, ChainError, internal, Config, profile};
+use util::{self, CargoResult, ChainError, internal, Config, profile, Cfg};
 use util::human;
+use util::jobserver::Client;
 
 use super::TargetConfig;
 use super::custom_build::BuildState;
@@ -39,15 +40,18 @@ pub struct Context<'a, 'cfg: 'a> {
     pub build_config: BuildConfig,
     pub build_scripts: HashMap<(&'a PackageId, &'a Target, &'a Profile, Kind),
                                Vec<&'a PackageId>>,
+    pub jobserver: Option<Arc<Client>>,
 
     host: Layout,
     target: Option<Layout>,
     target_triple: String,
     host_dylib: Option<(String, String)>,
     host_exe: String,
+    host_cfg: Option<Vec<Cfg>>,
     package_set: &'a PackageSet,
     target_dylib: Option<(String, String)>,
     target_exe: String,
+    target_cfg: Option<Vec<Cfg>>,
     requirements: HashMap<(&'a PackageId, &'a str), Platform>,
     profiles: &'a Profiles,
 }
@@ -64,12 +68,13 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                profiles: &'a Profiles) -> CargoResult<Context<'a, 'cfg>> {
         let target = build_config.requested_target.clone();
         let target = target.as_ref().map(|s| &s[..]);
-        let (target_dylib, target_exe) = try!(Context::filename_parts(target,
-                                                                      config));
-        let (host_dylib, host_exe) = if build_config.requested_target.is_none() {
-            (target_dylib.clone(), target_exe.clone())
+        let (target_dylib, target_exe, target_cfg) =
+                try!(Context::target_info(target, config));
+        let (host_dylib, host_exe, host_cfg) =
+                if build_config.requested_target.is_none() {
+            (target_dylib.clone(), target_exe.clone(), target_cfg.clone())
         } else {
-            try!(Context::filename_parts(None, config))
+            try!(Context::target_info(None, config))
         };
         let target_triple = target.unwrap_or_else(|| {
             &config.rustc_info().host[..]
@@ -77,6 +82,7 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         let engine = build_config.exec_engine.as_ref().cloned().unwrap_or({
             Arc::new(Box::new(ProcessEngine))
         });
+        let jobserver = try!(Client::for_build(build_config.jobs));
         Ok(Context {
             target_triple: target_triple,
             host: host,
@@ -89,6 +95,8 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             target_exe: target_exe,
             host_dylib: host_dylib,
             host_exe: host_exe,
+            host_cfg: host_cfg,
+            target_cfg: target_cfg,
             requirements: HashMap::new(),
             compilation: Compilation::new(root_pkg, config),
             build_state: Arc::new(BuildState::new(&build_config, deps)),
@@ -98,13 +106,19 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             profiles: profiles,
             compiled: HashSet::new(),
             build_scripts: HashMap::new(),
+            jobserver: jobserver,
         })
     }
 
     /// Run `rustc` to discover the dylib prefix/suffix for the target
-    /// specified as well as the exe suffix
-    fn filename_parts(target: Option<&str>, cfg: &Config)
-                      -> CargoResult<(Option<(String, String)>, String)> {
+    /// specified as well as the exe suffix and the set of `cfg` values the
+    /// target is compiled with.
+    ///
+    /// Older compilers do not understand `--print=cfg`, in which case the
+    /// returned set of `cfg` values is `None`.
+    pub fn target_info(target: Option<&str>, cfg: &Config)
+                   -> CargoResult<(Option<(String, String)>, String,
+                                   Option<Vec<Cfg>>)> {
         let mut process = try!(util::process(cfg.rustc()));
         process.arg("-")
                .arg("--crate-name").arg("_")
@@ -115,7 +129,18 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         if let Some(s) = target {
             process.arg("--target").arg(s);
         };
-        let output = try!(process.exec_with_output());
+
+        let mut with_cfg = process.clone();
+        with_cfg.arg("--print=cfg");
+
+        let mut has_cfg = true;
+        let output = try!(with_cfg.exec_with_output().or_else(|_| {
+            has_cfg = false;
+            process.exec_with_output()
+        }).chain_error(|| {
+            human(format!("failed to run `rustc` to learn about \
+                           target-specific information"))
+        }));
 
         let error = str::from_utf8(&output.stderr).unwrap();
         let output = str::from_utf8(&output.stdout).unwrap();
@@ -138,7 +163,14 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             lines.next().unwrap().trim()
                  .split('_').skip(1).next().unwrap().to_string()
         };
-        Ok((dylib, exe_suffix.to_string()))
+
+        let cfg = if has_cfg {
+            Some(try!(lines.map(|l| l.parse::<Cfg>())
+                           .collect::<CargoResult<Vec<_>>>()))
+        } else {
+            None
+        };
+        Ok((dylib, exe_suffix.to_string(), cfg))
     }
 
     /// Prepare this context, ensuring that all filesystem directories are in
@@ -267,7 +299,14 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
     pub fn target_metadata(&self, pkg: &Package, target: &Target,
                            profile: &Profile) -> Option<Metadata> {
         let metadata = target.metadata();
-        if target.is_lib() && profile.test {
+        if profile.check && !target.is_lib() {
+            // Checking emits `lib{name}.rmeta` whatever the kind of the
+            // target, so other targets mustn't clobber the library's.
+            let mut metadata = pkg.generate_metadata();
+            metadata.mix(&format!("check-{:?}-{}", target.kind(),
+                                  target.name()));
+            Some(metadata)
+        } else if target.is_lib() && profile.test {
             // Libs and their tests are built in parallel, so we need to make
             // sure that their metadata is different.
             metadata.map(|m| m.clone()).map(|mut m| {
@@ -313,6 +352,10 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
 
         let mut ret = Vec::new();
         match *target.kind() {
+            // Checking only emits the crate's metadata, whatever its kind
+            _ if profile.check => {
+                ret.push(format!("lib{}.rmeta", stem));
+            }
             TargetKind::Example | TargetKind::Bin | TargetKind::CustomBuild |
             TargetKind::Bench | TargetKind::Test => {
                 ret.push(format!("{}{}", stem, suffix));
@@ -374,14 +417,15 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                 // if the corresponding feature was activated
                 let activated = !d.is_optional() ||
                                 self.resolve.features(pkg.package_id()).map(|f| {
-                                    f.contains(d.name())
+                                    f.contains(d.name_in_toml())
                                 }).unwrap_or(false);
 
                 is_correct_dep && is_actual_dep && is_platform_same && activated
             })
         }).filter_map(|pkg| {
             pkg.targets().iter().find(|t| t.is_lib()).map(|t| {
-                (pkg, t, self.lib_profile(pkg.package_id()))
+                (pkg, t, self.lib_or_check_profile(pkg.package_id(), t,
+                                                   profile))
             })
         }).collect::<Vec<_>>();
 
@@ -399,7 +443,8 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         // it here and see if we need to push `(pkg, pkg_lib_target)`.
         if target.is_lib() { return ret }
         if let Some(t) = pkg.targets().iter().find(|t| t.linkable()) {
-            ret.push((pkg, t, self.lib_profile(pkg.package_id())));
+            ret.push((pkg, t, self.lib_or_check_profile(pkg.package_id(), t,
+                                                        profile)));
         }
 
         // Integration tests/benchmarks require binaries to be built
@@ -458,15 +503,15 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
     fn dep_platform_activated(&self, dep: &Dependency, kind: Kind) -> bool {
         // If this dependency is only available for certain platforms,
         // make sure we're only enabling it for that platform.
-        match (dep.only_for_platform(), kind) {
-            (Some(ref platform), Kind::Host) => {
-                *platform == self.config.rustc_info().host
-            },
-            (Some(ref platform), Kind::Target) => {
-                *platform == self.target_triple
-            },
-            (None, _) => true
-        }
+        let platform = match dep.platform() {
+            Some(p) => p,
+            None => return true,
+        };
+        let (name, info) = match kind {
+            Kind::Host => (&self.config.rustc_info().host, &self.host_cfg),
+            Kind::Target => (&self.target_triple, &self.target_cfg),
+        };
+        platform.matches(name, info.as_ref().map(|cfg| &cfg[..]))
     }
 
     /// Gets a package for the given package id.
@@ -486,6 +531,11 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         self.target_config(kind).ar.as_ref().map(|s| s.as_ref())
     }
 
+    /// Get the extra flags passed to rustc for a particular host or target
+    pub fn rustflags(&self, kind: Kind) -> &[String] {
+        &self.target_config(kind).rustflags
+    }
+
     /// Get the target configuration for a particular host or target
     fn target_config(&self, kind: Kind) -> &TargetConfig {
         match kind {
@@ -510,6 +560,22 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         }
     }
 
+    /// The profile of the library `target` when it's a dependency of a target
+    /// built with `profile`. Checking a target only needs the metadata of its
+    /// dependencies, except for those which have to actually run on the host.
+    pub fn lib_or_check_profile(&self, pkg: &PackageId, target: &Target,
+                                profile: &Profile) -> &'a Profile {
+        if profile.check && !target.for_host() {
+            if self.build_config.release {
+                &self.profiles.release_check
+            } else {
+                &self.profiles.check
+            }
+        } else {
+            self.lib_profile(pkg)
+        }
+    }
+
     pub fn build_script_profile(&self, _pkg: &PackageId) -> &'a Profile {
         // TODO: should build scripts always be built with a dev
         //       profile? How is this controlled at the CLI layer?
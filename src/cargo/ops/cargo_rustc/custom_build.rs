@@ -8,6 +8,7 @@ use std::sync::Mutex;
 use core::{Package, Target, PackageId, PackageSet, Profile};
 use util::{CargoResult, human, Human};
 use util::{internal, ChainError, profile};
+use util::machine_message;
 
 use super::job::Work;
 use super::{fingerprint, process, Kind, Context, Platform};
@@ -70,6 +71,9 @@ pub fn prepare(pkg: &Package, target: &Target, req: Platform,
      .env("OPT_LEVEL", &profile.opt_level.to_string())
      .env("PROFILE", if cx.build_config.release {"release"} else {"debug"})
      .env("HOST", &cx.config.rustc_info().host);
+    if let Some(ref jobserver) = cx.jobserver {
+        jobserver.configure(&mut p);
+    }
 
     // Be sure to pass along all enabled features for this package, this is the
     // last piece of statically known information that we have.
@@ -111,6 +115,7 @@ pub fn prepare(pkg: &Package, target: &Target, req: Platform,
     try!(fs::create_dir_all(&cx.layout(pkg, Kind::Host).build(pkg)));
 
     let exec_engine = cx.exec_engine.clone();
+    let json_messages = cx.build_config.json_messages;
 
     // Prepare the unit of "dirty work" which will actually run the custom build
     // command.
@@ -164,6 +169,9 @@ pub fn prepare(pkg: &Package, target: &Target, req: Platform,
             human("build script output was not valid utf-8")
         }));
         let parsed_output = try!(BuildOutput::parse(output, &pkg_name));
+        if json_messages {
+            emit_build_output(&id, &parsed_output);
+        }
         build_state.insert(id, req, parsed_output);
 
         try!(File::create(&build_output.parent().unwrap().join("output"))
@@ -200,6 +208,9 @@ pub fn prepare(pkg: &Package, target: &Target, req: Platform,
         let mut contents = String::new();
         try!(f.read_to_string(&mut contents));
         let output = try!(BuildOutput::parse(&contents, &pkg_name));
+        if json_messages {
+            emit_build_output(&id, &output);
+        }
         build_state.insert(id, req, output);
 
         fresh.call(tx)
@@ -208,6 +219,17 @@ pub fn prepare(pkg: &Package, target: &Target, req: Platform,
     Ok((dirty, fresh, freshness))
 }
 
+fn emit_build_output(id: &PackageId, output: &BuildOutput) {
+    machine_message::emit(&machine_message::BuildScript {
+        package_id: id,
+        linked_libs: &output.library_links,
+        linked_paths: output.library_paths.iter().map(|p| {
+            p.display().to_string()
+        }).collect(),
+        cfgs: &output.cfgs,
+    });
+}
+
 impl BuildState {
     pub fn new(config: &super::BuildConfig,
                packages: &PackageSet) -> BuildState {
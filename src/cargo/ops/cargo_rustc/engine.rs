@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
 use std::fmt;
+use std::io;
 use std::path::Path;
 use std::process::Output;
 
@@ -11,6 +12,9 @@ use util::Config;
 pub trait ExecEngine: Send + Sync {
     fn exec(&self, CommandPrototype) -> Result<(), ProcessError>;
     fn exec_with_output(&self, CommandPrototype) -> Result<Output, ProcessError>;
+    fn exec_with_streaming(&self, CommandPrototype,
+                           &mut FnMut(&str) -> io::Result<()>)
+                           -> Result<Output, ProcessError>;
 }
 
 /// Default implementation of `ExecEngine`.
@@ -26,6 +30,12 @@ impl ExecEngine for ProcessEngine {
                         -> Result<Output, ProcessError> {
         command.into_process_builder().exec_with_output()
     }
+
+    fn exec_with_streaming(&self, command: CommandPrototype,
+                           on_stderr_line: &mut FnMut(&str) -> io::Result<()>)
+                           -> Result<Output, ProcessError> {
+        command.into_process_builder().exec_with_streaming(on_stderr_line)
+    }
 }
 
 /// Prototype for a command that must be executed.
@@ -38,15 +48,20 @@ pub struct CommandPrototype {
 impl CommandPrototype {
     pub fn new(ty: CommandType, config: &Config)
                -> CargoResult<CommandPrototype> {
-        Ok(CommandPrototype {
-            builder: try!(match ty {
-                CommandType::Rustc => process(config.rustc()),
-                CommandType::Rustdoc => process(config.rustdoc()),
-                CommandType::Target(ref s) |
-                CommandType::Host(ref s) => process(s),
-            }),
-            ty: ty,
-        })
+        let builder = match ty {
+            CommandType::Rustc => match config.rustc_wrapper() {
+                Some(wrapper) => {
+                    let mut builder = try!(process(wrapper));
+                    builder.arg(config.rustc());
+                    builder
+                }
+                None => try!(process(config.rustc())),
+            },
+            CommandType::Rustdoc => try!(process(config.rustdoc())),
+            CommandType::Target(ref s) |
+            CommandType::Host(ref s) => try!(process(s)),
+        };
+        Ok(CommandPrototype { builder: builder, ty: ty })
     }
 
     pub fn get_type(&self) -> &CommandType { &self.ty }
@@ -130,6 +130,14 @@ impl FingerprintInner {
         *self.resolved.lock().unwrap() = Some(resolved.clone());
         Ok(resolved)
     }
+
+    fn is_precalculated(&self) -> bool {
+        let local = match self.local {
+            LocalFingerprint::Precalculated(..) => true,
+            LocalFingerprint::MtimeBased(..) => false,
+        };
+        local && self.deps.iter().all(|dep| dep.is_precalculated())
+    }
 }
 
 /// Calculates the fingerprint for a package/target pair.
@@ -166,7 +174,8 @@ fn calculate<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
         v
     });
     let extra = util::short_hash(&(&cx.config.rustc_info().verbose_version,
-                                   target, &features, profile));
+                                   target, &features, profile,
+                                   cx.rustflags(kind)));
     debug!("extra {:?} {:?} {:?} = {}", target, profile, features, extra);
 
     // Next, recursively calculate the fingerprint for all of our dependencies.
@@ -211,6 +220,21 @@ fn calculate<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
     Ok(fingerprint)
 }
 
+/// Returns the fingerprint of a target if it's known without looking at any
+/// files, which is the case if neither the target nor any of its dependencies
+/// come from a path source.
+pub fn precalculated<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
+                               pkg: &'a Package,
+                               target: &'a Target,
+                               profile: &'a Profile,
+                               kind: Kind) -> CargoResult<Option<String>> {
+    let fingerprint = try!(calculate(cx, pkg, target, profile, kind));
+    if fingerprint.is_precalculated() {
+        fingerprint.resolve(false).map(Some)
+    } else {
+        Ok(None)
+    }
+}
 
 // We want to use the mtime for files if we're a path source, but if we're a
 // git/registry source, then the mtime of files may fluctuate, but they won't
@@ -317,6 +341,15 @@ pub fn dep_info_loc(cx: &Context, pkg: &Package, target: &Target,
     dir(cx, pkg, kind).join(&format!("dep-{}", filename(target, profile)))
 }
 
+/// Returns the location where the compiler's diagnostics for a target are
+/// saved, either as JSON or as rendered for humans.
+pub fn output_loc(cx: &Context, pkg: &Package, target: &Target,
+                  profile: &Profile, kind: Kind, json: bool) -> PathBuf {
+    let format = if json {"json-"} else {""};
+    dir(cx, pkg, kind).join(&format!("output-{}{}", format,
+                                     filename(target, profile)))
+}
+
 fn is_fresh(loc: &Path, new_fingerprint: &Fingerprint) -> CargoResult<bool> {
     let mut file = match File::open(loc) {
         Ok(file) => file,
@@ -399,7 +432,9 @@ fn calculate_pkg_fingerprint(cx: &Context, pkg: &Package) -> CargoResult<String>
 
 fn filename(target: &Target, profile: &Profile) -> String {
     let kind = if target.is_lib() {"lib"} else {"bin"};
-    let flavor = if target.is_test() || profile.test {
+    let flavor = if profile.check {
+        if target.is_test() || profile.test {"check-test-"} else {"check-"}
+    } else if target.is_test() || profile.test {
         "test-"
     } else if profile.doc {
         "doc-"
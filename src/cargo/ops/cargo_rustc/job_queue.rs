@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use std::collections::hash_map::HashMap;
+use std::sync::Arc;
 use std::sync::mpsc::{channel, Sender, Receiver};
 
 use threadpool::ThreadPool;
@@ -8,6 +9,7 @@ use term::color::YELLOW;
 use core::{Package, PackageId, Resolve, PackageSet};
 use util::{Config, DependencyQueue, Fresh, Dirty, Freshness};
 use util::{CargoResult, Dependency, profile};
+use util::jobserver::Client;
 
 use super::job::Job;
 
@@ -28,6 +30,7 @@ pub struct JobQueue<'a> {
     pending: HashMap<(&'a PackageId, Stage), PendingBuild>,
     pkgids: HashSet<&'a PackageId>,
     printed: HashSet<&'a PackageId>,
+    jobserver: Option<Arc<Client>>,
 }
 
 /// A helper structure for metadata about the state of a building package.
@@ -63,8 +66,8 @@ pub enum Stage {
 type Message = (PackageId, Stage, Freshness, CargoResult<()>);
 
 impl<'a> JobQueue<'a> {
-    pub fn new(resolve: &'a Resolve, packages: &'a PackageSet, jobs: u32)
-               -> JobQueue<'a> {
+    pub fn new(resolve: &'a Resolve, packages: &'a PackageSet, jobs: u32,
+               jobserver: Option<Arc<Client>>) -> JobQueue<'a> {
         let (tx, rx) = channel();
         JobQueue {
             pool: ThreadPool::new(jobs as usize),
@@ -77,6 +80,7 @@ impl<'a> JobQueue<'a> {
             pending: HashMap::new(),
             pkgids: HashSet::new(),
             printed: HashSet::new(),
+            jobserver: jobserver,
         }
     }
 
@@ -173,8 +177,22 @@ impl<'a> JobQueue<'a> {
             let my_tx = self.tx.clone();
             let id = id.clone();
             let (desc_tx, desc_rx) = channel();
+            let jobserver = self.jobserver.clone();
             self.pool.execute(move|| {
-                my_tx.send((id, stage, fresh, job.run(fresh, desc_tx))).unwrap();
+                // Fresh jobs hardly do any work, so only dirty ones need to
+                // take a token from the jobserver for the time they run.
+                let token = match (fresh, jobserver) {
+                    (Dirty, Some(ref jobserver)) => {
+                        Some(Client::acquire(jobserver))
+                    }
+                    _ => None,
+                };
+                let res = match token {
+                    Some(Err(e)) => Err(e),
+                    Some(Ok(_token)) => job.run(fresh, desc_tx),
+                    None => job.run(fresh, desc_tx),
+                };
+                my_tx.send((id, stage, fresh, res)).unwrap();
             });
             // only the first message of each job is processed
             match desc_rx.recv() {
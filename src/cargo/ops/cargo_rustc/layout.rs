@@ -49,8 +49,8 @@ use std::fs;
 use std::io;
 use std::path::{PathBuf, Path};
 
-use core::Package;
-use util::Config;
+use core::{Package, Workspace};
+use util::{CargoResult, Config, FileLock};
 use util::hex::short_hash;
 
 pub struct Layout {
@@ -68,9 +68,8 @@ pub struct LayoutProxy<'a> {
 }
 
 impl Layout {
-    pub fn new(config: &Config, pkg: &Package, triple: Option<&str>,
-               dest: &str) -> Layout {
-        let mut path = config.target_dir(pkg);
+    pub fn new(ws: &Workspace, triple: Option<&str>, dest: &str) -> Layout {
+        let mut path = ws.target_dir();
         // Flexible target specifications often point at filenames, so interpret
         // the target triple as a Path and then just use the file stem as the
         // component for the directory name.
@@ -113,6 +112,13 @@ impl Layout {
         }
     }
 
+    /// Locks this build directory against other Cargo processes, for as
+    /// long as the returned lock is held.
+    pub fn lock(&self, config: &Config) -> CargoResult<FileLock> {
+        FileLock::exclusive(&self.root.join(".cargo-lock"), config,
+                            "build directory")
+    }
+
     pub fn dest<'a>(&'a self) -> &'a Path { &self.root }
     pub fn deps<'a>(&'a self) -> &'a Path { &self.deps }
     pub fn examples<'a>(&'a self) -> &'a Path { &self.examples }
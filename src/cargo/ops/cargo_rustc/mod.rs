@@ -1,15 +1,21 @@
 use std::collections::{HashSet, HashMap};
 use std::env;
 use std::ffi::{OsStr, OsString};
-use std::fs;
+use std::fs::{self, File};
+use std::io;
 use std::io::prelude::*;
 use std::path::{self, PathBuf};
+use std::process::Output;
 use std::sync::Arc;
 
+use rustc_serialize::json::Json;
+
 use core::{SourceMap, Package, PackageId, PackageSet, Target, Resolve};
-use core::{Profile, Profiles};
+use core::{Profile, Profiles, Workspace};
 use util::{self, CargoResult, human};
-use util::{Config, internal, ChainError, Fresh, profile, join_paths};
+use util::{internal, ChainError, Fresh, profile, join_paths};
+use util::{process_error, ProcessError};
+use util::machine_message;
 
 use self::job::{Job, Work};
 use self::job_queue::{JobQueue, Stage};
@@ -21,6 +27,7 @@ pub use self::engine::{CommandPrototype, CommandType, ExecEngine, ProcessEngine}
 pub use self::layout::{Layout, LayoutProxy};
 pub use self::custom_build::{BuildOutput, BuildMap};
 
+mod artifact_cache;
 mod context;
 mod compilation;
 mod custom_build;
@@ -43,12 +50,15 @@ pub struct BuildConfig {
     pub exec_engine: Option<Arc<Box<ExecEngine>>>,
     pub release: bool,
     pub doc_all: bool,
+    pub artifact_cache: Option<PathBuf>,
+    pub json_messages: bool,
 }
 
 #[derive(Clone, Default)]
 pub struct TargetConfig {
     pub ar: Option<PathBuf>,
     pub linker: Option<PathBuf>,
+    pub rustflags: Vec<String>,
     pub overrides: HashMap<String, BuildOutput>,
 }
 
@@ -59,10 +69,11 @@ pub fn compile_targets<'a, 'cfg: 'a>(targets: &[(&'a Target, &'a Profile)],
                                      deps: &'a PackageSet,
                                      resolve: &'a Resolve,
                                      sources: &'a SourceMap<'cfg>,
-                                     config: &'cfg Config,
+                                     ws: &Workspace<'cfg>,
                                      build_config: BuildConfig,
                                      profiles: &'a Profiles)
                                      -> CargoResult<Compilation<'cfg>> {
+    let config = ws.config();
     if targets.is_empty() {
         return Ok(Compilation::new(pkg, config))
     }
@@ -72,21 +83,26 @@ pub fn compile_targets<'a, 'cfg: 'a>(targets: &[(&'a Target, &'a Profile)],
     try!(links::validate(deps));
 
     let dest = if build_config.release {"release"} else {"debug"};
-    let root = if resolve.root() == pkg.package_id() {
-        pkg
-    } else {
-        deps.iter().find(|p| p.package_id() == resolve.root()).unwrap()
-    };
-    let host_layout = Layout::new(config, root, None, &dest);
+    let host_layout = Layout::new(ws, None, &dest);
+
+    // Every build goes through the host layout, so locking it keeps other
+    // Cargo processes out of this profile's build directory as a whole. The
+    // directory of a target is locked as well, for `cargo clean --target`.
+    let _lock = try!(host_layout.lock(config));
     let target_layout = build_config.requested_target.as_ref().map(|target| {
-        layout::Layout::new(config, root, Some(&target), &dest)
+        layout::Layout::new(ws, Some(&target), &dest)
     });
+    let _target_lock = match target_layout {
+        Some(ref layout) => Some(try!(layout.lock(config))),
+        None => None,
+    };
 
     let mut cx = try!(Context::new(resolve, sources, deps, config,
                                    host_layout, target_layout, pkg,
                                    build_config, profiles));
 
-    let mut queue = JobQueue::new(cx.resolve, deps, cx.jobs());
+    let mut queue = JobQueue::new(cx.resolve, deps, cx.jobs(),
+                                  cx.jobserver.clone());
 
     // Prep the context's build requirements and see the job graph for all
     // packages initially.
@@ -192,11 +208,25 @@ fn compile<'a, 'cfg>(targets: &[(&'a Target, &'a Profile)],
         for (work, kind) in work {
             let (freshness, dirty, fresh) =
                 try!(fingerprint::prepare_target(cx, pkg, target, profile, kind));
+            let work = try!(artifact_cache::prepare(cx, pkg, target, profile,
+                                                    kind, work));
 
             let dirty = Work::new(move |desc_tx| {
                 try!(work.call(desc_tx.clone()));
                 dirty.call(desc_tx)
             });
+            let fresh = if profile.doc {
+                fresh
+            } else {
+                replay_output(cx, pkg, target, profile, kind, fresh)
+            };
+            let (dirty, fresh) = if cx.build_config.json_messages &&
+                                    !profile.doc {
+                (try!(emit_artifact(cx, pkg, target, profile, kind, dirty)),
+                 try!(emit_artifact(cx, pkg, target, profile, kind, fresh)))
+            } else {
+                (dirty, fresh)
+            };
 
             // Figure out what stage this work will go into
             let dst = match (target.is_lib(),
@@ -274,6 +304,36 @@ fn compile<'a, 'cfg>(targets: &[(&'a Target, &'a Profile)],
     Ok(())
 }
 
+/// Wraps `work` to print a message listing the files produced for `target`
+/// once it's done.
+fn emit_artifact(cx: &Context, pkg: &Package, target: &Target,
+                 profile: &Profile, kind: Kind, work: Work)
+                 -> CargoResult<Work> {
+    let root = cx.out_dir(pkg, kind, target);
+    let filenames = try!(cx.target_filenames(pkg, target, profile, kind));
+    let filenames = filenames.iter().map(|f| {
+        root.join(f).display().to_string()
+    }).collect::<Vec<_>>();
+    let mut features = cx.resolve.features(pkg.package_id()).map(|f| {
+        f.iter().cloned().collect::<Vec<_>>()
+    }).unwrap_or(Vec::new());
+    features.sort();
+    let package_id = pkg.package_id().clone();
+    let target = target.clone();
+    let profile = profile.clone();
+    Ok(Work::new(move |desc_tx| {
+        try!(work.call(desc_tx));
+        machine_message::emit(&machine_message::Artifact {
+            package_id: &package_id,
+            target: &target,
+            profile: &profile,
+            features: features,
+            filenames: filenames,
+        });
+        Ok(())
+    }))
+}
+
 fn prepare_init<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
                           pkg: &'a Package,
                           jobs: &mut JobQueue<'a>,
@@ -329,6 +389,16 @@ fn rustc(package: &Package, target: &Target, profile: &Profile,
         }
         let has_custom_args = profile.rustc_args.is_some();
         let exec_engine = cx.exec_engine.clone();
+        let json_messages = cx.build_config.json_messages;
+        if json_messages {
+            rustc.arg("--error-format").arg("json");
+        } else if cx.config.shell().err().colored() {
+            // The output is piped through Cargo so rustc can't tell it's
+            // shown on a terminal by itself
+            rustc.arg("--color").arg("always");
+        }
+        let package_id = package.package_id().clone();
+        let msg_target = target.clone();
 
         let filenames = try!(cx.target_filenames(package, target, profile,
                                                  kind));
@@ -360,6 +430,11 @@ fn rustc(package: &Package, target: &Target, profile: &Profile,
         }.with_extension("d");
         let dep_info_loc = fingerprint::dep_info_loc(cx, package, target,
                                                      profile, kind);
+        let output_loc = fingerprint::output_loc(cx, package, target, profile,
+                                                 kind, json_messages);
+        let stale_output_loc = fingerprint::output_loc(cx, package, target,
+                                                       profile, kind,
+                                                       !json_messages);
         let cwd = cx.config.cwd().to_path_buf();
 
         Ok((Work::new(move |desc_tx| {
@@ -385,10 +460,34 @@ fn rustc(package: &Package, target: &Target, profile: &Profile,
                 }
             }
 
+            // The output saved in the other format is from an older build,
+            // so it mustn't be replayed once this one is fresh.
+            if fs::metadata(&stale_output_loc).is_ok() {
+                try!(fs::remove_file(&stale_output_loc));
+            }
+
             desc_tx.send(rustc.to_string()).ok();
-            try!(exec_engine.exec(rustc).chain_error(|| {
+            let mut report = |line: &str| {
+                report_line(line, json_messages, &package_id, &msg_target)
+            };
+            let (output, res) = exec_captured(&**exec_engine, rustc,
+                                              &mut report);
+            if let Some(ref output) = output {
+                try!(io::stdout().write_all(&output.stdout));
+            }
+            try!(res.chain_error(|| {
                 human(format!("Could not compile `{}`.", name))
             }));
+            // Whether the output is shown in color depends on where and how
+            // Cargo runs, so it's saved without.
+            if let Some(output) = output {
+                try!(File::create(&output_loc).and_then(|mut f| {
+                    f.write_all(&strip_colors(&output.stderr))
+                }).chain_error(|| {
+                    internal(format!("failed to save the compiler output to \
+                                      `{}`", output_loc.display()))
+                }));
+            }
 
             if do_rename && real_name != crate_name {
                 let dst = root.join(&filenames[0]);
@@ -443,6 +542,96 @@ fn rustc(package: &Package, target: &Target, profile: &Profile,
     }
 }
 
+/// Runs rustc with its output captured, so it can be saved for when the
+/// target is fresh. Each line rustc writes to stderr is passed on to
+/// `on_stderr_line` as it's written, so diagnostics are shown while rustc
+/// runs. Should rustc fail the error doesn't include the output, as it has
+/// been reported already.
+fn exec_captured(exec_engine: &ExecEngine, rustc: CommandPrototype,
+                 on_stderr_line: &mut FnMut(&str) -> io::Result<()>)
+                 -> (Option<Output>, Result<(), ProcessError>) {
+    let cmd = rustc.to_string();
+    match exec_engine.exec_with_streaming(rustc, on_stderr_line) {
+        Ok(output) => (Some(output), Ok(())),
+        Err(mut e) => {
+            let output = e.output.take();
+            if output.is_some() {
+                e = process_error(&format!("Process didn't exit successfully: \
+                                            `{}`", cmd),
+                                  None, e.exit.as_ref(), None);
+            }
+            (output, Err(e))
+        }
+    }
+}
+
+/// Prints what rustc wrote to stderr while compiling `target`, passing each
+/// diagnostic on as a message of its own with `--message-format json`.
+fn report_output(stderr: &[u8], json_messages: bool, package_id: &PackageId,
+                 target: &Target) -> CargoResult<()> {
+    for line in String::from_utf8_lossy(stderr).lines() {
+        try!(report_line(line, json_messages, package_id, target));
+    }
+    Ok(())
+}
+
+/// Prints a single line of what rustc wrote to stderr, see `report_output`.
+fn report_line(line: &str, json_messages: bool, package_id: &PackageId,
+               target: &Target) -> io::Result<()> {
+    if json_messages {
+        // Not everything rustc prints is a diagnostic
+        if let Ok(message) = Json::from_str(line) {
+            machine_message::emit(&machine_message::FromCompiler {
+                package_id: package_id,
+                target: target,
+                message: message,
+            });
+            return Ok(())
+        }
+    }
+    writeln!(io::stderr(), "{}", line)
+}
+
+/// Removes the ANSI escape sequences coloring `output`, as printed by rustc
+/// with `--color always`.
+fn strip_colors(output: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(output.len());
+    let mut bytes = output.iter();
+    while let Some(&b) = bytes.next() {
+        if b != 0x1b {
+            ret.push(b);
+            continue
+        }
+        // A control sequence is `ESC [`, parameters and a final byte
+        if bytes.next() == Some(&b'[') {
+            while let Some(&b) = bytes.next() {
+                if 0x40 <= b && b <= 0x7e { break }
+            }
+        }
+    }
+    ret
+}
+
+/// Wraps the work for a fresh `target` to replay the warnings it was last
+/// compiled with, which would otherwise go unnoticed until it's rebuilt.
+/// They're replayed without color as that's how they're saved.
+fn replay_output(cx: &Context, pkg: &Package, target: &Target,
+                 profile: &Profile, kind: Kind, work: Work) -> Work {
+    let json_messages = cx.build_config.json_messages;
+    let output_loc = fingerprint::output_loc(cx, pkg, target, profile, kind,
+                                             json_messages);
+    let package_id = pkg.package_id().clone();
+    let target = target.clone();
+    Work::new(move |desc_tx| {
+        let mut stderr = Vec::new();
+        if let Ok(mut f) = File::open(&output_loc) {
+            try!(f.read_to_end(&mut stderr));
+        }
+        try!(report_output(&stderr, json_messages, &package_id, &target));
+        work.call(desc_tx)
+    })
+}
+
 fn load_build_deps(cx: &Context, pkg: &Package, target: &Target,
                    profile: &Profile, kind: Kind) -> Vec<PackageId> {
     let pkg = cx.get_package(pkg.package_id());
@@ -482,8 +671,8 @@ fn prepare_rustc(package: &Package, target: &Target, profile: &Profile,
 
     let mut targ_cmd = base.clone();
     let mut host_cmd = base;
-    build_plugin_args(&mut targ_cmd, cx, package, target, Kind::Target);
-    build_plugin_args(&mut host_cmd, cx, package, target, Kind::Host);
+    build_plugin_args(&mut targ_cmd, cx, package, target, profile, Kind::Target);
+    build_plugin_args(&mut host_cmd, cx, package, target, profile, Kind::Host);
     try!(build_deps_args(&mut targ_cmd, target, profile, package, cx, Kind::Target));
     try!(build_deps_args(&mut host_cmd, target, profile, package, cx, Kind::Host));
 
@@ -507,13 +696,14 @@ fn rustdoc(package: &Package, target: &Target, profile: &Profile,
            .cwd(cx.config.cwd())
            .arg("--crate-name").arg(&target.crate_name());
 
-    let mut doc_dir = cx.config.target_dir(cx.get_package(cx.resolve.root()));
     if let Some(target) = cx.requested_target() {
         rustdoc.arg("--target").arg(target);
-        doc_dir.push(target);
     }
 
-    doc_dir.push("doc");
+    // Documentation is placed next to the output directory of the requested
+    // target, for example `target/doc` or `target/$triple/doc`.
+    let doc_dir = cx.layout(package, kind).proxy().dest().parent().unwrap()
+                    .join("doc");
     rustdoc.arg("-o").arg(doc_dir);
 
     match cx.resolve.features(package.package_id()) {
@@ -527,6 +717,10 @@ fn rustdoc(package: &Package, target: &Target, profile: &Profile,
 
     try!(build_deps_args(&mut rustdoc, target, profile, package, cx, kind));
 
+    if let Some(ref args) = profile.rustdoc_args {
+        rustdoc.args(args);
+    }
+
     if package.has_custom_build() {
         rustdoc.env("OUT_DIR", &cx.layout(package, kind).build_out(package));
     }
@@ -573,8 +767,8 @@ fn build_base_args(cx: &Context,
                    profile: &Profile,
                    crate_types: &[&str]) {
     let Profile {
-        opt_level, lto, codegen_units, ref rustc_args, debuginfo, debug_assertions,
-        rpath, test, doc: _doc,
+        opt_level, lto, codegen_units, ref rustc_args, rustdoc_args: _,
+        debuginfo, debug_assertions, rpath, test, doc: _doc, check: _check,
     } = *profile;
 
     // Move to cwd so the root_path() passed below is actually correct
@@ -655,7 +849,7 @@ fn build_base_args(cx: &Context,
 
 
 fn build_plugin_args(cmd: &mut CommandPrototype, cx: &Context, pkg: &Package,
-                     target: &Target, kind: Kind) {
+                     target: &Target, profile: &Profile, kind: Kind) {
     fn opt(cmd: &mut CommandPrototype, key: &str, prefix: &str,
            val: Option<&OsStr>)  {
         if let Some(val) = val {
@@ -666,7 +860,11 @@ fn build_plugin_args(cmd: &mut CommandPrototype, cx: &Context, pkg: &Package,
     }
 
     cmd.arg("--out-dir").arg(&cx.out_dir(pkg, kind, target));
-    cmd.arg("--emit=dep-info,link");
+    if profile.check {
+        cmd.arg("--emit=dep-info,metadata");
+    } else {
+        cmd.arg("--emit=dep-info,link");
+    }
 
     if kind == Kind::Target {
         opt(cmd, "--target", "", cx.requested_target().map(|s| s.as_ref()));
@@ -674,6 +872,7 @@ fn build_plugin_args(cmd: &mut CommandPrototype, cx: &Context, pkg: &Package,
 
     opt(cmd, "-C", "ar=", cx.ar(kind).map(|s| s.as_ref()));
     opt(cmd, "-C", "linker=", cx.linker(kind).map(|s| s.as_ref()));
+    cmd.args(cx.rustflags(kind));
 }
 
 fn build_deps_args(cmd: &mut CommandPrototype,
@@ -701,21 +900,43 @@ fn build_deps_args(cmd: &mut CommandPrototype,
 
     for (pkg, target, p) in cx.dep_targets(package, target, kind, profile) {
         if target.linkable() {
-            try!(link_to(cmd, pkg, target, p, cx, kind));
+            let name = extern_crate_name(package, pkg, target);
+            try!(link_to(cmd, pkg, target, &name, p, cx, kind));
         }
     }
 
     return Ok(());
 
+    // Returns the name `package` refers to the library `target` of `dep` by,
+    // which is the name of the dependency in the manifest if it was renamed.
+    //
+    // If the same package is depended on more than once (e.g. two major
+    // versions under different names) the version requirement picks the
+    // right declaration.
+    fn extern_crate_name(package: &Package, dep: &Package,
+                         target: &Target) -> String {
+        let candidates = package.dependencies().iter().filter(|d| {
+            d.name() == dep.name()
+        }).collect::<Vec<_>>();
+        let declared = candidates.iter().find(|d| {
+            d.matches_version(dep.version())
+        }).or(candidates.first());
+        match declared.and_then(|d| d.rename()) {
+            Some(name) => name.replace("-", "_"),
+            None => target.crate_name(),
+        }
+    }
+
     fn link_to(cmd: &mut CommandPrototype, pkg: &Package, target: &Target,
-               profile: &Profile, cx: &Context, kind: Kind) -> CargoResult<()> {
+               name: &str, profile: &Profile, cx: &Context, kind: Kind)
+               -> CargoResult<()> {
         let kind = kind.for_target(target);
         let layout = cx.layout(pkg, kind);
 
         for filename in try!(cx.target_filenames(pkg, target, profile, kind)).iter() {
             if filename.ends_with(".a") { continue }
             let mut v = OsString::new();
-            v.push(&target.crate_name());
+            v.push(name);
             v.push("=");
             v.push(layout.root());
             v.push(&path::MAIN_SEPARATOR.to_string());
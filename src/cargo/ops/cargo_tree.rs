@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use core::dependency::Platform;
+use core::registry::PackageRegistry;
+use core::resolver::Method;
+use core::{Package, PackageId, Resolve, Workspace};
+use ops::{self, Context};
+use util::{CargoResult, Cfg, Config};
+
+pub struct TreeOptions<'a> {
+    pub features: Vec<String>,
+    pub no_default_features: bool,
+    pub manifest_path: &'a Path,
+    pub target: Option<&'a str>,
+    pub invert: Option<&'a str>,
+    pub duplicates: bool,
+}
+
+/// The resolved dependency graph of a package, restricted to the edges that
+/// are active for the requested target.
+struct Graph<'a> {
+    edges: HashMap<&'a PackageId, Vec<&'a PackageId>>,
+}
+
+/// Prints the resolved dependency graph of the package at
+/// `opts.manifest_path` as an indented tree, or one tree for each member of
+/// the workspace if it's a virtual manifest.
+///
+/// Packages which show up more than once only have their dependencies listed
+/// the first time, later occurrences are marked with `(*)`.
+pub fn tree(opts: TreeOptions, config: &Config) -> CargoResult<()> {
+    let ws = try!(Workspace::new(opts.manifest_path, config));
+
+    let features = opts.features.iter().flat_map(|s| {
+        s.split(' ')
+    }).map(|s| s.to_string()).collect::<Vec<String>>();
+
+    let mut registry = PackageRegistry::new(config);
+    let resolve = try!(ops::resolve_ws(&mut registry, &ws));
+    let method = Method::Required {
+        dev_deps: true,
+        features: &features,
+        uses_default_features: !opts.no_default_features,
+    };
+    let resolve = try!(ops::resolve_with_previous(&mut registry, &ws, method,
+                                                  Some(&resolve), None));
+    let packages = try!(ops::get_resolved_packages(&resolve, &mut registry));
+    let packages = packages.iter().map(|p| {
+        (p.package_id(), p)
+    }).collect::<HashMap<_, _>>();
+
+    // `cfg(..)` dependencies are evaluated against what the compiler says
+    // about the target, just like when building for it.
+    let target = match opts.target {
+        Some(target) => {
+            let (_, _, cfg) = try!(Context::target_info(Some(target), config));
+            Some((target, cfg))
+        }
+        None => None,
+    };
+    let target = target.as_ref().map(|&(target, ref cfg)| {
+        (target, cfg.as_ref().map(|cfg| &cfg[..]))
+    });
+    let mut graph = build_graph(&resolve, &packages, target);
+
+    // For `--duplicates` every package which is present at more than one
+    // version gets an inverted tree, showing who pulls in each version.
+    if opts.duplicates {
+        let graph = graph.invert();
+        let mut by_name = HashMap::new();
+        for id in resolve.iter() {
+            by_name.entry(id.name()).or_insert(Vec::new()).push(id);
+        }
+        let mut dups = by_name.into_iter().filter(|&(_, ref ids)| {
+            ids.len() > 1
+        }).flat_map(|(_, ids)| ids).collect::<Vec<_>>();
+        dups.sort();
+
+        for (i, id) in dups.into_iter().enumerate() {
+            if i != 0 {
+                println!("");
+            }
+            print_tree(&graph, id, &mut Vec::new(), &mut HashSet::new());
+        }
+        return Ok(())
+    }
+
+    let roots = match opts.invert {
+        Some(spec) => {
+            graph = graph.invert();
+            vec![try!(resolve.query(spec))]
+        }
+        None => match ws.current() {
+            Ok(current) => vec![current.package_id()],
+            Err(..) => {
+                let mut members = ws.members().map(|p| {
+                    p.package_id()
+                }).collect::<Vec<_>>();
+                members.sort();
+                members
+            }
+        },
+    };
+
+    let mut visited = HashSet::new();
+    for (i, root) in roots.into_iter().enumerate() {
+        if i != 0 {
+            println!("");
+        }
+        print_tree(&graph, root, &mut Vec::new(), &mut visited);
+    }
+    Ok(())
+}
+
+fn build_graph<'a>(resolve: &'a Resolve,
+                   packages: &HashMap<&'a PackageId, &'a Package>,
+                   target: Option<(&str, Option<&[Cfg]>)>) -> Graph<'a> {
+    let mut edges = HashMap::new();
+    for id in resolve.iter() {
+        let pkg = packages[&id];
+        let mut deps = resolve.deps(id).into_iter().flat_map(|deps| {
+            deps
+        }).filter(|dep| {
+            pkg.dependencies().iter().any(|d| {
+                d.name() == dep.name() && platform_activated(d.platform(),
+                                                             target)
+            })
+        }).collect::<Vec<_>>();
+        deps.sort();
+        edges.insert(id, deps);
+    }
+    Graph { edges: edges }
+}
+
+// Without `--target` every dependency is shown. With it, only the
+// dependencies which are built for that target are.
+fn platform_activated(platform: Option<&Platform>,
+                      target: Option<(&str, Option<&[Cfg]>)>) -> bool {
+    match (platform, target) {
+        (Some(platform), Some((name, cfg))) => platform.matches(name, cfg),
+        _ => true,
+    }
+}
+
+impl<'a> Graph<'a> {
+    fn dependencies(&self, id: &PackageId) -> &[&'a PackageId] {
+        self.edges.get(id).map(|v| &v[..]).unwrap_or(&[])
+    }
+
+    /// Flips the direction of every edge, so a package's "dependencies" are
+    /// the packages which depend on it.
+    fn invert(&self) -> Graph<'a> {
+        let mut edges = HashMap::new();
+        for (&from, tos) in self.edges.iter() {
+            edges.entry(from).or_insert(Vec::new());
+            for &to in tos.iter() {
+                edges.entry(to).or_insert(Vec::new()).push(from);
+            }
+        }
+        for deps in edges.values_mut() {
+            deps.sort();
+        }
+        Graph { edges: edges }
+    }
+}
+
+fn describe(id: &PackageId) -> String {
+    let source = id.source_id();
+    if source.is_registry() {
+        format!("{} v{}", id.name(), id.version())
+    } else {
+        format!("{} v{} ({})", id.name(), id.version(), source.url())
+    }
+}
+
+// `levels` records, for every ancestor of `id`, whether it was the last child
+// of its parent, which decides whether a vertical bar is drawn in its column.
+fn print_tree<'a>(graph: &Graph<'a>, id: &'a PackageId, levels: &mut Vec<bool>,
+                  visited: &mut HashSet<&'a PackageId>) {
+    let mut line = String::new();
+    if levels.len() > 0 {
+        for &last in levels[..levels.len() - 1].iter() {
+            line.push_str(if last {"    "} else {"|   "});
+        }
+        line.push_str(if levels[levels.len() - 1] {"`-- "} else {"|-- "});
+    }
+    line.push_str(&describe(id));
+
+    let deps = graph.dependencies(id);
+    if !visited.insert(id) && deps.len() > 0 {
+        println!("{} (*)", line);
+        return
+    }
+    println!("{}", line);
+
+    for (i, &dep) in deps.iter().enumerate() {
+        levels.push(i == deps.len() - 1);
+        print_tree(graph, dep, levels, visited);
+        levels.pop();
+    }
+}
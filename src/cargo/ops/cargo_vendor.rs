@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json;
+
+use core::registry::PackageRegistry;
+use core::{Package, SourceId, Workspace};
+use ops;
+use sources::PathSource;
+use sources::directory::Checksum;
+use util::{self, hex, CargoResult, ChainError, Config, Sha256, human};
+
+pub struct VendorOptions<'a> {
+    pub manifest_path: &'a Path,
+    pub destination: &'a Path,
+}
+
+/// Copies every registry package in the resolved dependency graph of the
+/// package at `opts.manifest_path` into `opts.destination`, one directory per
+/// package, and prints the configuration needed to build from those copies.
+///
+/// Each directory gets a `.cargo-checksum.json` listing the checksums of its
+/// files, which the directory source uses to verify them later on. Vendored
+/// directories of packages which are no longer needed are removed.
+///
+/// Only registries can be replaced with the vendored directory, so it's an
+/// error for any dependency to come from elsewhere, like a git repository.
+pub fn vendor(opts: VendorOptions, config: &Config) -> CargoResult<()> {
+    let ws = try!(Workspace::new(opts.manifest_path, config));
+    let mut registry = PackageRegistry::new(config);
+    let resolve = try!(ops::resolve_ws(&mut registry, &ws));
+    let mut packages = try!(ops::get_resolved_packages(&resolve,
+                                                       &mut registry));
+    packages.sort_by(|a, b| a.package_id().cmp(b.package_id()));
+
+    let unsupported = packages.iter().map(|p| p.package_id()).find(|id| {
+        !id.source_id().is_path() && !id.source_id().is_registry()
+    });
+    if let Some(id) = unsupported {
+        return Err(human(format!("failed to vendor `{}`, only packages from \
+                                  a registry can be vendored", id)))
+    }
+
+    try!(fs::create_dir_all(opts.destination).chain_error(|| {
+        human(format!("failed to create `{}`", opts.destination.display()))
+    }));
+
+    let mut sources = Vec::new();
+    let mut vendored = HashSet::new();
+    for pkg in packages.iter() {
+        let id = pkg.package_id();
+        if id.source_id().is_path() { continue }
+        let dir = format!("{}-{}", id.name(), id.version());
+        try!(config.shell().status("Vendoring", id));
+        try!(vendor_package(pkg, &opts.destination.join(&dir), config)
+                .chain_error(|| {
+            human(format!("failed to vendor `{}`", id))
+        }));
+        vendored.insert(dir);
+        if !sources.contains(id.source_id()) {
+            sources.push(id.source_id().clone());
+        }
+    }
+
+    // Remove what was vendored by previous runs, but is no longer needed.
+    for entry in try!(fs::read_dir(opts.destination)) {
+        let path = try!(entry).path();
+        let name = path.file_name().and_then(|s| s.to_str())
+                       .map(|s| s.to_string());
+        let stale = match name {
+            Some(ref name) => !vendored.contains(name),
+            None => false,
+        };
+        if stale && fs::metadata(&path.join(".cargo-checksum.json")).is_ok() {
+            try!(fs::remove_dir_all(&path));
+        }
+    }
+
+    let root = opts.manifest_path.parent().unwrap();
+    print_config(&sources, opts.destination, root, config)
+}
+
+#[allow(deprecated)] // connect => join in 1.3
+fn vendor_package(pkg: &Package, dst: &Path, config: &Config)
+                  -> CargoResult<()> {
+    let mut src = PathSource::new(pkg.root(), pkg.package_id().source_id(),
+                                  config);
+    try!(src.update());
+    let files = try!(src.list_files(pkg));
+
+    // Everything is read up front as the package may well be coming from a
+    // previously vendored copy in `dst` itself.
+    let mut contents = Vec::new();
+    for file in files.iter() {
+        let relative = util::without_prefix(file, pkg.root()).unwrap();
+        let mut data = Vec::new();
+        try!(try!(File::open(file)).read_to_end(&mut data));
+        contents.push((relative.to_path_buf(), data));
+    }
+
+    if fs::metadata(dst).is_ok() {
+        try!(fs::remove_dir_all(dst));
+    }
+
+    let mut checksums = HashMap::new();
+    for &(ref relative, ref contents) in contents.iter() {
+        let target = dst.join(relative);
+        try!(fs::create_dir_all(target.parent().unwrap()));
+        try!(try!(File::create(&target)).write_all(contents));
+
+        let mut state = Sha256::new();
+        state.update(contents);
+        let name = relative.iter().map(|s| s.to_str().unwrap())
+                           .collect::<Vec<_>>().connect("/");
+        checksums.insert(name, state.finish().to_hex());
+    }
+
+    let cksum = Checksum {
+        package: pkg.summary().checksum().map(|s| s.to_string()),
+        files: checksums,
+    };
+    let json = try!(json::encode(&cksum).map_err(|e| {
+        human(format!("failed to encode checksums: {}", e))
+    }));
+    try!(try!(File::create(&dst.join(".cargo-checksum.json")))
+              .write_all(json.as_bytes()));
+    Ok(())
+}
+
+/// Prints the `.cargo/config` snippet replacing each of `sources` with the
+/// vendored directory, which is relative to `root` if it's inside of it.
+fn print_config(sources: &[SourceId], destination: &Path, root: &Path,
+                config: &Config) -> CargoResult<()> {
+    let central = try!(SourceId::for_central(config));
+    let destination = util::without_prefix(destination, root)
+                          .unwrap_or(destination);
+    println!("To use vendored sources, add this to your .cargo/config for \
+              this project:");
+    println!("");
+    for id in sources.iter() {
+        if *id == central {
+            println!("[source.crates-io]");
+        } else {
+            println!("[source.registry-{}]", hex::short_hash(id));
+            println!("registry = \"{}\"", id.url());
+        }
+        println!("replace-with = \"vendored-sources\"");
+        println!("");
+    }
+    println!("[source.vendored-sources]");
+    println!("directory = \"{}\"", destination.display());
+    Ok(())
+}
@@ -5,14 +5,19 @@ use std::path::Path;
 use rustc_serialize::{Encodable, Decodable};
 use toml::{self, Encoder, Value};
 
-use core::{Resolve, resolver, Package, SourceId};
+use core::{Resolve, resolver, SourceId, Workspace};
 use util::{CargoResult, ChainError, human};
 use util::toml as cargo_toml;
 
-pub fn load_pkg_lockfile(pkg: &Package) -> CargoResult<Option<Resolve>> {
-    let lockfile = pkg.root().join("Cargo.lock");
-    let source_id = pkg.package_id().source_id();
-    load_lockfile(&lockfile, source_id).chain_error(|| {
+pub fn load_pkg_lockfile(ws: &Workspace) -> CargoResult<Option<Resolve>> {
+    let lockfile = ws.lock_path();
+    // The root of the lock file is the first member of the workspace, and all
+    // packages listed without a source share the source of that root.
+    let source_id = match ws.members().next() {
+        Some(pkg) => pkg.package_id().source_id().clone(),
+        None => try!(SourceId::for_path(ws.root())),
+    };
+    load_lockfile(&lockfile, &source_id).chain_error(|| {
         human(format!("failed to parse lock file at: {}", lockfile.display()))
     })
 }
@@ -33,12 +38,38 @@ pub fn load_lockfile(path: &Path, sid: &SourceId) -> CargoResult<Option<Resolve>
     Ok(Some(try!(v.to_resolve(sid))))
 }
 
-pub fn write_pkg_lockfile(pkg: &Package, resolve: &Resolve) -> CargoResult<()> {
-    let loc = pkg.root().join("Cargo.lock");
-    write_lockfile(&loc, resolve)
+pub fn write_pkg_lockfile(ws: &Workspace, resolve: &Resolve) -> CargoResult<()> {
+    let path = ws.lock_path();
+    let out = serialize_resolve(resolve);
+
+    // If the lockfile contents haven't changed then there's nothing to write,
+    // which also means `--locked` has nothing to complain about.
+    if let Ok(mut f) = File::open(&path) {
+        let mut orig = String::new();
+        if f.read_to_string(&mut orig).is_ok() && orig == out {
+            return Ok(())
+        }
+    }
+
+    let config = ws.config();
+    if config.locked() {
+        let flag = if config.frozen() {"--frozen"} else {"--locked"};
+        return Err(human(format!("the lock file {} needs to be updated but {} \
+                                  was passed to prevent this",
+                                 path.display(), flag)))
+    }
+
+    try!(try!(File::create(&path)).write_all(out.as_bytes()));
+    Ok(())
 }
 
 pub fn write_lockfile(dst: &Path, resolve: &Resolve) -> CargoResult<()> {
+    let out = serialize_resolve(resolve);
+    try!(try!(File::create(dst)).write_all(out.as_bytes()));
+    Ok(())
+}
+
+fn serialize_resolve(resolve: &Resolve) -> String {
     let mut e = Encoder::new();
     resolve.encode(&mut e).unwrap();
 
@@ -63,13 +94,24 @@ pub fn write_lockfile(dst: &Path, resolve: &Resolve) -> CargoResult<()> {
     match e.toml.get(&"metadata".to_string()) {
         Some(metadata) => {
             out.push_str("[metadata]\n");
-            out.push_str(&metadata.to_string());
+            // Keys such as those of checksums contain spaces, so they need
+            // to be quoted.
+            for (k, v) in metadata.as_table().unwrap().iter() {
+                let bare = k.chars().all(|c| {
+                    c.is_alphanumeric() || c == '-' || c == '_'
+                });
+                if bare {
+                    out.push_str(&format!("{} = {}\n", k, v));
+                } else {
+                    out.push_str(&format!("{} = {}\n",
+                                          Value::String(k.clone()), v));
+                }
+            }
         }
         None => {}
     }
 
-    try!(try!(File::create(dst)).write_all(out.as_bytes()));
-    Ok(())
+    out
 }
 
 fn emit_package(dep: &toml::Table, out: &mut String) {
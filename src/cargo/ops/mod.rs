@@ -1,6 +1,6 @@
 pub use self::cargo_clean::{clean, CleanOptions};
-pub use self::cargo_compile::{compile, compile_pkg, CompileOptions};
-pub use self::cargo_compile::{CompileFilter, CompileMode};
+pub use self::cargo_compile::{compile, compile_ws, CompileOptions};
+pub use self::cargo_compile::{CompileFilter, CompileMode, MessageFormat};
 pub use self::cargo_read_manifest::{read_manifest,read_package,read_packages};
 pub use self::cargo_rustc::{compile_targets, Compilation, Layout, Kind};
 pub use self::cargo_rustc::{Context, LayoutProxy};
@@ -13,29 +13,40 @@ pub use self::cargo_doc::{doc, DocOptions};
 pub use self::cargo_generate_lockfile::{generate_lockfile};
 pub use self::cargo_generate_lockfile::{update_lockfile};
 pub use self::cargo_generate_lockfile::UpdateOptions;
+pub use self::cargo_install::{install, install_list, uninstall};
 pub use self::lockfile::{load_lockfile, load_pkg_lockfile};
 pub use self::lockfile::{write_lockfile, write_pkg_lockfile};
 pub use self::cargo_test::{run_tests, run_benches, TestOptions};
 pub use self::cargo_package::package;
 pub use self::registry::{publish, registry_configuration, RegistryConfig};
 pub use self::registry::{registry_login, search, http_proxy_exists, http_handle};
+pub use self::registry::{http_settings, http_cainfo, http_tls_configured};
+pub use self::registry::HttpSettings;
 pub use self::registry::{modify_owners, yank, OwnersOptions};
 pub use self::cargo_fetch::{fetch, get_resolved_packages};
 pub use self::cargo_pkgid::pkgid;
-pub use self::resolve::{resolve_pkg, resolve_with_previous};
+pub use self::cargo_output_metadata::{output_metadata, OutputMetadataOptions};
+pub use self::cargo_output_metadata::ExportInfo;
+pub use self::cargo_tree::{tree, TreeOptions};
+pub use self::cargo_vendor::{vendor, VendorOptions};
+pub use self::resolve::{resolve_ws, resolve_with_previous};
 
 mod cargo_clean;
 mod cargo_compile;
 mod cargo_doc;
 mod cargo_fetch;
 mod cargo_generate_lockfile;
+mod cargo_install;
 mod cargo_new;
+mod cargo_output_metadata;
 mod cargo_package;
 mod cargo_pkgid;
 mod cargo_read_manifest;
 mod cargo_run;
 mod cargo_rustc;
 mod cargo_test;
+mod cargo_tree;
+mod cargo_vendor;
 mod lockfile;
 mod registry;
 mod resolve;
@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::env;
 use std::fs::{self, File};
 use std::io::prelude::*;
@@ -6,7 +5,9 @@ use std::iter::repeat;
 use std::path::{Path, PathBuf};
 
 use curl::http;
+use curl_sys;
 use git2;
+use libc::c_long;
 use registry::{Registry, NewCrate, NewCrateDependency};
 use term::color::BLACK;
 
@@ -18,7 +19,7 @@ use ops;
 use sources::{RegistrySource};
 use util::config;
 use util::{CargoResult, human, ChainError, ToUrl};
-use util::config::{Config, ConfigValue, Location};
+use util::config::Config;
 use util::important_paths::find_root_manifest_for_cwd;
 
 pub struct RegistryConfig {
@@ -26,12 +27,30 @@ pub struct RegistryConfig {
     pub token: Option<String>,
 }
 
+#[allow(deprecated)] // connect => join in 1.3
 pub fn publish(manifest_path: &Path,
                config: &Config,
                token: Option<String>,
                index: Option<String>,
-               verify: bool) -> CargoResult<()> {
+               registry_name: Option<String>,
+               verify: bool,
+               allow_dirty: bool,
+               dry_run: bool) -> CargoResult<()> {
     let pkg = try!(Package::for_path(&manifest_path, config));
+    let (index, name) = try!(publish_registry(config, index, registry_name));
+
+    if let Some(allowed) = pkg.manifest().publish() {
+        if allowed.is_empty() {
+            return Err(human(format!("some crates cannot be published.\n\
+                                      `{}` is marked as unpublishable",
+                                     pkg.name())))
+        } else if !allowed.iter().any(|r| *r == name) {
+            return Err(human(format!("`{}` cannot be published to `{}`, it \
+                                      may only be published to: {}",
+                                     pkg.name(), name,
+                                     allowed.connect(", "))))
+        }
+    }
 
     let (mut registry, reg_id) = try!(registry(config, token, index));
     try!(verify_dependencies(&pkg, &reg_id));
@@ -39,15 +58,59 @@ pub fn publish(manifest_path: &Path,
     // Prepare a tarball, with a non-surpressable warning if metadata
     // is missing since this is being put online.
     let tarball = try!(ops::package(manifest_path, config, verify,
-                                    false, true)).unwrap();
+                                    false, true, allow_dirty)).unwrap();
+
+    if dry_run {
+        try!(config.shell().warn("warning: aborting upload due to dry run"));
+        return Ok(())
+    }
 
     // Upload said tarball to the specified destination
     try!(config.shell().status("Uploading", pkg.package_id().to_string()));
-    try!(transmit(&pkg, &tarball, &mut registry));
+    try!(transmit(&pkg, &tarball, &mut registry, &reg_id));
 
     Ok(())
 }
 
+/// Returns the index to publish to, given either `--host` or `--registry`,
+/// along with the name the registry goes by in the `publish` field of the
+/// manifest. An index given with `--host` which is configured in the
+/// `[registries]` table goes by the name it's configured under, any other
+/// index by its URL.
+fn publish_registry(config: &Config,
+                    index: Option<String>,
+                    name: Option<String>)
+                    -> CargoResult<(Option<String>, String)> {
+    match (index, name) {
+        (Some(..), Some(..)) => {
+            Err(human("only one of `--host` and `--registry` may be given"))
+        }
+        (None, Some(name)) => {
+            if name == "crates-io" {
+                return Ok((None, name))
+            }
+            let id = try!(SourceId::for_alt_registry(&name, config));
+            Ok((Some(id.url().to_string()), name))
+        }
+        (Some(index), None) => {
+            let url = try!(index.to_url().map_err(human));
+            let mut names = match try!(config.get_table("registries")) {
+                Some((table, _)) => table.keys().cloned().collect::<Vec<_>>(),
+                None => Vec::new(),
+            };
+            names.sort();
+            for name in names {
+                let id = try!(SourceId::for_alt_registry(&name, config));
+                if *id.url() == url {
+                    return Ok((Some(index), name))
+                }
+            }
+            Ok((Some(index.clone()), index))
+        }
+        (None, None) => Ok((None, "crates-io".to_string())),
+    }
+}
+
 fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
                        -> CargoResult<()> {
     for dep in pkg.dependencies().iter() {
@@ -59,7 +122,11 @@ fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
                                           dependency `{}` does not specify \
                                           a version", dep.name())))
             }
-        } else if dep.source_id() != registry_src {
+        } else if dep.source_id() != registry_src &&
+                  (!dep.source_id().is_registry() ||
+                   is_crates_io(registry_src)) {
+            // Other registries may accept dependencies from elsewhere, but
+            // crates.io only takes its own.
             return Err(human(format!("all dependencies must come from the \
                                       same source.\ndependency `{}` comes \
                                       from {} instead", dep.name(),
@@ -69,8 +136,12 @@ fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
     Ok(())
 }
 
-fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
-            -> CargoResult<()> {
+fn is_crates_io(id: &SourceId) -> bool {
+    id.url().to_string() == RegistrySource::default_url()
+}
+
+fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry,
+            registry_src: &SourceId) -> CargoResult<()> {
     let deps = pkg.dependencies().iter().map(|dep| {
         NewCrateDependency {
             optional: dep.is_optional(),
@@ -78,12 +149,21 @@ fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
             name: dep.name().to_string(),
             features: dep.features().to_vec(),
             version_req: dep.version_req().to_string(),
-            target: dep.only_for_platform().map(|s| s.to_string()),
+            target: dep.platform().map(|s| s.to_string()),
             kind: match dep.kind() {
                 Kind::Normal => "normal",
                 Kind::Build => "build",
                 Kind::Development => "dev",
             }.to_string(),
+            explicit_name_in_toml: dep.rename().map(|s| s.to_string()),
+            // Path dependencies are published to the same registry as this
+            // package, so they don't name one either.
+            registry: if dep.source_id().is_registry() &&
+                         dep.source_id() != registry_src {
+                Some(dep.source_id().url().to_string())
+            } else {
+                None
+            },
         }
     }).collect::<Vec<NewCrateDependency>>();
     let manifest = pkg.manifest();
@@ -163,24 +243,94 @@ pub fn registry(config: &Config,
 
 /// Create a new HTTP handle with appropriate global configuration for cargo.
 pub fn http_handle(config: &Config) -> CargoResult<http::Handle> {
-    // The timeout option for libcurl by default times out the entire transfer,
-    // but we probably don't want this. Instead we only set timeouts for the
-    // connect phase as well as a "low speed" timeout so if we don't receive
-    // many bytes in a large-ish period of time then we time out.
-    let handle = http::handle().timeout(0)
-                               .connect_timeout(30_000 /* milliseconds */)
-                               .low_speed_limit(10 /* bytes per second */)
-                               .low_speed_timeout(30 /* seconds */);
-    let handle = match try!(http_proxy(config)) {
-        Some(proxy) => handle.proxy(proxy),
-        None => handle,
-    };
-    let handle = match try!(http_timeout(config)) {
-        Some(timeout) => handle.connect_timeout(timeout as usize)
-                               .low_speed_timeout((timeout as usize) / 1000),
-        None => handle,
-    };
-    Ok(handle)
+    Ok(try!(http_settings(config)).handle())
+}
+
+/// The configuration of the HTTP layer, read from a `Config` up front so
+/// that handles can also be created on other threads.
+#[derive(Clone)]
+pub struct HttpSettings {
+    proxy: Option<String>,
+    timeout: Option<i64>,
+    low_speed_limit: Option<i64>,
+    cainfo: Option<PathBuf>,
+    check_revoke: bool,
+    ssl_version: Option<c_long>,
+}
+
+// Values of libcurl's `CURLOPT_SSLVERSION` and `CURLOPT_SSL_OPTIONS`
+const CURL_SSLVERSION_DEFAULT: c_long = 0;
+const CURL_SSLVERSION_TLSV1: c_long = 1;
+const CURL_SSLVERSION_TLSV1_0: c_long = 4;
+const CURL_SSLVERSION_TLSV1_1: c_long = 5;
+const CURL_SSLVERSION_TLSV1_2: c_long = 6;
+const CURL_SSLVERSION_TLSV1_3: c_long = 7;
+const CURLSSLOPT_NO_REVOKE: c_long = 2;
+
+pub fn http_settings(config: &Config) -> CargoResult<HttpSettings> {
+    if try!(config.offline()) {
+        return Err(human("attempting to access the network, but Cargo is \
+                          running in offline mode"))
+    }
+    Ok(HttpSettings {
+        proxy: try!(http_proxy(config)),
+        timeout: try!(http_timeout(config)),
+        low_speed_limit: try!(config.get_i64("http.low-speed-limit"))
+                             .map(|(n, _)| n),
+        cainfo: try!(http_cainfo(config)),
+        check_revoke: try!(config.get_bool("http.check-revoke"))
+                          .map(|(b, _)| b).unwrap_or(true),
+        ssl_version: try!(http_ssl_version(config)),
+    })
+}
+
+impl HttpSettings {
+    pub fn handle(&self) -> http::Handle {
+        // The timeout option for libcurl by default times out the entire
+        // transfer, but we probably don't want this. Instead we only set
+        // timeouts for the connect phase as well as a "low speed" timeout so
+        // if we don't receive many bytes in a large-ish period of time then we
+        // time out.
+        let handle = http::handle().timeout(0)
+                                   .connect_timeout(30_000 /* milliseconds */)
+                                   .low_speed_limit(10 /* bytes per second */)
+                                   .low_speed_timeout(30 /* seconds */);
+        let handle = match self.proxy {
+            Some(ref proxy) => handle.proxy(proxy.clone()),
+            None => handle,
+        };
+        let handle = match self.cainfo {
+            Some(ref path) => handle.ssl_ca_info(path),
+            None => handle,
+        };
+        let handle = match self.low_speed_limit {
+            Some(limit) => handle.low_speed_limit(limit as usize),
+            None => handle,
+        };
+        let mut handle = match self.timeout {
+            Some(timeout) => handle.connect_timeout(timeout as usize)
+                                   .low_speed_timeout((timeout as usize) / 1000),
+            None => handle,
+        };
+        if !self.check_revoke {
+            setopt(&mut handle, curl_sys::CURLOPT_SSL_OPTIONS,
+                   CURLSSLOPT_NO_REVOKE);
+        }
+        if let Some(version) = self.ssl_version {
+            setopt(&mut handle, curl_sys::CURLOPT_SSLVERSION, version);
+        }
+        handle
+    }
+}
+
+/// Sets a libcurl option which `http::Handle` has no method for. The handle
+/// is nothing but a wrapper around the pointer to the libcurl easy handle,
+/// which is read from it directly.
+fn setopt(handle: &mut http::Handle, opt: curl_sys::CURLoption, val: c_long) {
+    unsafe {
+        let easy = *(handle as *mut http::Handle as *mut *mut curl_sys::CURL);
+        curl_sys::curl_easy_setopt(easy, opt, val);
+    }
 }
 
 /// Find an explicit HTTP proxy if one is available.
@@ -223,6 +373,41 @@ pub fn http_proxy_exists(config: &Config) -> CargoResult<bool> {
     }
 }
 
+/// The file of certificate authorities to verify peers with instead of the
+/// system's, configured through `http.cainfo`.
+pub fn http_cainfo(config: &Config) -> CargoResult<Option<PathBuf>> {
+    config.get_path("http.cainfo")
+}
+
+/// The TLS version to force, configured through `http.ssl-version`.
+fn http_ssl_version(config: &Config) -> CargoResult<Option<c_long>> {
+    let (version, path) = match try!(config.get_string("http.ssl-version")) {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let version = match &version[..] {
+        "default" => CURL_SSLVERSION_DEFAULT,
+        "tlsv1" => CURL_SSLVERSION_TLSV1,
+        "tlsv1.0" => CURL_SSLVERSION_TLSV1_0,
+        "tlsv1.1" => CURL_SSLVERSION_TLSV1_1,
+        "tlsv1.2" => CURL_SSLVERSION_TLSV1_2,
+        "tlsv1.3" => CURL_SSLVERSION_TLSV1_3,
+        s => return Err(human(format!("unsupported TLS version `{}` for \
+                                       `http.ssl-version` in {}, expected \
+                                       one of `default`, `tlsv1`, `tlsv1.0`, \
+                                       `tlsv1.1`, `tlsv1.2` or `tlsv1.3`",
+                                      s, path.display()))),
+    };
+    Ok(Some(version))
+}
+
+/// Determine if any of the TLS settings of the HTTP layer are configured.
+pub fn http_tls_configured(config: &Config) -> CargoResult<bool> {
+    Ok(try!(http_cainfo(config)).is_some() ||
+       try!(config.get_bool("http.check-revoke")).is_some() ||
+       try!(config.get_string("http.ssl-version")).is_some())
+}
+
 pub fn http_timeout(config: &Config) -> CargoResult<Option<i64>> {
     match try!(config.get_i64("http.timeout")) {
         Some((s, _)) => return Ok(Some(s)),
@@ -232,19 +417,7 @@ pub fn http_timeout(config: &Config) -> CargoResult<Option<i64>> {
 }
 
 pub fn registry_login(config: &Config, token: String) -> CargoResult<()> {
-    let RegistryConfig { index, token: _ } = try!(registry_configuration(config));
-    let mut map = HashMap::new();
-    let p = config.cwd().to_path_buf();
-    match index {
-        Some(index) => {
-            map.insert("index".to_string(), ConfigValue::String(index, p.clone()));
-        }
-        None => {}
-    }
-    map.insert("token".to_string(), ConfigValue::String(token, p));
-
-    config::set_config(config, Location::Global, "registry",
-                       ConfigValue::Table(map, PathBuf::from(".")))
+    config::save_credentials(config, token)
 }
 
 pub struct OwnersOptions {
@@ -347,7 +520,10 @@ pub fn yank(config: &Config,
     Ok(())
 }
 
-pub fn search(query: &str, config: &Config, index: Option<String>) -> CargoResult<()> {
+pub fn search(query: &str,
+              config: &Config,
+              index: Option<String>,
+              limit: u32) -> CargoResult<()> {
     fn truncate_with_ellipsis(s: &str, max_length: usize) -> String {
         if s.len() < max_length {
             s.to_string()
@@ -357,13 +533,14 @@ pub fn search(query: &str, config: &Config, index: Option<String>) -> CargoResul
     }
 
     let (mut registry, _) = try!(registry(config, None, index));
-    let crates = try!(registry.search(query).map_err(|e| {
+    let (crates, total) = try!(registry.search(query, limit).map_err(|e| {
         human(format!("failed to retrieve search results from the registry: {}", e))
     }));
 
+    // Each line can be pasted as-is into the `[dependencies]` of a manifest
     let list_items = crates.iter()
         .map(|krate| (
-            format!("{} ({})", krate.name, krate.max_version),
+            format!("{} = \"{}\"", krate.name, krate.max_version),
             krate.description.as_ref().map(|desc|
                 truncate_with_ellipsis(&desc.replace("\n", " "), 128))
         ))
@@ -378,12 +555,19 @@ pub fn search(query: &str, config: &Config, index: Option<String>) -> CargoResul
             Some(desc) => {
                 let space = repeat(' ').take(description_margin - name.len())
                                        .collect::<String>();
-                name.to_string() + &space + &desc
+                name.to_string() + &space + "# " + &desc
             }
             None => name
         };
         try!(config.shell().say(line, BLACK));
     }
 
+    let more = total.saturating_sub(crates.len() as u32);
+    if more > 0 {
+        try!(config.shell().say(format!("... and {} crates more (use \
+                                         --limit N to see more)", more),
+                                BLACK));
+    }
+
     Ok(())
 }
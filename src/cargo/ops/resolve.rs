@@ -1,28 +1,34 @@
 use std::collections::{HashMap, HashSet};
 
-use core::{Package, PackageId, SourceId};
+use core::{PackageId, SourceId, Summary, Workspace};
 use core::registry::PackageRegistry;
 use core::resolver::{self, Resolve, Method};
 use ops;
-use util::CargoResult;
+use util::{CargoResult, human};
 
-/// Resolve all dependencies for the specified `package` using the previous
-/// lockfile as a guide if present.
+/// Resolve all dependencies for all members of the workspace `ws` using the
+/// previous lockfile as a guide if present.
 ///
 /// This function will also write the result of resolution as a new
-/// lockfile.
-pub fn resolve_pkg(registry: &mut PackageRegistry, package: &Package)
-                   -> CargoResult<Resolve> {
-    let prev = try!(ops::load_pkg_lockfile(package));
-    let resolve = try!(resolve_with_previous(registry, package,
+/// lockfile, unless the workspace is ephemeral.
+pub fn resolve_ws(registry: &mut PackageRegistry, ws: &Workspace)
+                  -> CargoResult<Resolve> {
+    let prev = try!(ops::load_pkg_lockfile(ws));
+    let resolve = try!(resolve_with_previous(registry, ws,
                                              Method::Everything,
                                              prev.as_ref(), None));
-    try!(ops::write_pkg_lockfile(package, &resolve));
+    if !ws.is_ephemeral() {
+        try!(ops::write_pkg_lockfile(ws, &resolve));
+    }
     Ok(resolve)
 }
 
-/// Resolve all dependencies for a package using an optional previous instance
-/// of resolve to guide the resolution process.
+/// Resolve all dependencies for a workspace using an optional previous
+/// instance of resolve to guide the resolution process.
+///
+/// With `Method::Everything` all members of the workspace are resolved
+/// together, otherwise only the current package is resolved with the given
+/// method, or every member if the workspace root is a virtual manifest.
 ///
 /// This also takes an optional hash set, `to_avoid`, which is a list of package
 /// ids that should be avoided when consulting the previous instance of resolve
@@ -31,14 +37,27 @@ pub fn resolve_pkg(registry: &mut PackageRegistry, package: &Package)
 /// The previous resolve normally comes from a lockfile. This function does not
 /// read or write lockfiles from the filesystem.
 pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
-                                 package: &Package,
+                                 ws: &Workspace,
                                  method: Method,
                                  previous: Option<&'a Resolve>,
                                  to_avoid: Option<&HashSet<&'a PackageId>>)
                                  -> CargoResult<Resolve> {
+    let members = match method {
+        Method::Everything => ws.members().collect::<Vec<_>>(),
+        Method::Required { .. } => match ws.current() {
+            Ok(current) => vec![current],
+            Err(..) => ws.members().collect(),
+        },
+    };
+
+    if members.is_empty() {
+        return Err(human("the workspace has no members to resolve"))
+    }
 
-    try!(registry.add_sources(&[package.package_id().source_id()
-                                       .clone()]));
+    let sources = members.iter().map(|p| {
+        p.package_id().source_id().clone()
+    }).collect::<Vec<_>>();
+    try!(registry.add_sources(&sources));
 
     // Here we place an artificial limitation that all non-registry sources
     // cannot be locked at more than one revision. This means that if a git
@@ -60,59 +79,75 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
         None => {}
     }
 
-    let summary = package.summary().clone();
-    let summary = match previous {
-        Some(r) => {
-            // In the case where a previous instance of resolve is available, we
-            // want to lock as many packages as possible to the previous version
-            // without disturbing the graph structure. To this end we perform
-            // two actions here:
-            //
-            // 1. We inform the package registry of all locked packages. This
-            //    involves informing it of both the locked package's id as well
-            //    as the versions of all locked dependencies. The registry will
-            //    then takes this information into account when it is queried.
-            //
-            // 2. The specified package's summary will have its dependencies
-            //    modified to their precise variants. This will instruct the
-            //    first step of the resolution process to not query for ranges
-            //    but rather for precise dependency versions.
-            //
-            //    This process must handle altered dependencies, however, as
-            //    it's possible for a manifest to change over time to have
-            //    dependencies added, removed, or modified to different version
-            //    ranges. To deal with this, we only actually lock a dependency
-            //    to the previously resolved version if the dependency listed
-            //    still matches the locked version.
-            for node in r.iter().filter(|p| keep(p, to_avoid, &to_avoid_sources)) {
-                let deps = r.deps(node).into_iter().flat_map(|i| i)
-                            .filter(|p| keep(p, to_avoid, &to_avoid_sources))
-                            .map(|p| p.clone()).collect();
-                registry.register_lock(node.clone(), deps);
-            }
-
-            let map = r.deps(r.root()).into_iter().flat_map(|i| i).filter(|p| {
-                keep(p, to_avoid, &to_avoid_sources)
-            }).map(|d| {
-                (d.name(), d)
-            }).collect::<HashMap<_, _>>();
-            summary.map_dependencies(|d| {
-                match map.get(d.name()) {
-                    Some(&lock) if d.matches_id(lock) => d.lock_to(lock),
-                    _ => d,
-                }
-            })
+    if let Some(r) = previous {
+        // In the case where a previous instance of resolve is available, we
+        // want to lock as many packages as possible to the previous version
+        // without disturbing the graph structure. To this end we inform the
+        // package registry of all locked packages. This involves informing it
+        // of both the locked package's id as well as the versions of all
+        // locked dependencies. The registry will then takes this information
+        // into account when it is queried.
+        for node in r.iter().filter(|p| keep(p, to_avoid, &to_avoid_sources)) {
+            let deps = r.deps(node).into_iter().flat_map(|i| i)
+                        .filter(|p| keep(p, to_avoid, &to_avoid_sources))
+                        .map(|p| p.clone()).collect();
+            registry.register_lock(node.clone(), deps);
         }
-        None => summary,
-    };
+    }
 
-    let mut resolved = try!(resolver::resolve(&summary, &method, registry));
+    let summaries = members.iter().map(|member| {
+        let summary = member.summary().clone();
+        let summary = match previous {
+            // The summaries of the workspace members will additionally have
+            // their dependencies modified to their precise variants. This will
+            // instruct the first step of the resolution process to not query
+            // for ranges but rather for precise dependency versions.
+            //
+            // This process must handle altered dependencies, however, as it's
+            // possible for a manifest to change over time to have dependencies
+            // added, removed, or modified to different version ranges. To deal
+            // with this, we only actually lock a dependency to the previously
+            // resolved version if the dependency listed still matches the
+            // locked version.
+            Some(r) => lock_member(r, summary, to_avoid, &to_avoid_sources),
+            None => summary,
+        };
+        (summary, method)
+    }).collect::<Vec<_>>();
+
+    let mut resolved = try!(resolver::resolve(&summaries,
+                                              ws.root_replace(),
+                                              registry,
+                                              Some(ws.config())));
     match previous {
-        Some(r) => resolved.copy_metadata(r),
+        Some(r) => try!(resolved.merge_from(r)),
         None => {}
     }
     return Ok(resolved);
 
+    fn lock_member<'a>(r: &'a Resolve,
+                       summary: Summary,
+                       to_avoid_packages: Option<&HashSet<&'a PackageId>>,
+                       to_avoid_sources: &HashSet<&'a SourceId>) -> Summary {
+        let id = match r.iter().find(|id| {
+            id.name() == summary.name() && id.version() == summary.version()
+        }) {
+            Some(id) => id,
+            None => return summary,
+        };
+        let map = r.deps(id).into_iter().flat_map(|i| i).filter(|p| {
+            keep(p, to_avoid_packages, to_avoid_sources)
+        }).map(|d| {
+            (d.name(), d)
+        }).collect::<HashMap<_, _>>();
+        summary.map_dependencies(|d| {
+            match map.get(d.name()) {
+                Some(&lock) if d.matches_id(lock) => d.lock_to(lock),
+                _ => d,
+            }
+        })
+    }
+
     fn keep<'a>(p: &&'a PackageId,
                 to_avoid_packages: Option<&HashSet<&'a PackageId>>,
                 to_avoid_sources: &HashSet<&'a SourceId>)
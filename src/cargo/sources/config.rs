@@ -0,0 +1,184 @@
+//! Implementation of configuration for various sources
+//!
+//! This module parses the `[source]` tables of `.cargo/config` into a
+//! structure usable by Cargo itself. Currently this is used to replace one
+//! source with another through the `replace-with` key, for example to point
+//! crates.io at a mirror:
+//!
+//! ```toml
+//! [source.crates-io]
+//! replace-with = "my-mirror"
+//!
+//! [source.my-mirror]
+//! registry = "https://example.com/index"
+//! ```
+//!
+//! Besides `registry`, a source can also be a `local-registry`: a directory
+//! holding an index and the `.crate` files it lists, or a `directory` of
+//! unpacked packages as created by `cargo vendor`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use core::{Source, SourceId};
+use sources::ReplacedSource;
+use util::{CargoResult, Config, ChainError, ToUrl, human};
+use util::config::ConfigValue;
+
+pub struct SourceConfigMap<'cfg> {
+    cfgs: HashMap<String, SourceConfig>,
+    id2name: HashMap<SourceId, String>,
+    config: &'cfg Config,
+}
+
+/// Configuration for a particular source, found in TOML looking like:
+///
+/// ```toml
+/// [source.crates-io]
+/// registry = 'https://github.com/rust-lang/crates.io-index'
+/// # or
+/// local-registry = 'path/to/registry'
+/// directory = 'path/to/vendor'
+/// replace-with = 'foo'    # optional
+/// ```
+struct SourceConfig {
+    // The source this configuration describes.
+    id: SourceId,
+
+    // Name of the source that this source should be replaced with, along with
+    // the path of the configuration file which said so.
+    replace_with: Option<(String, PathBuf)>,
+}
+
+impl<'cfg> SourceConfigMap<'cfg> {
+    pub fn new(config: &'cfg Config) -> CargoResult<SourceConfigMap<'cfg>> {
+        let mut base = SourceConfigMap {
+            cfgs: HashMap::new(),
+            id2name: HashMap::new(),
+            config: config,
+        };
+        if let Some((table, _)) = try!(config.get_table("source")) {
+            for (name, value) in table.iter() {
+                try!(base.add_config(name, value));
+            }
+        }
+        if !base.cfgs.contains_key("crates-io") {
+            let id = try!(SourceId::for_central(config));
+            base.add("crates-io", SourceConfig {
+                id: id,
+                replace_with: None,
+            });
+        }
+        Ok(base)
+    }
+
+    /// Creates the `Source` which should be used for `id`, taking any
+    /// configured replacement into account.
+    pub fn load(&self, id: &SourceId) -> CargoResult<Box<Source + 'cfg>> {
+        debug!("loading: {}", id);
+        let mut name = match self.id2name.get(id) {
+            Some(name) => name,
+            None => return Ok(id.load(self.config)),
+        };
+        let orig_name = name;
+        let mut path = Path::new("/");
+        let mut seen = HashSet::new();
+        let new_id;
+        loop {
+            let cfg = match self.cfgs.get(name) {
+                Some(cfg) => cfg,
+                None => {
+                    return Err(human(format!("could not find a configured \
+                                              source with the name `{}` when \
+                                              attempting to lookup `{}` \
+                                              (configuration in `{}`)",
+                                             name, orig_name,
+                                             path.display())))
+                }
+            };
+            match cfg.replace_with {
+                Some((ref s, ref p)) => {
+                    name = s;
+                    path = p.as_path();
+                }
+                None if *id == cfg.id => return Ok(id.load(self.config)),
+                None => {
+                    let precise = id.precise().map(|s| s.to_string());
+                    new_id = cfg.id.with_precise(precise);
+                    break
+                }
+            }
+            if !seen.insert(name) || name == orig_name {
+                return Err(human(format!("detected a cycle of `replace-with` \
+                                          sources, the source `{}` is \
+                                          eventually replaced with itself \
+                                          (configuration in `{}`)",
+                                         name, path.display())))
+            }
+        }
+        debug!("following pointer to registry: {}", new_id);
+        let new_src = new_id.load(self.config);
+        Ok(Box::new(ReplacedSource::new(id, &new_id, new_src)))
+    }
+
+    fn add(&mut self, name: &str, cfg: SourceConfig) {
+        self.id2name.insert(cfg.id.clone(), name.to_string());
+        self.cfgs.insert(name.to_string(), cfg);
+    }
+
+    fn add_config(&mut self, name: &str, cfg: &ConfigValue) -> CargoResult<()> {
+        let (table, path) = try!(cfg.table().chain_error(|| {
+            human(format!("invalid configuration for `source.{}`", name))
+        }));
+        let mut srcs = Vec::new();
+        if let Some(val) = table.get("registry") {
+            let (url, _) = try!(val.string());
+            let url = try!(url.to_url().map_err(human));
+            srcs.push(SourceId::for_registry(&url));
+        }
+        if let Some(val) = table.get("local-registry") {
+            let (s, path) = try!(val.string());
+            // Relative paths are relative to the directory containing the
+            // `.cargo` directory the configuration was found in.
+            let root = path.parent().and_then(|p| p.parent()).unwrap();
+            srcs.push(try!(SourceId::for_local_registry(&root.join(s))));
+        }
+        if let Some(val) = table.get("directory") {
+            let (s, path) = try!(val.string());
+            let root = path.parent().and_then(|p| p.parent()).unwrap();
+            srcs.push(try!(SourceId::for_directory(&root.join(s))));
+        }
+
+        let mut srcs = srcs.into_iter();
+        let src = match (srcs.next(), name == "crates-io") {
+            (Some(src), _) => src,
+            (None, true) => try!(SourceId::for_central(self.config)),
+            (None, false) => {
+                return Err(human(format!("no source URL specified for \
+                                          `source.{}`, need e.g. `registry`, \
+                                          `local-registry` or `directory` \
+                                          defined (configuration in `{}`)",
+                                         name, path.display())))
+            }
+        };
+        if srcs.next().is_some() {
+            return Err(human(format!("more than one source URL specified for \
+                                      `source.{}` (configuration in `{}`)",
+                                     name, path.display())))
+        }
+
+        let replace_with = match table.get("replace-with") {
+            Some(val) => {
+                let (s, path) = try!(val.string());
+                Some((s.to_string(), path.to_path_buf()))
+            }
+            None => None,
+        };
+
+        self.add(name, SourceConfig {
+            id: src,
+            replace_with: replace_with,
+        });
+        Ok(())
+    }
+}
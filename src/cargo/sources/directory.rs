@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json;
+
+use core::{Package, PackageId, Summary, SourceId, Source, Dependency, Registry};
+use ops;
+use util::{CargoResult, ChainError, Config, Sha256, human};
+
+/// A source of packages which have already been unpacked into subdirectories
+/// of one directory, such as the `vendor` directory `cargo vendor` creates.
+///
+/// Next to its `Cargo.toml` every package has a `.cargo-checksum.json` file
+/// listing the checksum of each of its files, as well as the checksum of the
+/// `.crate` file it was unpacked from. The files are verified before the
+/// package is used, as the directory is not supposed to be edited.
+pub struct DirectorySource<'cfg> {
+    id: SourceId,
+    root: PathBuf,
+    packages: HashMap<PackageId, (Package, Checksum)>,
+    config: &'cfg Config,
+}
+
+#[derive(RustcEncodable, RustcDecodable)]
+pub struct Checksum {
+    /// Checksum of the `.crate` file the package was unpacked from, if known.
+    pub package: Option<String>,
+    /// Checksums of all files of the package, keyed by their path relative to
+    /// the root of the package.
+    pub files: HashMap<String, String>,
+}
+
+impl<'cfg> DirectorySource<'cfg> {
+    pub fn new(path: &Path, id: &SourceId, config: &'cfg Config)
+               -> DirectorySource<'cfg> {
+        DirectorySource {
+            id: id.clone(),
+            root: path.to_path_buf(),
+            config: config,
+            packages: HashMap::new(),
+        }
+    }
+}
+
+impl<'cfg> Registry for DirectorySource<'cfg> {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let mut summaries = self.packages.values().map(|&(ref pkg, ref cksum)| {
+            let summary = pkg.summary().clone();
+            match cksum.package {
+                Some(ref c) => summary.set_checksum(c.clone()),
+                None => summary,
+            }
+        }).collect::<Vec<_>>();
+        summaries.query(dep)
+    }
+}
+
+impl<'cfg> Source for DirectorySource<'cfg> {
+    fn update(&mut self) -> CargoResult<()> {
+        self.packages.clear();
+        let entries = try!(fs::read_dir(&self.root).chain_error(|| {
+            human(format!("failed to read root of directory source: {}",
+                          self.root.display()))
+        }));
+
+        for entry in entries {
+            let entry = try!(entry);
+            let path = entry.path();
+
+            // Hidden directories don't contain packages, but may well be
+            // there if the directory is checked into version control.
+            if let Some(s) = path.file_name().and_then(|s| s.to_str()) {
+                if s.starts_with('.') {
+                    continue
+                }
+            }
+
+            let manifest = path.join("Cargo.toml");
+            if fs::metadata(&manifest).is_err() { continue }
+            let (pkg, _) = try!(ops::read_package(&manifest, &self.id,
+                                                  self.config));
+
+            let cksum_file = path.join(".cargo-checksum.json");
+            let mut contents = String::new();
+            try!(File::open(&cksum_file).and_then(|mut f| {
+                f.read_to_string(&mut contents)
+            }).chain_error(|| {
+                human(format!("failed to load checksum `.cargo-checksum.json` \
+                               of {} v{}", pkg.name(), pkg.version()))
+            }));
+            let cksum: Checksum = try!(json::decode(&contents).chain_error(|| {
+                human(format!("failed to decode `.cargo-checksum.json` of \
+                               {} v{}", pkg.name(), pkg.version()))
+            }));
+
+            let pkg = match cksum.package {
+                Some(ref c) => pkg.set_checksum(c.clone()),
+                None => pkg,
+            };
+            self.packages.insert(pkg.package_id().clone(), (pkg, cksum));
+        }
+        Ok(())
+    }
+
+    fn download(&mut self, ids: &[PackageId]) -> CargoResult<()> {
+        // Nothing needs to be downloaded, but make sure that the files are
+        // still the ones which were vendored.
+        for id in ids.iter() {
+            let &(ref pkg, ref cksum) = match self.packages.get(id) {
+                Some(p) => p,
+                None => continue,
+            };
+            for (file, expected) in cksum.files.iter() {
+                let path = pkg.root().join(file);
+                let mut contents = Vec::new();
+                try!(File::open(&path).and_then(|mut f| {
+                    f.read_to_end(&mut contents)
+                }).chain_error(|| {
+                    human(format!("failed to read `{}`", path.display()))
+                }));
+                let actual = {
+                    let mut state = Sha256::new();
+                    state.update(&contents);
+                    state.finish().to_hex()
+                };
+                if &actual != expected {
+                    return Err(human(format!("\
+the listed checksum of `{}` has changed:
+expected: {}
+actual:   {}
+
+directory sources are not intended to be edited, if modifications are \
+required then it is recommended to use a path dependency on a copy of the \
+package instead", path.display(), expected, actual)))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, ids: &[PackageId]) -> CargoResult<Vec<Package>> {
+        Ok(ids.iter().filter_map(|id| {
+            self.packages.get(id).map(|p| p.0.clone())
+        }).collect())
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        Ok(pkg.package_id().version().to_string())
+    }
+}
@@ -8,7 +8,7 @@ use url::{self, Url};
 use core::source::{Source, SourceId};
 use core::GitReference;
 use core::{Package, PackageId, Summary, Registry, Dependency};
-use util::{CargoResult, Config, to_hex};
+use util::{CargoResult, Config, FileLock, human, to_hex};
 use sources::PathSource;
 use sources::git::utils::{GitRemote, GitRevision};
 
@@ -18,7 +18,9 @@ pub struct GitSource<'cfg> {
     remote: GitRemote,
     reference: GitReference,
     db_path: PathBuf,
-    checkout_path: PathBuf,
+    db_lock_path: PathBuf,
+    // Checkouts live in a directory per revision below this one.
+    checkouts_path: PathBuf,
     source_id: SourceId,
     path_source: Option<PathSource<'cfg>>,
     rev: Option<GitRevision>,
@@ -39,26 +41,20 @@ impl<'cfg> GitSource<'cfg> {
         let ident = ident(source_id.url());
 
         let db_path = config.git_db_path().join(&ident);
-
-        let reference_path = match *reference {
-            GitReference::Branch(ref s) |
-            GitReference::Tag(ref s) |
-            GitReference::Rev(ref s) => s.to_string(),
-        };
-        let checkout_path = config.git_checkout_path()
-                                  .join(&ident)
-                                  .join(&reference_path);
+        let db_lock_path = config.git_db_path().join(format!("{}.lock", ident));
+        let checkouts_path = config.git_checkout_path().join(&ident);
 
         let reference = match source_id.precise() {
             Some(s) => GitReference::Rev(s.to_string()),
-            None => source_id.git_reference().unwrap().clone(),
+            None => reference.clone(),
         };
 
         GitSource {
             remote: remote,
             reference: reference,
             db_path: db_path,
-            checkout_path: checkout_path,
+            db_lock_path: db_lock_path,
+            checkouts_path: checkouts_path,
             source_id: source_id.clone(),
             path_source: None,
             rev: None,
@@ -162,11 +158,26 @@ impl<'cfg> Registry for GitSource<'cfg> {
 
 impl<'cfg> Source for GitSource<'cfg> {
     fn update(&mut self) -> CargoResult<()> {
+        // The database and its checkouts are shared by every Cargo process
+        // using this repository, so they're locked while they're written to.
+        // Each revision is checked out into a directory of its own which
+        // never changes once it's complete, so builds using a checkout don't
+        // need to hold on to any lock.
+        let _db_lock = try!(FileLock::exclusive(&self.db_lock_path, self.config,
+                                                "git repository"));
         let actual_rev = self.remote.rev_for(&self.db_path, &self.reference);
         let should_update = actual_rev.is_err() ||
                             self.source_id.precise().is_none();
 
-        let (repo, actual_rev) = if should_update {
+        // In offline mode we can only use what's already in the database.
+        let offline = try!(self.config.offline());
+        if offline && actual_rev.is_err() {
+            return Err(human(format!("cannot fetch git repository `{}` in \
+                                      offline mode, it has not been checked \
+                                      out before", self.remote.url())))
+        }
+
+        let (repo, actual_rev) = if should_update && !offline {
             try!(self.config.shell().status("Updating",
                 format!("git repository `{}`", self.remote.url())));
 
@@ -178,10 +189,18 @@ impl<'cfg> Source for GitSource<'cfg> {
             (try!(self.remote.db_at(&self.db_path)), actual_rev.unwrap())
         };
 
-        try!(repo.copy_to(actual_rev.clone(), &self.checkout_path));
+        let checkout_path = self.checkouts_path.join(actual_rev.to_string());
+        let checkout_lock_path = self.checkouts_path
+                                     .join(format!("{}.lock", actual_rev));
+        {
+            let _checkout_lock = try!(FileLock::exclusive(&checkout_lock_path,
+                                                          self.config,
+                                                          "git checkout"));
+            try!(repo.copy_to(actual_rev.clone(), &checkout_path));
+        }
 
         let source_id = self.source_id.with_precise(Some(actual_rev.to_string()));
-        let path_source = PathSource::new(&self.checkout_path, &source_id,
+        let path_source = PathSource::new(&checkout_path, &source_id,
                                           self.config);
 
         self.path_source = Some(path_source);
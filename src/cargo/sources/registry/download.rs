@@ -0,0 +1,189 @@
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+use curl::http;
+use rustc_serialize::hex::ToHex;
+use url::Url;
+
+use core::PackageId;
+use ops::{self, HttpSettings};
+use util::{CargoResult, ChainError, Config, Sha256, human, internal};
+use util::network;
+
+/// Number of `.crate` files downloaded at the same time unless configured
+/// otherwise through `net.download-jobs`.
+const DEFAULT_JOBS: i64 = 4;
+
+/// Size of the chunks `.crate` files are requested in, which is as much as
+/// is lost when a download is interrupted.
+const CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// A `.crate` file which needs to be downloaded into the local cache.
+pub struct Download {
+    pub id: PackageId,
+    pub url: Url,
+    pub dst: PathBuf,
+    /// The checksum listed in the index, which the download must match.
+    pub cksum: String,
+}
+
+/// Downloads all of `downloads`, a handful of them at a time.
+///
+/// Each transfer happens on a thread of its own with its own HTTP handle and
+/// is retried on failure, while progress is reported from this thread. The
+/// first download which fails is returned as the error, in which case the
+/// transfers still in flight are left to finish on their own.
+pub fn download_all(downloads: Vec<Download>, config: &Config)
+                    -> CargoResult<()> {
+    if downloads.is_empty() { return Ok(()) }
+
+    let settings = try!(ops::http_settings(config));
+    let retries = try!(network::retries(config));
+    let jobs = try!(config.get_i64("net.download-jobs")).map(|(n, _)| n)
+                                                         .unwrap_or(DEFAULT_JOBS);
+    let jobs = if jobs < 1 {1} else {jobs as usize};
+
+    // Our own sender is dropped once every download has been started, so
+    // that the channel is closed rather than waited on forever should the
+    // threads somehow all go away without replying.
+    let (tx, rx) = mpsc::channel();
+    let mut tx = Some(tx);
+    let mut pending = downloads.into_iter();
+    let mut running = 0;
+    loop {
+        while running < jobs {
+            let download = match pending.next() {
+                Some(download) => download,
+                None => { tx = None; break }
+            };
+            try!(config.shell().status("Downloading", &download.id));
+            let reply = Reply(tx.clone());
+            let settings = settings.clone();
+            thread::spawn(move || {
+                let res = download.fetch(&settings, retries).chain_error(|| {
+                    internal(format!("Failed to download package `{}` from {}",
+                                     download.id, download.url))
+                });
+                reply.send(res);
+            });
+            running += 1;
+        }
+        if running == 0 { break }
+        let res = match rx.recv() {
+            Ok(res) => res,
+            Err(..) => return Err(internal("download threads exited \
+                                            without reporting a result")),
+        };
+        running -= 1;
+        try!(res);
+    }
+    Ok(())
+}
+
+/// Sends the result of a download back to the thread waiting on it. If the
+/// download thread panics before getting that far an error is sent instead,
+/// as otherwise its result would be waited on forever.
+struct Reply(Option<mpsc::Sender<CargoResult<()>>>);
+
+impl Reply {
+    fn send(mut self, res: CargoResult<()>) {
+        let _ = self.0.take().unwrap().send(res);
+    }
+}
+
+impl Drop for Reply {
+    fn drop(&mut self) {
+        if let Some(tx) = self.0.take() {
+            let _ = tx.send(Err(internal("download thread panicked")));
+        }
+    }
+}
+
+impl Download {
+    /// Fetches the `.crate` file in chunks, appending each one to a partial
+    /// file next to the destination. If the download fails halfway through,
+    /// the next attempt picks up where this one left off rather than starting
+    /// all over again.
+    fn fetch(&self, settings: &HttpSettings, retries: u32) -> CargoResult<()> {
+        let mut handle = settings.handle();
+        let part = self.dst.with_extension("crate-part");
+        try!(fs::create_dir_all(self.dst.parent().unwrap()));
+
+        loop {
+            let offset = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+            let range = format!("bytes={}-{}", offset, offset + CHUNK_SIZE - 1);
+            // TODO: don't download into memory (curl-rust doesn't expose it)
+            let resp = try!(network::with_retry(retries, || {
+                let resp = try!(handle.get(self.url.to_string())
+                                      .header("Range", &range)
+                                      .follow_redirects(true)
+                                      .exec());
+                // Server errors are usually transient, so they're worth
+                // another try, unlike most others.
+                if resp.get_code() >= 500 {
+                    return Err(internal(format!("Failed to get 200 response \
+                                                 from {}\n{}", self.url, resp)))
+                }
+                Ok(resp)
+            }));
+
+            match resp.get_code() {
+                // Part of the file, add it to what we have so far
+                206 => {
+                    let mut f = try!(fs::OpenOptions::new().write(true)
+                                                           .append(true)
+                                                           .create(true)
+                                                           .open(&part));
+                    try!(f.write_all(resp.get_body()));
+                    if is_last_chunk(&resp, offset) { break }
+                }
+                // The server doesn't support ranges and sent the whole file,
+                // as do local `file://` urls which report a code of 0
+                200 | 0 => {
+                    try!(try!(File::create(&part)).write_all(resp.get_body()));
+                    break
+                }
+                // Everything was already downloaded by a previous attempt
+                416 if offset > 0 => break,
+                _ => {
+                    return Err(internal(format!("Failed to get 200 response \
+                                                 from {}\n{}", self.url, resp)))
+                }
+            }
+        }
+
+        // Verify what we just downloaded
+        let mut contents = Vec::new();
+        try!(try!(File::open(&part)).read_to_end(&mut contents));
+        let actual = {
+            let mut state = Sha256::new();
+            state.update(&contents);
+            state.finish()
+        };
+        if actual.to_hex() != self.cksum {
+            // Don't resume from something which is known to be broken
+            let _ = fs::remove_file(&part);
+            return Err(human(format!("Failed to verify the checksum of `{}`",
+                                     self.id)))
+        }
+
+        try!(fs::rename(&part, &self.dst));
+        Ok(())
+    }
+}
+
+/// Whether the chunk in `resp`, starting at `offset`, ends the file according
+/// to its `Content-Range` header of the form `bytes <start>-<end>/<total>`.
+fn is_last_chunk(resp: &http::Response, offset: u64) -> bool {
+    let len = resp.get_body().len() as u64;
+    let total = resp.get_header("content-range").first().and_then(|range| {
+        range.rsplit('/').next().and_then(|s| s.trim().parse::<u64>().ok())
+    });
+    match total {
+        Some(total) => offset + len >= total,
+        None => len < CHUNK_SIZE,
+    }
+}
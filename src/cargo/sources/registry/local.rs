@@ -0,0 +1,48 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use core::PackageId;
+use sources::registry::RegistryData;
+use util::CargoResult;
+
+/// A registry which lives entirely on the local filesystem, for example a
+/// snapshot of crates.io copied over to a machine without network access.
+///
+/// The root directory contains the index in `index/`, laid out just like a
+/// git index, next to the `<name>-<version>.crate` file of every package the
+/// index lists. Nothing is ever fetched, so updating the index does nothing.
+pub struct LocalRegistry {
+    index_path: PathBuf,
+    root: PathBuf,
+}
+
+impl LocalRegistry {
+    pub fn new(root: &Path) -> LocalRegistry {
+        LocalRegistry {
+            index_path: root.join("index"),
+            root: root.to_path_buf(),
+        }
+    }
+}
+
+impl RegistryData for LocalRegistry {
+    fn load(&mut self, path: &Path) -> CargoResult<Option<String>> {
+        let mut f = match File::open(&self.index_path.join(path)) {
+            Ok(f) => f,
+            Err(..) => return Ok(None),
+        };
+        let mut contents = String::new();
+        try!(f.read_to_string(&mut contents));
+        Ok(Some(contents))
+    }
+
+    fn update_index(&mut self) -> CargoResult<()> {
+        Ok(())
+    }
+
+    fn crate_file(&self, pkg: &PackageId) -> Option<PathBuf> {
+        let name = format!("{}-{}.crate", pkg.name(), pkg.version());
+        Some(self.root.join(name))
+    }
+}
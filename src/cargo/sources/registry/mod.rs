@@ -49,6 +49,30 @@
 //!   the end of a file (the exact format is described later). This means that
 //!   the commits for an index are quite small and easily applied/compressable.
 //!
+//! ## Sparse indexes
+//!
+//! Cloning the whole git index just to look up a handful of crates can be
+//! slow, so a registry can also be accessed through a "sparse" index. Its URL
+//! is the registry's URL prefixed with `sparse+`, for example
+//! `sparse+https://example.com/index/`. The files of a sparse index have the
+//! same layout as those of a git index, but each one is downloaded over plain
+//! HTTP only when a crate of that name is queried. Downloaded files are
+//! cached locally along with their `ETag`, so refreshing them later is a
+//! cheap conditional request.
+//!
+//! ## Local registries
+//!
+//! A registry can also be a plain directory, such as a snapshot copied onto a
+//! machine which has no network access at all. The directory holds the index
+//! under `index/` and the `.crate` files of all packages it lists right next
+//! to it. Local registries are never updated and nothing is downloaded, but
+//! the `.crate` files are still verified against the checksums in the index
+//! before they're unpacked.
+//!
+//! The ways of accessing an index are abstracted behind the `RegistryData`
+//! trait, with `RemoteRegistry` implementing the git index, `SparseRegistry`
+//! the HTTP one and `LocalRegistry` the one on the local filesystem.
+//!
 //! ## The format of the Index
 //!
 //! The index is a store for the list of versions for all packages known, so its
@@ -119,7 +143,11 @@
 //!
 //! To accomplish this, this source's `download` method will make an HTTP
 //! request per-package requested to download tarballs into a local cache. These
-//! tarballs will then be unpacked into a destination folder.
+//! tarballs will then be unpacked into a destination folder. A clean build may
+//! need dozens of packages, so several of them are downloaded at once, each
+//! transfer being retried a few times if it fails. Tarballs are requested in
+//! chunks and stored as `<pkg>-<version>.crate-part` until they're complete,
+//! so an interrupted download is resumed the next time around.
 //!
 //! Note that because versions uploaded to the registry are frozen forever that
 //! the HTTP download and unpacking can all be skipped if the version has
@@ -161,38 +189,65 @@
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use curl::http;
 use flate2::read::GzDecoder;
-use git2;
 use rustc_serialize::hex::ToHex;
 use rustc_serialize::json;
 use tar::Archive;
 use url::Url;
 
 use core::{Source, SourceId, PackageId, Package, Summary, Registry};
-use core::dependency::{Dependency, DependencyInner, Kind};
-use sources::{PathSource, git};
+use core::dependency::{Dependency, DependencyInner, Kind, Platform};
+use sources::PathSource;
 use util::{CargoResult, Config, internal, ChainError, ToUrl, human};
-use util::{hex, Sha256};
+use util::{hex, FileLock, Sha256};
 use ops;
 
+use self::download::Download;
+
+pub use self::local::LocalRegistry;
+pub use self::remote::RemoteRegistry;
+pub use self::sparse::SparseRegistry;
+
+mod download;
+mod local;
+mod remote;
+mod sparse;
+
 static DEFAULT: &'static str = "https://github.com/rust-lang/crates.io-index";
+static SPARSE_PREFIX: &'static str = "sparse+";
 
 pub struct RegistrySource<'cfg> {
     source_id: SourceId,
-    checkout_path: PathBuf,
+    ops: Box<RegistryData + 'cfg>,
     cache_path: PathBuf,
     src_path: PathBuf,
     config: &'cfg Config,
-    handle: Option<http::Handle>,
     sources: Vec<PathSource<'cfg>>,
     hashes: HashMap<(String, String), String>, // (name, vers) => cksum
     cache: HashMap<String, Vec<(Summary, bool)>>,
     updated: bool,
 }
 
+/// The interface between `RegistrySource` and the various ways in which the
+/// index of a registry can be stored and kept up to date.
+pub trait RegistryData {
+    /// Returns the contents of the index file at `path`, which is relative to
+    /// the root of the index, or `None` if the index has no such file.
+    fn load(&mut self, path: &Path) -> CargoResult<Option<String>>;
+
+    /// Brings the local copy of the index up to date.
+    fn update_index(&mut self) -> CargoResult<()>;
+
+    /// Returns where the `.crate` file of `pkg` already lives on the local
+    /// filesystem, if this kind of index is shipped along with its packages.
+    ///
+    /// By default packages are instead downloaded from the `dl` endpoint
+    /// listed in the `config.json` of the index.
+    fn crate_file(&self, _pkg: &PackageId) -> Option<PathBuf> { None }
+}
+
 #[derive(RustcDecodable)]
 pub struct RegistryConfig {
     /// Download endpoint for all crates. This will be appended with
@@ -224,21 +279,57 @@ struct RegistryDependency {
     default_features: bool,
     target: Option<String>,
     kind: Option<String>,
+    // The name of the package depended on when the dependency is renamed, in
+    // which case `name` is the name it's known by in the depending crate.
+    package: Option<String>,
+    // The index of the registry the dependency comes from, if it's not this
+    // one.
+    registry: Option<String>,
 }
 
 impl<'cfg> RegistrySource<'cfg> {
     pub fn new(source_id: &SourceId,
                config: &'cfg Config) -> RegistrySource<'cfg> {
         let hash = hex::short_hash(source_id);
-        let ident = source_id.url().host().unwrap().to_string();
+        let url = source_id.url().to_string();
+        let sparse_url = if url.starts_with(SPARSE_PREFIX) {
+            Some(url[SPARSE_PREFIX.len()..].to_url().unwrap())
+        } else {
+            None
+        };
+        let ident = match sparse_url {
+            Some(ref url) => url.host().unwrap().to_string(),
+            None => source_id.url().host().unwrap().to_string(),
+        };
         let part = format!("{}-{}", ident, hash);
+        let index_path = config.registry_index_path().join(&part);
+        let ops: Box<RegistryData + 'cfg> = match sparse_url {
+            Some(url) => Box::new(SparseRegistry::new(url, index_path, config)),
+            None => Box::new(RemoteRegistry::new(source_id, index_path)),
+        };
+        RegistrySource::with_ops(source_id, ops, &part, config)
+    }
+
+    /// Creates a source for the local registry stored in the directory
+    /// `root`.
+    pub fn local(source_id: &SourceId,
+                 root: &Path,
+                 config: &'cfg Config) -> RegistrySource<'cfg> {
+        let part = format!("local-{}", hex::short_hash(source_id));
+        let ops = Box::new(LocalRegistry::new(root));
+        RegistrySource::with_ops(source_id, ops, &part, config)
+    }
+
+    fn with_ops(source_id: &SourceId,
+                ops: Box<RegistryData + 'cfg>,
+                part: &str,
+                config: &'cfg Config) -> RegistrySource<'cfg> {
         RegistrySource {
-            checkout_path: config.registry_index_path().join(&part),
-            cache_path: config.registry_cache_path().join(&part),
-            src_path: config.registry_source_path().join(&part),
+            ops: ops,
+            cache_path: config.registry_cache_path().join(part),
+            src_path: config.registry_source_path().join(part),
             config: config,
             source_id: source_id.clone(),
-            handle: None,
             sources: Vec::new(),
             hashes: HashMap::new(),
             cache: HashMap::new(),
@@ -264,75 +355,45 @@ impl<'cfg> RegistrySource<'cfg> {
     /// Decode the configuration stored within the registry.
     ///
     /// This requires that the index has been at least checked out.
-    pub fn config(&self) -> CargoResult<RegistryConfig> {
-        let mut f = try!(File::open(&self.checkout_path.join("config.json")));
-        let mut contents = String::new();
-        try!(f.read_to_string(&mut contents));
+    pub fn config(&mut self) -> CargoResult<RegistryConfig> {
+        let contents = try!(self.load(Path::new("config.json")));
+        let contents = try!(contents.chain_error(|| {
+            human(format!("the index of `{}` has no config.json",
+                          self.source_id.url()))
+        }));
         let config = try!(json::decode(&contents));
         Ok(config)
     }
 
-    /// Open the git repository for the index of the registry.
-    ///
-    /// This will attempt to open an existing checkout, and failing that it will
-    /// initialize a fresh new directory and git checkout. No remotes will be
-    /// configured by default.
-    fn open(&self) -> CargoResult<git2::Repository> {
-        match git2::Repository::open(&self.checkout_path) {
-            Ok(repo) => return Ok(repo),
-            Err(..) => {}
-        }
-
-        try!(fs::create_dir_all(&self.checkout_path));
-        let _ = fs::remove_dir_all(&self.checkout_path);
-        let repo = try!(git2::Repository::init(&self.checkout_path));
-        Ok(repo)
-    }
-
-    /// Download the given package from the given url into the local cache.
-    ///
-    /// This will perform the HTTP request to fetch the package. This function
-    /// will only succeed if the HTTP download was successful and the file is
-    /// then ready for inspection.
-    ///
-    /// No action is taken if the package is already downloaded.
-    fn download_package(&mut self, pkg: &PackageId, url: &Url)
-                        -> CargoResult<PathBuf> {
+    /// Where the `.crate` file of `pkg` is stored in the local cache once
+    /// it has been downloaded.
+    fn crate_path(&self, pkg: &PackageId) -> PathBuf {
         // TODO: should discover filename from the S3 redirect
         let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
-        let dst = self.cache_path.join(&filename);
-        if fs::metadata(&dst).is_ok() { return Ok(dst) }
-        try!(self.config.shell().status("Downloading", pkg));
+        self.cache_path.join(&filename)
+    }
 
-        try!(fs::create_dir_all(dst.parent().unwrap()));
+    /// Checks a `.crate` file which is already on the local filesystem
+    /// against the checksum listed for it in the index.
+    fn verify_local_package(&mut self, pkg: &PackageId, path: &Path)
+                            -> CargoResult<()> {
         let expected_hash = try!(self.hash(pkg));
-        let handle = match self.handle {
-            Some(ref mut handle) => handle,
-            None => {
-                self.handle = Some(try!(ops::http_handle(self.config)));
-                self.handle.as_mut().unwrap()
-            }
-        };
-        // TODO: don't download into memory (curl-rust doesn't expose it)
-        let resp = try!(handle.get(url.to_string()).follow_redirects(true).exec());
-        if resp.get_code() != 200 && resp.get_code() != 0 {
-            return Err(internal(format!("Failed to get 200 response from {}\n{}",
-                                        url, resp)))
-        }
-
-        // Verify what we just downloaded
+        let mut contents = Vec::new();
+        let mut f = try!(File::open(path).chain_error(|| {
+            human(format!("the local registry has no `.crate` file for \
+                           `{}` at `{}`", pkg, path.display()))
+        }));
+        try!(f.read_to_end(&mut contents));
         let actual = {
             let mut state = Sha256::new();
-            state.update(resp.get_body());
+            state.update(&contents);
             state.finish()
         };
         if actual.to_hex() != expected_hash {
             return Err(human(format!("Failed to verify the checksum of `{}`",
                                      pkg)))
         }
-
-        try!(try!(File::create(&dst)).write_all(resp.get_body()));
-        Ok(dst)
+        Ok(())
     }
 
     /// Return the hash listed for a specified PackageId.
@@ -373,7 +434,7 @@ impl<'cfg> RegistrySource<'cfg> {
             return Ok(self.cache.get(name).unwrap());
         }
         // see module comment for why this is structured the way it is
-        let path = self.checkout_path.clone();
+        let path = PathBuf::new();
         let fs_name = name.chars().flat_map(|c| c.to_lowercase()).collect::<String>();
         let path = match fs_name.len() {
             1 => path.join("1").join(&fs_name),
@@ -383,10 +444,8 @@ impl<'cfg> RegistrySource<'cfg> {
                      .join(&fs_name[2..4])
                      .join(&fs_name),
         };
-        let summaries = match File::open(&path) {
-            Ok(mut f) => {
-                let mut contents = String::new();
-                try!(f.read_to_string(&mut contents));
+        let summaries = match try!(self.load(&path)) {
+            Some(contents) => {
                 let ret: CargoResult<Vec<(Summary, bool)>>;
                 ret = contents.lines().filter(|l| l.trim().len() > 0)
                               .map(|l| self.parse_registry_package(l))
@@ -396,7 +455,7 @@ impl<'cfg> RegistrySource<'cfg> {
                                       for: {}", name))
                 }))
             }
-            Err(..) => Vec::new(),
+            None => Vec::new(),
         };
         let summaries = summaries.into_iter().filter(|summary| {
             summary.0.package_id().name() == name
@@ -419,19 +478,38 @@ impl<'cfg> RegistrySource<'cfg> {
             self.parse_registry_dependency(dep)
         }).collect();
         let deps = try!(deps);
+        let summary = try!(Summary::new(pkgid, deps, features));
+        let summary = summary.set_checksum(cksum.clone());
         self.hashes.insert((name, vers), cksum);
-        Ok((try!(Summary::new(pkgid, deps, features)), yanked.unwrap_or(false)))
+        Ok((summary, yanked.unwrap_or(false)))
     }
 
     /// Converts an encoded dependency in the registry to a cargo dependency
     fn parse_registry_dependency(&self, dep: RegistryDependency)
                                  -> CargoResult<Dependency> {
         let RegistryDependency {
-            name, req, features, optional, default_features, target, kind
+            name, req, features, optional, default_features, target, kind,
+            package, registry
         } = dep;
 
-        let dep = try!(DependencyInner::parse(&name, Some(&req),
-                                              &self.source_id));
+        let source_id = match registry {
+            Some(index) => {
+                SourceId::for_registry(&try!(index.to_url().map_err(human)))
+            }
+            None => self.source_id.clone(),
+        };
+        let dep = match package {
+            Some(package) => {
+                try!(DependencyInner::parse(&package, Some(&req),
+                                            &source_id)).set_rename(&name)
+            }
+            None => try!(DependencyInner::parse(&name, Some(&req),
+                                                &source_id)),
+        };
+        let platform = match target {
+            Some(target) => Some(try!(target.parse::<Platform>())),
+            None => None,
+        };
         let kind = match kind.as_ref().map(|s| &s[..]).unwrap_or("") {
             "dev" => Kind::Development,
             "build" => Kind::Build,
@@ -448,7 +526,7 @@ impl<'cfg> RegistrySource<'cfg> {
         Ok(dep.set_optional(optional)
               .set_default_features(default_features)
               .set_features(features)
-              .set_only_for_platform(target)
+              .set_platform(platform)
               .set_kind(kind)
               .into_dependency())
     }
@@ -457,27 +535,37 @@ impl<'cfg> RegistrySource<'cfg> {
     fn do_update(&mut self) -> CargoResult<()> {
         if self.updated { return Ok(()) }
 
-        try!(self.config.shell().status("Updating",
-             format!("registry `{}`", self.source_id.url())));
-        let repo = try!(self.open());
-
-        // git fetch origin
-        let url = self.source_id.url().to_string();
-        let refspec = "refs/heads/*:refs/remotes/origin/*";
-        try!(git::fetch(&repo, &url, refspec).chain_error(|| {
-            internal(format!("failed to fetch `{}`", url))
-        }));
+        // In offline mode whatever copy of the index we have locally is the
+        // best we can do, so use that as-is.
+        if try!(self.config.offline()) {
+            self.updated = true;
+            return Ok(())
+        }
 
-        // git reset --hard origin/master
-        let reference = "refs/remotes/origin/master";
-        let oid = try!(repo.refname_to_id(reference));
-        trace!("[{}] updating to rev {}", self.source_id, oid);
-        let object = try!(repo.find_object(oid, None));
-        try!(repo.reset(&object, git2::ResetType::Hard, None));
+        if !self.source_id.is_local_registry() {
+            try!(self.config.shell().status("Updating",
+                 format!("registry `{}`", self.source_id.url())));
+        }
+        let _lock = try!(self.lock());
+        try!(self.ops.update_index());
         self.updated = true;
         self.cache.clear();
         Ok(())
     }
+
+    /// Locks this registry's index and caches against other Cargo processes
+    /// for as long as the returned lock is alive.
+    fn lock(&self) -> CargoResult<FileLock> {
+        FileLock::exclusive(&self.cache_path.join(".cargo-lock"), self.config,
+                            "package cache")
+    }
+
+    /// Reads a file of the index, which another Cargo process may be in the
+    /// middle of updating otherwise.
+    fn load(&mut self, path: &Path) -> CargoResult<Option<String>> {
+        let _lock = try!(self.lock());
+        self.ops.load(path)
+    }
 }
 
 impl<'cfg> Registry for RegistrySource<'cfg> {
@@ -495,6 +583,14 @@ impl<'cfg> Registry for RegistrySource<'cfg> {
             }
         }
 
+        if try!(self.config.offline()) &&
+           try!(self.summaries(dep.name())).len() == 0 {
+            return Err(human(format!("package `{}` was not found in the cached \
+                                      index of `{}`, and the index cannot be \
+                                      updated in offline mode",
+                                     dep.name(), self.source_id.url())))
+        }
+
         let mut summaries = {
             let summaries = try!(self.summaries(dep.name()));
             summaries.iter().filter(|&&(_, yanked)| {
@@ -535,19 +631,57 @@ impl<'cfg> Source for RegistrySource<'cfg> {
     }
 
     fn download(&mut self, packages: &[PackageId]) -> CargoResult<()> {
-        let config = try!(self.config());
-        let url = try!(config.dl.to_url().map_err(internal));
-        for package in packages.iter() {
-            if self.source_id != *package.source_id() { continue }
+        let packages = packages.iter().filter(|p| {
+            self.source_id == *p.source_id()
+        }).collect::<Vec<_>>();
+
+        // Everything needed from the index is read before the caches are
+        // locked, as reading the index takes the same lock.
+        for &package in packages.iter() {
+            try!(self.hash(package));
+        }
+        let dl = if packages.iter().any(|p| self.ops.crate_file(p).is_none()) {
+            let config = try!(self.config());
+            Some(try!(config.dl.to_url().map_err(internal)))
+        } else {
+            None
+        };
+        let _lock = try!(self.lock());
+
+        // First figure out which packages actually need to be fetched, so
+        // they can all be downloaded in one go.
+        let mut downloads = Vec::new();
+        for &package in packages.iter() {
+            if self.ops.crate_file(package).is_some() { continue }
+            let dst = self.crate_path(package);
+            if fs::metadata(&dst).is_ok() { continue }
+            if try!(self.config.offline()) {
+                return Err(human(format!("cannot download `{}` in offline \
+                                          mode, it has not been downloaded \
+                                          before", package)))
+            }
 
-            let mut url = url.clone();
+            let mut url = dl.clone().unwrap();
             url.path_mut().unwrap().push(package.name().to_string());
             url.path_mut().unwrap().push(package.version().to_string());
             url.path_mut().unwrap().push("download".to_string());
-            let path = try!(self.download_package(package, &url).chain_error(|| {
-                internal(format!("Failed to download package `{}` from {}",
-                                 package, url))
-            }));
+            downloads.push(Download {
+                id: package.clone(),
+                url: url,
+                dst: dst,
+                cksum: try!(self.hash(package)),
+            });
+        }
+        try!(download::download_all(downloads, self.config));
+
+        for &package in packages.iter() {
+            let path = match self.ops.crate_file(package) {
+                Some(path) => {
+                    try!(self.verify_local_package(package, &path));
+                    path
+                }
+                None => self.crate_path(package),
+            };
             let path = try!(self.unpack_package(package, path).chain_error(|| {
                 internal(format!("Failed to unpack package `{}`", package))
             }));
@@ -563,7 +697,15 @@ impl<'cfg> Source for RegistrySource<'cfg> {
         for src in self.sources.iter() {
             ret.extend(try!(src.get(packages)).into_iter());
         }
-        return Ok(ret);
+        // The manifests of unpacked packages don't know where they came
+        // from, so tell them what they were verified against.
+        Ok(ret.into_iter().map(|pkg| {
+            let key = (pkg.name().to_string(), pkg.version().to_string());
+            match self.hashes.get(&key) {
+                Some(cksum) => pkg.set_checksum(cksum.clone()),
+                None => pkg,
+            }
+        }).collect())
     }
 
     fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
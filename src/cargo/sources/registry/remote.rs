@@ -0,0 +1,74 @@
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use git2;
+
+use core::SourceId;
+use sources::git;
+use sources::registry::RegistryData;
+use util::{CargoResult, ChainError, internal};
+
+/// An index which is a git repository, checked out in its entirety under the
+/// cargo home directory and updated with `git fetch`.
+pub struct RemoteRegistry {
+    source_id: SourceId,
+    checkout_path: PathBuf,
+}
+
+impl RemoteRegistry {
+    pub fn new(source_id: &SourceId, checkout_path: PathBuf) -> RemoteRegistry {
+        RemoteRegistry {
+            source_id: source_id.clone(),
+            checkout_path: checkout_path,
+        }
+    }
+
+    /// Open the git repository for the index of the registry.
+    ///
+    /// This will attempt to open an existing checkout, and failing that it will
+    /// initialize a fresh new directory and git checkout. No remotes will be
+    /// configured by default.
+    fn open(&self) -> CargoResult<git2::Repository> {
+        match git2::Repository::open(&self.checkout_path) {
+            Ok(repo) => return Ok(repo),
+            Err(..) => {}
+        }
+
+        try!(fs::create_dir_all(&self.checkout_path));
+        let _ = fs::remove_dir_all(&self.checkout_path);
+        let repo = try!(git2::Repository::init(&self.checkout_path));
+        Ok(repo)
+    }
+}
+
+impl RegistryData for RemoteRegistry {
+    fn load(&mut self, path: &Path) -> CargoResult<Option<String>> {
+        let mut f = match File::open(&self.checkout_path.join(path)) {
+            Ok(f) => f,
+            Err(..) => return Ok(None),
+        };
+        let mut contents = String::new();
+        try!(f.read_to_string(&mut contents));
+        Ok(Some(contents))
+    }
+
+    fn update_index(&mut self) -> CargoResult<()> {
+        let repo = try!(self.open());
+
+        // git fetch origin
+        let url = self.source_id.url().to_string();
+        let refspec = "refs/heads/*:refs/remotes/origin/*";
+        try!(git::fetch(&repo, &url, refspec).chain_error(|| {
+            internal(format!("failed to fetch `{}`", url))
+        }));
+
+        // git reset --hard origin/master
+        let reference = "refs/remotes/origin/master";
+        let oid = try!(repo.refname_to_id(reference));
+        trace!("[{}] updating to rev {}", self.source_id, oid);
+        let object = try!(repo.find_object(oid, None));
+        try!(repo.reset(&object, git2::ResetType::Hard, None));
+        Ok(())
+    }
+}
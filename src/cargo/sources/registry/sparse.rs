@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use curl::http;
+use url::Url;
+
+use ops;
+use sources::registry::RegistryData;
+use util::{CargoResult, ChainError, Config, human, internal};
+
+/// An index which is served over plain HTTP, one file per crate.
+///
+/// Files are only downloaded when they're asked for, and are cached on disk
+/// next to the `ETag` the server sent for them. Until the index is updated a
+/// cached file is used as-is, afterwards it's revalidated with a conditional
+/// request the next time it's loaded.
+pub struct SparseRegistry<'cfg> {
+    url: Url,
+    index_path: PathBuf,
+    config: &'cfg Config,
+    handle: Option<http::Handle>,
+    // Whether cached files need to be revalidated against the server, which
+    // is the case once the index has been asked to update.
+    refresh: bool,
+    // Files which have already been revalidated during this session.
+    fresh: HashSet<PathBuf>,
+}
+
+impl<'cfg> SparseRegistry<'cfg> {
+    pub fn new(url: Url, index_path: PathBuf, config: &'cfg Config)
+               -> SparseRegistry<'cfg> {
+        SparseRegistry {
+            url: url,
+            index_path: index_path,
+            config: config,
+            handle: None,
+            refresh: false,
+            fresh: HashSet::new(),
+        }
+    }
+
+    fn read(path: &Path) -> CargoResult<Option<String>> {
+        let mut f = match File::open(path) {
+            Ok(f) => f,
+            Err(..) => return Ok(None),
+        };
+        let mut contents = String::new();
+        try!(f.read_to_string(&mut contents));
+        Ok(Some(contents))
+    }
+
+    /// Downloads the index file at `path`, sending along the `ETag` of the
+    /// cached copy (if any) so the server can tell us it hasn't changed.
+    fn fetch(&mut self, path: &Path) -> CargoResult<()> {
+        let dst = self.index_path.join(path);
+        let etag_path = dst.with_extension("etag");
+        let etag = try!(SparseRegistry::read(&etag_path));
+
+        let mut url = self.url.clone();
+        {
+            let segments = url.path_mut().unwrap();
+            if segments.last().map(|s| s.is_empty()) == Some(true) {
+                segments.pop();
+            }
+            for part in path.iter() {
+                segments.push(part.to_str().unwrap().to_string());
+            }
+        }
+
+        let handle = match self.handle {
+            Some(ref mut handle) => handle,
+            None => {
+                self.handle = Some(try!(ops::http_handle(self.config)));
+                self.handle.as_mut().unwrap()
+            }
+        };
+        let mut req = handle.get(url.to_string()).follow_redirects(true);
+        if let Some(ref etag) = etag {
+            if fs::metadata(&dst).is_ok() {
+                req = req.header("If-None-Match", etag);
+            }
+        }
+        let resp = try!(req.exec());
+
+        match resp.get_code() {
+            304 => {}
+            // A code of 0 is what's returned for local `file://` urls
+            200 | 0 => {
+                try!(fs::create_dir_all(dst.parent().unwrap()));
+                try!(try!(File::create(&dst)).write_all(resp.get_body()));
+                match resp.get_header("etag").first() {
+                    Some(etag) => {
+                        try!(try!(File::create(&etag_path))
+                                      .write_all(etag.as_bytes()));
+                    }
+                    None => { let _ = fs::remove_file(&etag_path); }
+                }
+            }
+            // Crates which don't exist simply have no file in the index
+            404 | 410 => {
+                let _ = fs::remove_file(&dst);
+                let _ = fs::remove_file(&etag_path);
+            }
+            code => {
+                return Err(internal(format!("failed to get 200 response from \
+                                             `{}`, got {}", url, code)))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'cfg> RegistryData for SparseRegistry<'cfg> {
+    fn load(&mut self, path: &Path) -> CargoResult<Option<String>> {
+        let dst = self.index_path.join(path);
+        let cached = fs::metadata(&dst).is_ok();
+        let stale = !cached || (self.refresh && !self.fresh.contains(path));
+        if stale && !try!(self.config.offline()) {
+            try!(self.fetch(path).chain_error(|| {
+                human(format!("failed to fetch `{}` from the index at `{}`",
+                              path.display(), self.url))
+            }));
+            self.fresh.insert(path.to_path_buf());
+        }
+        SparseRegistry::read(&dst)
+    }
+
+    fn update_index(&mut self) -> CargoResult<()> {
+        // Nothing is downloaded up front, rather every file which is loaded
+        // from now on is revalidated against the server.
+        self.refresh = true;
+        self.fresh.clear();
+        Ok(())
+    }
+}
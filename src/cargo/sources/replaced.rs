@@ -0,0 +1,84 @@
+use core::{Source, Registry, PackageId, Package, Dependency, Summary, SourceId};
+use util::{CargoResult, ChainError, human};
+
+/// A `Source` standing in for another one, as configured with `replace-with`
+/// in `.cargo/config`.
+///
+/// Packages are fetched from the replacement, but everything handed back to
+/// Cargo is presented as coming from the original source, so lock files and
+/// package ids are unaffected by the replacement.
+pub struct ReplacedSource<'cfg> {
+    to_replace: SourceId,
+    replace_with: SourceId,
+    inner: Box<Source + 'cfg>,
+}
+
+impl<'cfg> ReplacedSource<'cfg> {
+    pub fn new(to_replace: &SourceId,
+               replace_with: &SourceId,
+               src: Box<Source + 'cfg>) -> ReplacedSource<'cfg> {
+        ReplacedSource {
+            to_replace: to_replace.clone(),
+            replace_with: replace_with.clone(),
+            inner: src,
+        }
+    }
+
+    // Only the packages which actually belong to the replaced source are
+    // moved over, others are left alone for the inner source to ignore.
+    fn map_ids(&self, ids: &[PackageId]) -> Vec<PackageId> {
+        ids.iter().map(|id| {
+            if id.source_id() == &self.to_replace {
+                id.with_source_id(&self.replace_with)
+            } else {
+                id.clone()
+            }
+        }).collect()
+    }
+}
+
+impl<'cfg> Registry for ReplacedSource<'cfg> {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let dep = dep.clone().map_source(&self.to_replace, &self.replace_with);
+        let ret = try!(self.inner.query(&dep).chain_error(|| {
+            human(format!("failed to query replaced source `{}`",
+                          self.to_replace))
+        }));
+        Ok(ret.into_iter().map(|s| {
+            s.map_source(&self.replace_with, &self.to_replace)
+        }).collect())
+    }
+}
+
+impl<'cfg> Source for ReplacedSource<'cfg> {
+    fn update(&mut self) -> CargoResult<()> {
+        self.inner.update().chain_error(|| {
+            human(format!("failed to update replaced source `{}`",
+                          self.to_replace))
+        })
+    }
+
+    fn download(&mut self, ids: &[PackageId]) -> CargoResult<()> {
+        let ids = self.map_ids(ids);
+        self.inner.download(&ids).chain_error(|| {
+            human(format!("failed to download replaced source `{}`",
+                          self.to_replace))
+        })
+    }
+
+    fn get(&self, ids: &[PackageId]) -> CargoResult<Vec<Package>> {
+        let ids = self.map_ids(ids);
+        let ret = try!(self.inner.get(&ids).chain_error(|| {
+            human(format!("failed to get packages from replaced source `{}`",
+                          self.to_replace))
+        }));
+        Ok(ret.into_iter().map(|p| {
+            p.map_source(&self.replace_with, &self.to_replace)
+        }).collect())
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        let pkg = pkg.clone().map_source(&self.to_replace, &self.replace_with);
+        self.inner.fingerprint(&pkg)
+    }
+}
@@ -11,7 +11,7 @@ use std::path::{Path, PathBuf};
 
 use rustc_serialize::{Encodable,Encoder};
 use toml;
-use core::{MultiShell, Package};
+use core::MultiShell;
 use util::{CargoResult, ChainError, Rustc, internal, human};
 
 use util::toml as cargo_toml;
@@ -26,8 +26,13 @@ pub struct Config {
     values_loaded: Cell<bool>,
     cwd: PathBuf,
     rustc: PathBuf,
+    rustc_wrapper: Option<PathBuf>,
     rustdoc: PathBuf,
-    target_dir: Option<PathBuf>,
+    target_dir: RefCell<Option<PathBuf>>,
+    minimal_versions: Cell<bool>,
+    offline: Cell<bool>,
+    locked: Cell<bool>,
+    frozen: Cell<bool>,
 }
 
 impl Config {
@@ -47,8 +52,13 @@ impl Config {
             values: RefCell::new(HashMap::new()),
             values_loaded: Cell::new(false),
             rustc: PathBuf::from("rustc"),
+            rustc_wrapper: None,
             rustdoc: PathBuf::from("rustdoc"),
-            target_dir: None,
+            target_dir: RefCell::new(None),
+            minimal_versions: Cell::new(false),
+            offline: Cell::new(false),
+            locked: Cell::new(false),
+            frozen: Cell::new(false),
         };
 
         try!(cfg.scrape_tool_config());
@@ -86,6 +96,13 @@ impl Config {
 
     pub fn rustc(&self) -> &Path { &self.rustc }
 
+    /// A program to run compilations through, which is passed the path of
+    /// the compiler followed by its arguments. Only used to compile crates,
+    /// the compiler is queried for its version and such directly.
+    pub fn rustc_wrapper(&self) -> Option<&Path> {
+        self.rustc_wrapper.as_ref().map(|p| &**p)
+    }
+
     pub fn rustdoc(&self) -> &Path { &self.rustdoc }
 
     pub fn rustc_info(&self) -> &Rustc { &self.rustc_info }
@@ -100,10 +117,62 @@ impl Config {
 
     pub fn cwd(&self) -> &Path { &self.cwd }
 
-    pub fn target_dir(&self, pkg: &Package) -> PathBuf {
-        self.target_dir.clone().unwrap_or_else(|| {
-            pkg.root().join("target")
-        })
+    /// Returns the target directory configured through `--target-dir`,
+    /// `build.target-dir` or `CARGO_TARGET_DIR`, if any.
+    pub fn target_dir(&self) -> Option<PathBuf> {
+        self.target_dir.borrow().clone()
+    }
+
+    /// Configures the `--target-dir` flag, which takes precedence over the
+    /// configuration and the environment.
+    pub fn set_target_dir(&self, dir: &str) {
+        *self.target_dir.borrow_mut() = Some(self.cwd.join(dir));
+    }
+
+    /// Applies the unstable `-Z` flags passed on the command line.
+    pub fn configure_unstable_flags(&self, flags: &[String]) -> CargoResult<()> {
+        for flag in flags.iter() {
+            match &flag[..] {
+                "minimal-versions" => self.minimal_versions.set(true),
+                _ => return Err(human(format!("unknown `-Z` flag specified: {}",
+                                              flag))),
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the resolver should select the lowest versions which satisfy
+    /// each requirement rather than the highest.
+    pub fn minimal_versions(&self) -> bool { self.minimal_versions.get() }
+
+    /// Forbids network access for the rest of this invocation, as requested
+    /// by the `--offline` flag.
+    pub fn set_offline(&self, offline: bool) {
+        if offline {
+            self.offline.set(true);
+        }
+    }
+
+    /// Configures the `--locked` and `--frozen` flags. Being frozen implies
+    /// being both locked and offline.
+    pub fn set_locked(&self, locked: bool, frozen: bool) {
+        self.locked.set(locked || frozen);
+        self.frozen.set(frozen);
+    }
+
+    /// Whether `Cargo.lock` must be left as-is rather than being rewritten.
+    pub fn locked(&self) -> bool { self.locked.get() }
+
+    /// Whether `--frozen` was passed.
+    pub fn frozen(&self) -> bool { self.frozen.get() }
+
+    /// Whether Cargo must not touch the network, either because `--offline`
+    /// or `--frozen` was passed or because `net.offline` is set in the configuration.
+    pub fn offline(&self) -> CargoResult<bool> {
+        if self.offline.get() || self.frozen.get() {
+            return Ok(true)
+        }
+        Ok(try!(self.get_bool("net.offline")).map(|b| b.0).unwrap_or(false))
     }
 
     pub fn get(&self, key: &str) -> CargoResult<Option<ConfigValue>> {
@@ -146,6 +215,14 @@ impl Config {
         }
     }
 
+    pub fn get_bool(&self, key: &str) -> CargoResult<Option<(bool, PathBuf)>> {
+        match try!(self.get(key)) {
+            Some(CV::Boolean(b, path)) => Ok(Some((b, path))),
+            Some(val) => self.expected("bool", key, val),
+            None => Ok(None),
+        }
+    }
+
     pub fn get_path(&self, key: &str) -> CargoResult<Option<PathBuf>> {
         if let Some((specified_path, path_to_config)) = try!(self.get_string(&key)) {
             if specified_path.contains("/") || (cfg!(windows) && specified_path.contains("\\")) {
@@ -212,6 +289,23 @@ impl Config {
             Ok(())
         }).chain_error(|| human("Couldn't load Cargo configuration")));
 
+        // Credentials are kept out of the configuration files so those can be
+        // shared freely, and they take precedence over anything found there.
+        let credentials = self.home_path.join("credentials");
+        if fs::metadata(&credentials).is_ok() {
+            let mut contents = String::new();
+            try!(try!(File::open(&credentials))
+                     .read_to_string(&mut contents));
+            let table = try!(cargo_toml::parse(&contents, &credentials)
+                                        .chain_error(|| {
+                human(format!("could not parse TOML credentials in `{}`",
+                              credentials.display()))
+            }));
+            let mut value = try!(CV::from_toml(&credentials,
+                                               toml::Value::Table(table)));
+            try!(value.merge(cfg));
+            cfg = value;
+        }
 
         *self.values.borrow_mut() = match cfg {
             CV::Table(map, _) => map,
@@ -223,6 +317,15 @@ impl Config {
     fn scrape_tool_config(&mut self) -> CargoResult<()> {
         self.rustc = try!(self.get_tool("rustc"));
         self.rustdoc = try!(self.get_tool("rustdoc"));
+        self.rustc_wrapper = match try!(self.get_path("build.rustc-wrapper")) {
+            Some(path) => Some(path),
+            None => match env::var_os("RUSTC_WRAPPER") {
+                Some(ref path) if path.to_str() != Some("") => {
+                    Some(PathBuf::from(path))
+                }
+                _ => None,
+            },
+        };
         Ok(())
     }
 
@@ -237,9 +340,9 @@ impl Config {
             path.pop();
             path.pop();
             path.push(dir);
-            self.target_dir = Some(path);
+            *self.target_dir.borrow_mut() = Some(path);
         } else if let Some(dir) = env::var_os("CARGO_TARGET_DIR") {
-            self.target_dir = Some(self.cwd.join(dir));
+            *self.target_dir.borrow_mut() = Some(self.cwd.join(dir));
         }
         Ok(())
     }
@@ -497,6 +600,41 @@ fn walk_tree<F>(pwd: &Path, mut walk: F) -> CargoResult<()>
     Ok(())
 }
 
+/// Saves `token` as the API token for the registry in the `credentials` file
+/// of the Cargo home directory, which only the current user can read.
+pub fn save_credentials(cfg: &Config, token: String) -> CargoResult<()> {
+    let file = cfg.home_path.join("credentials");
+    try!(fs::create_dir_all(file.parent().unwrap()));
+    let mut contents = String::new();
+    let _ = File::open(&file).and_then(|mut f| f.read_to_string(&mut contents));
+    let mut toml = try!(cargo_toml::parse(&contents, &file));
+
+    let mut registry = match toml.remove("registry") {
+        Some(toml::Value::Table(table)) => table,
+        _ => toml::Table::new(),
+    };
+    registry.insert("token".to_string(), toml::Value::String(token));
+    toml.insert("registry".to_string(), toml::Value::Table(registry));
+
+    // Restrict the permissions before the token is written out
+    let mut out = try!(File::create(&file));
+    try!(restrict_permissions(&file));
+    try!(out.write_all(toml::Value::Table(toml).to_string().as_bytes()));
+    return Ok(());
+
+    #[cfg(unix)]
+    fn restrict_permissions(path: &Path) -> CargoResult<()> {
+        use std::os::unix::fs::PermissionsExt;
+        try!(fs::set_permissions(path, fs::Permissions::from_mode(0o600)));
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn restrict_permissions(_path: &Path) -> CargoResult<()> {
+        Ok(())
+    }
+}
+
 pub fn set_config(cfg: &Config, loc: Location, key: &str,
                   value: ConfigValue) -> CargoResult<()> {
     // TODO: There are a number of drawbacks here
@@ -0,0 +1,134 @@
+//! Advisory file locks guarding state shared between Cargo processes, such
+//! as a build directory or the caches in the Cargo home directory.
+//!
+//! Locks are taken on a dedicated lock file next to (or inside of) whatever
+//! they protect, and are released when the `FileLock` is dropped or the
+//! process exits.
+
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+
+use util::{CargoResult, ChainError, Config, human};
+
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Takes an exclusive lock on `path`, creating the file if needed. If
+    /// another process holds the lock already a message saying we're waiting
+    /// for the lock on `what` is printed before blocking.
+    pub fn exclusive(path: &Path, config: &Config, what: &str)
+                     -> CargoResult<FileLock> {
+        let file = try!((|| {
+            if let Some(parent) = path.parent() {
+                try!(fs::create_dir_all(parent));
+            }
+            OpenOptions::new().read(true).write(true).create(true).open(path)
+        })().chain_error(|| {
+            human(format!("failed to open lock file `{}`", path.display()))
+        }));
+
+        match sys::lock(&file, false) {
+            Ok(()) => {}
+            Err(ref e) if sys::is_contended(e) => {
+                try!(config.shell().status("Blocking",
+                     format!("waiting for file lock on {}", what)));
+                try!(sys::lock(&file, true).chain_error(|| {
+                    human(format!("failed to lock file `{}`", path.display()))
+                }));
+            }
+            Err(e) => {
+                return Err(e).chain_error(|| {
+                    human(format!("failed to lock file `{}`", path.display()))
+                })
+            }
+        }
+        Ok(FileLock { file: file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = sys::unlock(&self.file);
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::prelude::*;
+    use libc::c_int;
+
+    const LOCK_EX: c_int = 2;
+    const LOCK_NB: c_int = 4;
+    const LOCK_UN: c_int = 8;
+
+    extern {
+        fn flock(fd: c_int, operation: c_int) -> c_int;
+    }
+
+    pub fn lock(file: &File, block: bool) -> io::Result<()> {
+        flock_file(file, if block {LOCK_EX} else {LOCK_EX | LOCK_NB})
+    }
+
+    pub fn unlock(file: &File) -> io::Result<()> {
+        flock_file(file, LOCK_UN)
+    }
+
+    pub fn is_contended(err: &io::Error) -> bool {
+        err.kind() == io::ErrorKind::WouldBlock
+    }
+
+    fn flock_file(file: &File, op: c_int) -> io::Result<()> {
+        loop {
+            if unsafe { flock(file.as_raw_fd(), op) } == 0 {
+                return Ok(())
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err)
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    extern crate kernel32;
+    extern crate winapi;
+
+    use std::fs::File;
+    use std::io;
+    use std::mem;
+    use std::os::windows::prelude::*;
+
+    const LOCKFILE_FAIL_IMMEDIATELY: winapi::DWORD = 0x1;
+    const LOCKFILE_EXCLUSIVE_LOCK: winapi::DWORD = 0x2;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    pub fn lock(file: &File, block: bool) -> io::Result<()> {
+        let mut flags = LOCKFILE_EXCLUSIVE_LOCK;
+        if !block {
+            flags |= LOCKFILE_FAIL_IMMEDIATELY;
+        }
+        unsafe {
+            let mut overlapped = mem::zeroed();
+            let ret = kernel32::LockFileEx(file.as_raw_handle(), flags, 0,
+                                           !0, !0, &mut overlapped);
+            if ret == 0 {Err(io::Error::last_os_error())} else {Ok(())}
+        }
+    }
+
+    pub fn unlock(file: &File) -> io::Result<()> {
+        unsafe {
+            let ret = kernel32::UnlockFile(file.as_raw_handle(), 0, 0, !0, !0);
+            if ret == 0 {Err(io::Error::last_os_error())} else {Ok(())}
+        }
+    }
+
+    pub fn is_contended(err: &io::Error) -> bool {
+        err.raw_os_error() == Some(ERROR_LOCK_VIOLATION)
+    }
+}
@@ -0,0 +1,250 @@
+//! Integration with the jobserver of GNU make.
+//!
+//! When Cargo is run from a Makefile with `-jN`, make hands it a pipe holding
+//! one byte, a "token", for every job which may run in addition to the one
+//! Cargo itself was started as. Every compilation beyond the first one takes
+//! a token out of the pipe before it starts and puts it back when it's done,
+//! so the parallelism of the build as a whole stays within what make was
+//! told. Without a jobserver to join Cargo creates its own, which is then
+//! shared with build scripts and any `make` or `cargo` they run in turn.
+//!
+//! The jobserver is only supported on Unix, elsewhere the number of jobs is
+//! limited by Cargo alone.
+
+use std::env;
+use std::sync::{Arc, Mutex};
+
+use util::{CargoResult, ProcessBuilder};
+
+pub struct Client {
+    inner: imp::Client,
+    // Whether the token Cargo implicitly owns is currently unused
+    implicit: Mutex<bool>,
+}
+
+/// A token taken from the jobserver, which is given back when dropped.
+pub struct Acquired {
+    client: Arc<Client>,
+    token: Option<u8>,
+}
+
+impl Client {
+    /// Returns the jobserver to use for a build with `jobs` jobs: the one of
+    /// the make or cargo invoking us if there is one, a new one otherwise.
+    pub fn for_build(jobs: u32) -> CargoResult<Option<Arc<Client>>> {
+        let inner = match Client::from_env() {
+            Some(inner) => Some(inner),
+            None => try!(imp::Client::new(jobs.saturating_sub(1))),
+        };
+        Ok(inner.map(|inner| {
+            Arc::new(Client { inner: inner, implicit: Mutex::new(true) })
+        }))
+    }
+
+    fn from_env() -> Option<imp::Client> {
+        let vars = ["CARGO_MAKEFLAGS", "MAKEFLAGS", "MFLAGS"];
+        let flags = match vars.iter().filter_map(|v| env::var(v).ok()).next() {
+            Some(flags) => flags,
+            None => return None,
+        };
+        // Make has called the flag `--jobserver-fds` as well as
+        // `--jobserver-auth` over time, the last one given counts.
+        let fds = flags.split(' ').filter_map(|arg| {
+            let prefixes = ["--jobserver-fds=", "--jobserver-auth="];
+            prefixes.iter().filter(|p| arg.starts_with(**p)).map(|p| {
+                &arg[p.len()..]
+            }).next()
+        }).last();
+        fds.and_then(imp::Client::from_fds)
+    }
+
+    /// Blocks until a token is available and takes it.
+    pub fn acquire(client: &Arc<Client>) -> CargoResult<Acquired> {
+        loop {
+            {
+                let mut implicit = client.implicit.lock().unwrap();
+                if *implicit {
+                    *implicit = false;
+                    return Ok(Acquired { client: client.clone(), token: None })
+                }
+            }
+            // The implicit token may be released while we're waiting for the
+            // pipe, so the wait is only a short one before looking again.
+            if let Some(token) = try!(client.inner.acquire(100)) {
+                return Ok(Acquired { client: client.clone(), token: Some(token) })
+            }
+        }
+    }
+
+    /// Configures `cmd` to share this jobserver, for build scripts which run
+    /// `make` or `cargo` themselves.
+    pub fn configure(&self, cmd: &mut ProcessBuilder) {
+        let fds = self.inner.fds();
+        let flags = format!("-j --jobserver-fds={0} --jobserver-auth={0}", fds);
+        cmd.env("CARGO_MAKEFLAGS", &flags);
+        if env::var_os("MAKEFLAGS").is_none() {
+            cmd.env("MAKEFLAGS", &flags);
+        }
+    }
+}
+
+impl Drop for Acquired {
+    fn drop(&mut self) {
+        match self.token {
+            Some(token) => { let _ = self.client.inner.release(token); }
+            None => *self.client.implicit.lock().unwrap() = true,
+        }
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+    use libc::{c_int, c_short, c_void, size_t, ssize_t};
+
+    use util::{CargoResult, ChainError, human};
+
+    pub struct Client {
+        read: c_int,
+        write: c_int,
+        // Whether the pipe was created by us rather than inherited from make
+        owned: bool,
+    }
+
+    #[repr(C)]
+    struct pollfd {
+        fd: c_int,
+        events: c_short,
+        revents: c_short,
+    }
+
+    #[cfg(target_os = "macos")]
+    #[allow(non_camel_case_types)]
+    type nfds_t = ::libc::c_uint;
+    #[cfg(not(target_os = "macos"))]
+    #[allow(non_camel_case_types)]
+    type nfds_t = ::libc::c_ulong;
+
+    const POLLIN: c_short = 0x1;
+    const F_GETFD: c_int = 1;
+
+    extern {
+        fn pipe(fds: *mut c_int) -> c_int;
+        fn read(fd: c_int, buf: *mut c_void, count: size_t) -> ssize_t;
+        fn write(fd: c_int, buf: *const c_void, count: size_t) -> ssize_t;
+        fn poll(fds: *mut pollfd, nfds: nfds_t, timeout: c_int) -> c_int;
+        fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+        fn close(fd: c_int) -> c_int;
+    }
+
+    impl Client {
+        pub fn new(tokens: u32) -> CargoResult<Option<Client>> {
+            let mut fds = [0; 2];
+            if unsafe { pipe(fds.as_mut_ptr()) } != 0 {
+                return Err(io::Error::last_os_error()).chain_error(|| {
+                    human("failed to create the jobserver pipe")
+                })
+            }
+            let client = Client { read: fds[0], write: fds[1], owned: true };
+            for _ in 0..tokens {
+                try!(client.release(b'|').chain_error(|| {
+                    human("failed to fill the jobserver pipe")
+                }));
+            }
+            Ok(Some(client))
+        }
+
+        pub fn from_fds(fds: &str) -> Option<Client> {
+            let mut parts = fds.splitn(2, ',');
+            let read = parts.next().and_then(|s| s.parse().ok());
+            let write = parts.next().and_then(|s| s.parse().ok());
+            match (read, write) {
+                // Make only passes the pipe on to commands it knows to be
+                // recursive invocations, so the descriptors may be closed.
+                (Some(read), Some(write)) => unsafe {
+                    if fcntl(read, F_GETFD) == -1 || fcntl(write, F_GETFD) == -1 {
+                        None
+                    } else {
+                        Some(Client { read: read, write: write,
+                                      owned: false })
+                    }
+                },
+                _ => None,
+            }
+        }
+
+        pub fn fds(&self) -> String {
+            format!("{},{}", self.read, self.write)
+        }
+
+        /// Takes a token if one shows up within `timeout` milliseconds.
+        pub fn acquire(&self, timeout: c_int) -> CargoResult<Option<u8>> {
+            let mut fd = pollfd { fd: self.read, events: POLLIN, revents: 0 };
+            match unsafe { poll(&mut fd, 1, timeout) } {
+                0 => return Ok(None),
+                -1 => return self.os_error("failed to wait on the jobserver"),
+                _ => {}
+            }
+            let mut token = 0u8;
+            let n = unsafe {
+                read(self.read, &mut token as *mut u8 as *mut c_void, 1)
+            };
+            match n {
+                1 => Ok(Some(token)),
+                -1 => self.os_error("failed to read from the jobserver"),
+                _ => Err(human("the jobserver pipe was closed")),
+            }
+        }
+
+        // Being interrupted just means that no token was taken this time, as
+        // does EAGAIN: the pipe is shared with other processes which may
+        // have taken the token between our poll and read.
+        fn os_error(&self, msg: &'static str) -> CargoResult<Option<u8>> {
+            let err = io::Error::last_os_error();
+            match err.kind() {
+                io::ErrorKind::Interrupted |
+                io::ErrorKind::WouldBlock => Ok(None),
+                _ => Err(err).chain_error(|| human(msg)),
+            }
+        }
+
+        pub fn release(&self, token: u8) -> io::Result<()> {
+            let n = unsafe {
+                write(self.write, &token as *const u8 as *const c_void, 1)
+            };
+            if n == 1 {Ok(())} else {Err(io::Error::last_os_error())}
+        }
+    }
+
+    // The descriptors of an inherited jobserver belong to make and stay open,
+    // another build in this process may need them again.
+    impl Drop for Client {
+        fn drop(&mut self) {
+            if self.owned {
+                unsafe {
+                    close(self.read);
+                    close(self.write);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::io;
+
+    use util::CargoResult;
+
+    pub enum Client {}
+
+    impl Client {
+        pub fn new(_tokens: u32) -> CargoResult<Option<Client>> { Ok(None) }
+        pub fn from_fds(_fds: &str) -> Option<Client> { None }
+        pub fn fds(&self) -> String { match *self {} }
+        pub fn acquire(&self, _timeout: i32) -> CargoResult<Option<u8>> {
+            match *self {}
+        }
+        pub fn release(&self, _token: u8) -> io::Result<()> { match *self {} }
+    }
+}
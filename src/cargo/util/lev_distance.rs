@@ -41,6 +41,26 @@ pub fn lev_distance(me: &str, t: &str) -> usize {
     dcol[t_last + 1]
 }
 
+/// Returns the candidate closest to `choice`, as long as it's at most
+/// `max_distance` edits away from it.
+pub fn closest<'a, I>(choice: &str, candidates: I, max_distance: usize)
+                      -> Option<&'a str>
+    where I: IntoIterator<Item=&'a str>
+{
+    let mut best = None;
+    for candidate in candidates {
+        let distance = lev_distance(choice, candidate);
+        if distance > max_distance {
+            continue
+        }
+        match best {
+            Some((d, _)) if d <= distance => {}
+            _ => best = Some((distance, candidate)),
+        }
+    }
+    best.map(|(_, candidate)| candidate)
+}
+
 #[test]
 fn test_lev_distance() {
     use std::char::{ from_u32, MAX };
@@ -60,4 +80,7 @@ fn test_lev_distance() {
     assert_eq!(lev_distance(c, a), 2);
     assert_eq!(lev_distance(b, c), 1);
     assert_eq!(lev_distance(c, b), 1);
+
+    assert_eq!(closest(a, vec![b, c], 1), Some(b));
+    assert_eq!(closest(a, vec![c], 1), None);
 }
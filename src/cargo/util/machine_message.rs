@@ -0,0 +1,62 @@
+//! Messages printed on stdout with `--message-format json`, one JSON object
+//! per line, for tools driving Cargo which can't make sense of its usual
+//! output. Every object has a `reason` field naming what kind of message it
+//! is, the other fields depend on the kind.
+
+use rustc_serialize::Encodable;
+use rustc_serialize::json::{self, Json};
+
+use core::{PackageId, Profile, Target};
+
+pub trait Message: Encodable {
+    fn reason(&self) -> &str;
+}
+
+pub fn emit<T: Message>(t: &T) {
+    let mut json = Json::from_str(&json::encode(t).unwrap()).unwrap();
+    if let Json::Object(ref mut map) = json {
+        map.insert("reason".to_string(), Json::String(t.reason().to_string()));
+    }
+    println!("{}", json);
+}
+
+/// A diagnostic emitted by rustc while compiling `target`, `message` is the
+/// diagnostic as rustc serialized it.
+#[derive(RustcEncodable)]
+pub struct FromCompiler<'a> {
+    pub package_id: &'a PackageId,
+    pub target: &'a Target,
+    pub message: Json,
+}
+
+impl<'a> Message for FromCompiler<'a> {
+    fn reason(&self) -> &str { "compiler-message" }
+}
+
+/// The files produced for `target`, whether they were just built or found
+/// to be up to date.
+#[derive(RustcEncodable)]
+pub struct Artifact<'a> {
+    pub package_id: &'a PackageId,
+    pub target: &'a Target,
+    pub profile: &'a Profile,
+    pub features: Vec<String>,
+    pub filenames: Vec<String>,
+}
+
+impl<'a> Message for Artifact<'a> {
+    fn reason(&self) -> &str { "compiler-artifact" }
+}
+
+/// What a build script asked for, once it has run.
+#[derive(RustcEncodable)]
+pub struct BuildScript<'a> {
+    pub package_id: &'a PackageId,
+    pub linked_libs: &'a [String],
+    pub linked_paths: Vec<String>,
+    pub cfgs: &'a [String],
+}
+
+impl<'a> Message for BuildScript<'a> {
+    fn reason(&self) -> &str { "build-script-executed" }
+}
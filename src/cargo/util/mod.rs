@@ -1,3 +1,4 @@
+pub use self::cfg::{Cfg, CfgExpr};
 pub use self::config::Config;
 pub use self::dependency_queue::Dependency;
 pub use self::dependency_queue::{DependencyQueue, Fresh, Dirty, Freshness};
@@ -5,9 +6,10 @@ pub use self::errors::{CargoResult, CargoError, ChainError, CliResult};
 pub use self::errors::{CliError, ProcessError, CargoTestError};
 pub use self::errors::{Human, caused_human};
 pub use self::errors::{process_error, internal_error, internal, human};
+pub use self::flock::FileLock;
 pub use self::graph::Graph;
 pub use self::hex::{to_hex, short_hash};
-pub use self::lev_distance::{lev_distance};
+pub use self::lev_distance::{lev_distance, closest};
 pub use self::paths::{join_paths, path2bytes, bytes2path, dylib_path};
 pub use self::paths::{normalize_path, dylib_path_envvar, without_prefix};
 pub use self::process_builder::{process, ProcessBuilder};
@@ -17,11 +19,14 @@ pub use self::to_semver::ToSemver;
 pub use self::to_url::ToUrl;
 pub use self::vcs::{GitRepo, HgRepo};
 
+pub mod cfg;
 pub mod config;
 pub mod errors;
+pub mod flock;
 pub mod graph;
 pub mod hex;
 pub mod important_paths;
+pub mod jobserver;
 pub mod paths;
 pub mod process_builder;
 pub mod profile;
@@ -29,6 +34,8 @@ pub mod to_semver;
 pub mod to_url;
 pub mod toml;
 pub mod lev_distance;
+pub mod machine_message;
+pub mod network;
 mod dependency_queue;
 mod sha256;
 mod shell_escape;
@@ -0,0 +1,40 @@
+use std::thread;
+
+use util::{CargoResult, Config};
+
+/// Number of times a failed network operation is retried unless configured
+/// otherwise through `net.retry`.
+const DEFAULT_RETRIES: i64 = 2;
+
+/// Returns how many times network operations should be retried.
+pub fn retries(config: &Config) -> CargoResult<u32> {
+    let retries = try!(config.get_i64("net.retry")).map(|(n, _)| n)
+                                                   .unwrap_or(DEFAULT_RETRIES);
+    Ok(if retries < 0 {0} else {retries as u32})
+}
+
+/// Calls `f` until it succeeds, retrying up to `retries` times on failure.
+///
+/// The delay between attempts starts at half a second and doubles after
+/// every failed attempt. The error of the last attempt is returned if none
+/// of them succeed.
+#[allow(deprecated)] // sleep_ms => sleep in 1.4
+pub fn with_retry<T, F>(retries: u32, mut f: F) -> CargoResult<T>
+    where F: FnMut() -> CargoResult<T>
+{
+    let mut remaining = retries;
+    let mut delay = 500;
+    loop {
+        match f() {
+            Ok(t) => return Ok(t),
+            Err(e) => {
+                if remaining == 0 { return Err(e) }
+                debug!("network operation failed, retrying in {}ms: {}",
+                       delay, e);
+                thread::sleep_ms(delay);
+                delay *= 2;
+                remaining -= 1;
+            }
+        }
+    }
+}
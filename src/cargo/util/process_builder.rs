@@ -2,8 +2,11 @@ use std::collections::HashMap;
 use std::env;
 use std::ffi::{OsString, OsStr};
 use std::fmt;
+use std::io::{self, BufReader};
+use std::io::prelude::*;
 use std::path::Path;
-use std::process::{Command, Output};
+use std::process::{Command, Output, Stdio};
+use std::thread;
 
 use util::{CargoResult, ProcessError, process_error};
 use util::shell_escape::escape;
@@ -104,6 +107,74 @@ impl ProcessBuilder {
         }
     }
 
+    /// Like `exec_with_output`, but passes each line the process writes to
+    /// stderr on to `on_stderr_line` as soon as it's written, without its
+    /// line ending.
+    pub fn exec_with_streaming(&self,
+                               on_stderr_line: &mut FnMut(&str) -> io::Result<()>)
+                               -> Result<Output, ProcessError> {
+        let mut command = self.build_command();
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let mut child = try!(command.spawn().map_err(|e| {
+            process_error(&format!("Could not execute process `{}`",
+                                   self.debug_string()),
+                          Some(e), None, None)
+        }));
+
+        // stdout is read on a thread of its own so the process can't block
+        // on a full pipe which nobody reads.
+        let mut out = child.stdout.take().unwrap();
+        let stdout = thread::spawn(move || {
+            let mut stdout = Vec::new();
+            out.read_to_end(&mut stdout).map(|_| stdout)
+        });
+
+        let mut stderr = Vec::new();
+        let mut res = Ok(());
+        {
+            let mut err = BufReader::new(child.stderr.take().unwrap());
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match err.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(..) => {}
+                    Err(e) => {
+                        let _ = child.kill();
+                        res = Err(e);
+                        break
+                    }
+                }
+                stderr.extend(line.iter().cloned());
+                if res.is_ok() {
+                    let line = String::from_utf8_lossy(&line);
+                    res = on_stderr_line(line.trim_right_matches(|c: char| {
+                        c == '\n' || c == '\r'
+                    }));
+                }
+            }
+        }
+
+        let status = child.wait();
+        let stdout = stdout.join().unwrap_or(Ok(Vec::new()));
+        let output = try!(status.and_then(|status| {
+            try!(res);
+            Ok(Output { status: status, stdout: try!(stdout), stderr: stderr })
+        }).map_err(|e| {
+            process_error(&format!("Could not read the output of process `{}`",
+                                   self.debug_string()),
+                          Some(e), None, None)
+        }));
+
+        if output.status.success() {
+            Ok(output)
+        } else {
+            Err(process_error(&format!("Process didn't exit successfully: `{}`",
+                                       self.debug_string()),
+                              None, Some(&output.status), Some(&output)))
+        }
+    }
+
     pub fn build_command(&self) -> Command {
         let mut command = Command::new(&self.program);
         command.current_dir(&self.cwd);
@@ -1,11 +1,14 @@
 use std::path::Path;
 
+use semver::Version;
+
 use util::{self, CargoResult, internal, ChainError};
 
 pub struct Rustc {
     pub verbose_version: String,
     pub host: String,
     pub cap_lints: bool,
+    pub version: Option<Version>,
 }
 
 impl Rustc {
@@ -37,6 +40,9 @@ impl Rustc {
             }));
             triple.to_string()
         };
+        ret.version = ret.verbose_version.lines().filter(|l| {
+            l.starts_with("release: ")
+        }).filter_map(|l| Version::parse(&l[9..]).ok()).next();
         Ok(ret)
     }
 
@@ -45,6 +51,7 @@ impl Rustc {
             verbose_version: String::new(),
             host: String::new(),
             cap_lints: false,
+            version: None,
         }
     }
 }
@@ -1,19 +1,21 @@
 use std::collections::HashMap;
 use std::default::Default;
 use std::fmt;
-use std::fs;
+use std::fs::{self, File};
+use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 use std::str;
 
 use toml;
-use semver;
+use semver::{self, VersionReq};
 use rustc_serialize::{Decodable, Decoder};
 
-use core::{SourceId, Profiles};
+use core::{SourceId, Profiles, PackageIdSpec};
 use core::{Summary, Manifest, Target, Dependency, DependencyInner, PackageId,
            GitReference};
-use core::dependency::Kind;
+use core::dependency::{Kind, Platform};
 use core::manifest::{LibKind, Profile, ManifestMetadata};
+use core::manifest::{EitherManifest, VirtualManifest, WorkspaceConfig};
 use core::package_id::Metadata;
 use util::{self, CargoResult, human, ToUrl, ToSemver, ChainError, Config};
 
@@ -106,7 +108,7 @@ pub fn to_manifest(contents: &[u8],
                    source_id: &SourceId,
                    layout: Layout,
                    config: &Config)
-                   -> CargoResult<(Manifest, Vec<PathBuf>)> {
+                   -> CargoResult<(EitherManifest, Vec<PathBuf>)> {
     let manifest = layout.root.join("Cargo.toml");
     let manifest = match util::without_prefix(&manifest, config.cwd()) {
         Some(path) => path.to_path_buf(),
@@ -121,24 +123,72 @@ pub fn to_manifest(contents: &[u8],
         human(e.to_string())
     }));
 
-    let pair = try!(manifest.to_manifest(source_id, &layout, config));
-    let (mut manifest, paths) = pair;
+    let mut unused_keys = Vec::new();
     match d.toml {
-        Some(ref toml) => add_unused_keys(&mut manifest, toml, "".to_string()),
+        Some(ref toml) => add_unused_keys(&mut unused_keys, toml,
+                                          "".to_string()),
         None => {}
     }
+
+    // Unknown keys in a dependency usually mean it's not going to be what was
+    // intended (e.g. a misspelled `version`), so these are an error.
+    for key in unused_keys.iter() {
+        if let Some((dep, field)) = dependency_key(key) {
+            let mut msg = format!("unknown key `{}` in dependency `{}`",
+                                  field, dep);
+            if let Some(known) = util::closest(field, DEPENDENCY_KEYS.iter()
+                                                          .map(|s| *s), 2) {
+                msg.push_str(&format!(", did you mean `{}`?", known));
+            }
+            return Err(human(msg))
+        }
+    }
+    let unused = unused_keys.iter().map(|key| {
+        let mut msg = format!("unused manifest key: {}", key);
+        if let Some(known) = suggest_key(key) {
+            msg.push_str(&format!(", did you mean `{}`?", known));
+        }
+        msg
+    }).collect::<Vec<_>>();
+
+    if manifest.project.is_none() && manifest.package.is_none() {
+        if manifest.workspace.is_none() {
+            let keys = root_keys(&d);
+            let typo = keys.iter().filter_map(|k| {
+                util::closest(k, vec!["package", "project"], 2).map(|c| (k, c))
+            }).next();
+            if let Some((found, expected)) = typo {
+                return Err(human(format!("no `[package]` section found, but \
+                                          found `[{}]`, did you mean \
+                                          `[{}]`?", found, expected)))
+            }
+        }
+        let mut manifest = try!(manifest.to_virtual_manifest(source_id,
+                                                             config));
+        for warning in unused {
+            manifest.add_warning(warning);
+        }
+        return Ok((EitherManifest::Virtual(manifest), Vec::new()))
+    }
+
+    let pair = try!(manifest.to_manifest(source_id, &layout, config));
+    let (mut manifest, paths) = pair;
+    for warning in unused {
+        manifest.add_warning(warning);
+    }
     if !manifest.targets().iter().any(|t| !t.is_custom_build()) {
         return Err(human(format!("no targets specified in the manifest\n  either \
                                   src/lib.rs, src/main.rs, a [lib] section, or [[bin]] \
                                   section must be present")))
     }
-    return Ok((manifest, paths));
+    return Ok((EitherManifest::Real(manifest), paths));
 
-    fn add_unused_keys(m: &mut Manifest, toml: &toml::Value, key: String) {
+    fn add_unused_keys(keys: &mut Vec<String>, toml: &toml::Value,
+                       key: String) {
         match *toml {
             toml::Value::Table(ref table) => {
                 for (k, v) in table.iter() {
-                    add_unused_keys(m, v, if key.len() == 0 {
+                    add_unused_keys(keys, v, if key.len() == 0 {
                         k.clone()
                     } else {
                         key.clone() + "." + k
@@ -147,14 +197,107 @@ pub fn to_manifest(contents: &[u8],
             }
             toml::Value::Array(ref arr) => {
                 for v in arr.iter() {
-                    add_unused_keys(m, v, key.clone());
+                    add_unused_keys(keys, v, key.clone());
                 }
             }
-            _ => m.add_warning(format!("unused manifest key: {}", key)),
+            _ => keys.push(key),
+        }
+    }
+
+    fn root_keys(d: &toml::Decoder) -> Vec<String> {
+        match d.toml {
+            Some(toml::Value::Table(ref table)) => {
+                table.keys().cloned().collect()
+            }
+            _ => Vec::new(),
         }
     }
 }
 
+const MANIFEST_KEYS: &'static [&'static str] = &[
+    "package", "project", "profile", "lib", "bin", "example", "test", "bench",
+    "dependencies", "dev-dependencies", "build-dependencies", "features",
+    "target", "replace", "workspace",
+];
+const PROJECT_KEYS: &'static [&'static str] = &[
+    "name", "version", "authors", "build", "links", "exclude", "include",
+    "workspace", "description", "homepage", "documentation", "readme",
+    "keywords", "license", "license-file", "repository", "metadata",
+    "rust-version", "default-run", "publish",
+];
+const TARGET_KEYS: &'static [&'static str] = &[
+    "name", "crate-type", "path", "test", "doctest", "bench", "doc", "plugin",
+    "harness", "required-features",
+];
+const PROFILE_KEYS: &'static [&'static str] = &[
+    "opt-level", "lto", "codegen-units", "debug", "debug-assertions", "rpath",
+];
+const DEPENDENCY_KEYS: &'static [&'static str] = &[
+    "version", "path", "git", "branch", "tag", "rev", "features", "optional",
+    "default-features", "package", "workspace", "registry", "registry-index",
+];
+const WORKSPACE_KEYS: &'static [&'static str] = &["members", "dependencies"];
+const DEPENDENCY_TABLES: &'static [&'static str] = &[
+    "dependencies", "dev-dependencies", "build-dependencies",
+];
+
+/// If the dotted path of an unused key points into a detailed dependency
+/// table, returns the name of the dependency and the unknown key.
+fn dependency_key(key: &str) -> Option<(&str, &str)> {
+    // Package ID specs in `[replace]` contain dots themselves.
+    if key.starts_with("replace.") {
+        let mut parts = key["replace.".len()..].rsplitn(2, '.');
+        let field = parts.next().unwrap();
+        return parts.next().map(|spec| (spec, field))
+    }
+    let path = key.split('.').collect::<Vec<_>>();
+    let is_deps = |s: &str| DEPENDENCY_TABLES.iter().any(|t| *t == s);
+    match path.len() {
+        3 if is_deps(path[0]) => {
+            Some((path[1], path[2]))
+        }
+        4 if path[0] == "workspace" && path[1] == "dependencies" => {
+            Some((path[2], path[3]))
+        }
+        5 if path[0] == "target" && is_deps(path[2]) => {
+            Some((path[3], path[4]))
+        }
+        _ => None,
+    }
+}
+
+/// Given the dotted path of a key which wasn't used when decoding a manifest,
+/// returns the known key at the same position that it's most likely a
+/// misspelling of.
+fn suggest_key(key: &str) -> Option<&'static str> {
+    let path = key.split('.').collect::<Vec<_>>();
+    let last = match path.last() {
+        Some(last) => *last,
+        None => return None,
+    };
+    let parents = &path[..path.len() - 1];
+    let is_deps = |s: &str| DEPENDENCY_TABLES.iter().any(|t| *t == s);
+    let known = match parents.len() {
+        0 => MANIFEST_KEYS,
+        1 => match parents[0] {
+            "package" | "project" => PROJECT_KEYS,
+            "lib" | "bin" | "example" | "test" | "bench" => TARGET_KEYS,
+            "workspace" => WORKSPACE_KEYS,
+            _ => return None,
+        },
+        2 => match parents[0] {
+            "profile" => PROFILE_KEYS,
+            "target" => DEPENDENCY_TABLES,
+            "replace" => DEPENDENCY_KEYS,
+            table if is_deps(table) => DEPENDENCY_KEYS,
+            _ => return None,
+        },
+        4 if parents[0] == "target" && is_deps(parents[2]) => DEPENDENCY_KEYS,
+        _ => return None,
+    };
+    util::closest(last, known.iter().map(|s| *s), 2)
+}
+
 pub fn parse(toml: &str, file: &Path) -> CargoResult<toml::Table> {
     let mut parser = toml::Parser::new(&toml);
     match parser.parse() {
@@ -189,7 +332,7 @@ type TomlBenchTarget = TomlTarget;
  * TODO: Make all struct fields private
  */
 
-#[derive(RustcDecodable)]
+#[derive(RustcDecodable, Clone)]
 pub enum TomlDependency {
     Simple(String),
     Detailed(DetailedTomlDependency)
@@ -207,6 +350,10 @@ pub struct DetailedTomlDependency {
     features: Option<Vec<String>>,
     optional: Option<bool>,
     default_features: Option<bool>,
+    package: Option<String>,
+    workspace: Option<bool>,
+    registry: Option<String>,
+    registry_index: Option<String>,
 }
 
 #[derive(RustcDecodable)]
@@ -224,6 +371,8 @@ pub struct TomlManifest {
     build_dependencies: Option<HashMap<String, TomlDependency>>,
     features: Option<HashMap<String, Vec<String>>>,
     target: Option<HashMap<String, TomlPlatform>>,
+    replace: Option<HashMap<String, TomlDependency>>,
+    workspace: Option<TomlWorkspace>,
 }
 
 #[derive(RustcDecodable, Clone, Default)]
@@ -254,6 +403,7 @@ pub struct TomlProject {
     links: Option<String>,
     exclude: Option<Vec<String>>,
     include: Option<Vec<String>>,
+    workspace: Option<String>,
 
     // package metadata
     description: Option<String>,
@@ -264,6 +414,29 @@ pub struct TomlProject {
     license: Option<String>,
     license_file: Option<String>,
     repository: Option<String>,
+    metadata: Option<toml::Value>,
+    rust_version: Option<String>,
+    default_run: Option<String>,
+    publish: Option<TomlPublish>,
+}
+
+#[derive(RustcDecodable)]
+pub enum TomlPublish {
+    Flag(bool),
+    Registries(Vec<String>),
+}
+
+#[derive(RustcDecodable)]
+pub struct TomlWorkspace {
+    members: Option<Vec<String>>,
+    dependencies: Option<HashMap<String, TomlDependency>>,
+}
+
+/// The `[workspace.dependencies]` table of a workspace root, which members can
+/// inherit dependencies from with `workspace = true`.
+struct InheritedDeps {
+    root: PathBuf,
+    deps: HashMap<String, TomlDependency>,
 }
 
 pub struct TomlVersion {
@@ -292,6 +465,7 @@ struct Context<'a, 'b> {
     source_id: &'a SourceId,
     nested_paths: &'a mut Vec<PathBuf>,
     config: &'b Config,
+    inherited: Option<&'a InheritedDeps>,
 }
 
 // These functions produce the equivalent of specific manifest entries. One
@@ -366,6 +540,170 @@ fn inferred_bench_targets(layout: &Layout) -> Vec<TomlTarget> {
 }
 
 impl TomlManifest {
+    fn to_virtual_manifest(&self, source_id: &SourceId, config: &Config)
+                           -> CargoResult<VirtualManifest> {
+        if self.lib.is_some() {
+            return Err(human("virtual manifests do not specify [lib]"))
+        }
+        if self.bin.is_some() {
+            return Err(human("virtual manifests do not specify [[bin]]"))
+        }
+        if self.example.is_some() {
+            return Err(human("virtual manifests do not specify [[example]]"))
+        }
+        if self.test.is_some() {
+            return Err(human("virtual manifests do not specify [[test]]"))
+        }
+        if self.bench.is_some() {
+            return Err(human("virtual manifests do not specify [[bench]]"))
+        }
+        if self.dependencies.is_some() || self.dev_dependencies.is_some() ||
+           self.build_dependencies.is_some() || self.target.is_some() {
+            return Err(human("virtual manifests do not specify dependencies"))
+        }
+        if self.features.is_some() {
+            return Err(human("virtual manifests do not specify [features]"))
+        }
+
+        let workspace = match self.workspace {
+            Some(ref config) => {
+                WorkspaceConfig::Root { members: config.members.clone() }
+            }
+            None => {
+                return Err(human("virtual manifests must be configured with \
+                                  [workspace]"))
+            }
+        };
+        let replace = {
+            let mut nested_paths = Vec::new();
+            let mut deps = Vec::new();
+            let mut cx = Context {
+                deps: &mut deps,
+                source_id: source_id,
+                nested_paths: &mut nested_paths,
+                config: config,
+                inherited: None,
+            };
+            try!(self.replace(&mut cx))
+        };
+        Ok(VirtualManifest::new(build_profiles(&self.profile), workspace,
+                                replace))
+    }
+
+    fn replace(&self, cx: &mut Context)
+               -> CargoResult<Vec<(PackageIdSpec, Dependency)>> {
+        let replace = match self.replace {
+            Some(ref replace) => replace,
+            None => return Ok(Vec::new()),
+        };
+        let mut ret = Vec::new();
+        for (spec, replacement) in replace.iter() {
+            let spec = try!(PackageIdSpec::parse(spec));
+            let version = match spec.version() {
+                Some(v) => v.to_string(),
+                None => {
+                    return Err(human(format!("replacements must specify a \
+                                              version to replace, but `{}` \
+                                              does not", spec)))
+                }
+            };
+            match *replacement {
+                TomlDependency::Detailed(ref d) if d.version.is_none() &&
+                                                   (d.git.is_some() ||
+                                                    d.path.is_some()) => {}
+                TomlDependency::Detailed(ref d) if d.version.is_some() => {
+                    return Err(human(format!("replacements cannot specify a \
+                                              version requirement, but found \
+                                              one for `{}`", spec)))
+                }
+                _ => {
+                    return Err(human(format!("replacements must specify a \
+                                              `git` or `path` source, but \
+                                              `{}` does not", spec)))
+                }
+            }
+            let dep = try!(to_dependency(cx, spec.name(), replacement));
+            let req = try!(VersionReq::parse(&format!("={}", version)));
+            ret.push((spec, dep.set_version_req(req).into_dependency()));
+        }
+        Ok(ret)
+    }
+
+    /// Returns whether any dependency of this manifest is inherited from the
+    /// workspace with `workspace = true`.
+    fn uses_workspace_deps(&self) -> bool {
+        fn any(deps: Option<&HashMap<String, TomlDependency>>) -> bool {
+            deps.map(|deps| deps.values().any(|dep| {
+                match *dep {
+                    TomlDependency::Detailed(ref d) => d.workspace.is_some(),
+                    TomlDependency::Simple(..) => false,
+                }
+            })).unwrap_or(false)
+        }
+
+        any(self.dependencies.as_ref()) ||
+            any(self.dev_dependencies.as_ref()) ||
+            any(self.build_dependencies.as_ref()) ||
+            self.target.as_ref().map(|targets| {
+                targets.values().any(|p| {
+                    any(p.dependencies.as_ref()) ||
+                        any(p.dev_dependencies.as_ref()) ||
+                        any(p.build_dependencies.as_ref())
+                })
+            }).unwrap_or(false)
+    }
+
+    /// Locates the root of the workspace that the package at `root` belongs
+    /// to, in the same way as `Workspace` does, and returns the dependencies
+    /// its members may inherit.
+    fn find_inherited_deps(&self, root: &Path, project: &TomlProject)
+                           -> CargoResult<Option<InheritedDeps>> {
+        if let Some(ref workspace) = self.workspace {
+            return Ok(Some(InheritedDeps {
+                root: root.to_path_buf(),
+                deps: workspace.dependencies.clone().unwrap_or(HashMap::new()),
+            }))
+        }
+
+        let candidates = match project.workspace {
+            Some(ref path) => vec![util::normalize_path(&root.join(path))],
+            None => {
+                let mut ret = Vec::new();
+                let mut cur = root.parent();
+                while let Some(path) = cur {
+                    ret.push(path.to_path_buf());
+                    cur = path.parent();
+                }
+                ret
+            }
+        };
+        for dir in candidates {
+            let manifest = dir.join("Cargo.toml");
+            if fs::metadata(&manifest).is_err() {
+                continue
+            }
+            let mut contents = String::new();
+            try!(File::open(&manifest).and_then(|mut f| {
+                f.read_to_string(&mut contents)
+            }).chain_error(|| {
+                human(format!("failed to read `{}`", manifest.display()))
+            }));
+            let toml = try!(parse(&contents, &manifest));
+            let mut d = toml::Decoder::new(toml::Value::Table(toml));
+            let parsed: TomlManifest = try!(Decodable::decode(&mut d).map_err(|e| {
+                human(format!("failed to parse manifest at `{}`: {}",
+                              manifest.display(), e))
+            }));
+            if let Some(workspace) = parsed.workspace {
+                return Ok(Some(InheritedDeps {
+                    root: dir,
+                    deps: workspace.dependencies.unwrap_or(HashMap::new()),
+                }))
+            }
+        }
+        Ok(None)
+    }
+
     pub fn to_manifest(&self, source_id: &SourceId, layout: &Layout,
                        config: &Config)
         -> CargoResult<(Manifest, Vec<PathBuf>)> {
@@ -483,6 +821,12 @@ impl TomlManifest {
         }
 
         let mut deps = Vec::new();
+        let replace;
+        let inherited = if self.uses_workspace_deps() {
+            try!(self.find_inherited_deps(&layout.root, project))
+        } else {
+            None
+        };
 
         {
 
@@ -491,6 +835,7 @@ impl TomlManifest {
                 source_id: source_id,
                 nested_paths: &mut nested_paths,
                 config: config,
+                inherited: inherited.as_ref(),
             };
 
             // Collect the deps
@@ -503,25 +848,28 @@ impl TomlManifest {
 
             if let Some(targets) = self.target.as_ref() {
                 for (name, platform) in targets.iter() {
+                    let name = try!(name.parse::<Platform>());
                     try!(process_dependencies(&mut cx,
                                               platform.dependencies.as_ref(),
                                               |dep| {
-                        dep.set_only_for_platform(Some(name.clone()))
+                        dep.set_platform(Some(name.clone()))
                     }));
                     try!(process_dependencies(&mut cx,
                                               platform.build_dependencies.as_ref(),
                                               |dep| {
-                        dep.set_only_for_platform(Some(name.clone()))
+                        dep.set_platform(Some(name.clone()))
                            .set_kind(Kind::Build)
                     }));
                     try!(process_dependencies(&mut cx,
                                               platform.dev_dependencies.as_ref(),
                                               |dep| {
-                        dep.set_only_for_platform(Some(name.clone()))
+                        dep.set_platform(Some(name.clone()))
                            .set_kind(Kind::Development)
                     }));
                 }
             }
+
+            replace = try!(self.replace(&mut cx));
         }
 
         let exclude = project.exclude.clone().unwrap_or(Vec::new());
@@ -541,18 +889,64 @@ impl TomlManifest {
             repository: project.repository.clone(),
             keywords: project.keywords.clone().unwrap_or(Vec::new()),
         };
+        let rust_version = match project.rust_version {
+            // Allow the patch version to be omitted, e.g. `1.3`.
+            Some(ref v) if v.split('.').count() == 2 => {
+                Some(try!(format!("{}.0", v).to_semver().map_err(human)))
+            }
+            Some(ref v) => Some(try!(v.to_semver().map_err(human))),
+            None => None,
+        };
+        let publish = match project.publish {
+            Some(TomlPublish::Flag(true)) | None => None,
+            Some(TomlPublish::Flag(false)) => Some(Vec::new()),
+            Some(TomlPublish::Registries(ref registries)) => {
+                Some(registries.clone())
+            }
+        };
         let profiles = build_profiles(&self.profile);
+        let workspace_config = match (self.workspace.as_ref(),
+                                      project.workspace.as_ref()) {
+            (Some(config), None) => {
+                WorkspaceConfig::Root { members: config.members.clone() }
+            }
+            (None, root) => {
+                WorkspaceConfig::Member { root: root.cloned() }
+            }
+            (Some(..), Some(..)) => {
+                return Err(human("cannot configure both `package.workspace` and \
+                                  `[workspace]`, only one can be specified"))
+            }
+        };
+        if let Some(ref name) = project.default_run {
+            if !targets.iter().any(|t| t.is_bin() && t.name() == &name[..]) {
+                return Err(human(format!("`default-run` target `{}` is not \
+                                          one of the binaries of this \
+                                          package", name)))
+            }
+        }
         let mut manifest = Manifest::new(summary,
                                          targets,
                                          exclude,
                                          include,
                                          project.links.clone(),
                                          metadata,
-                                         profiles);
+                                         profiles,
+                                         workspace_config,
+                                         replace,
+                                         project.metadata.clone(),
+                                         rust_version,
+                                         project.default_run.clone(),
+                                         publish);
         if project.license_file.is_some() && project.license.is_some() {
             manifest.add_warning(format!("warning: only one of `license` or \
                                                    `license-file` is necessary"));
         }
+        if self.project.is_some() && self.package.is_some() {
+            manifest.add_warning(format!("warning: manifest has both a \
+                                          `[package]` and a `[project]` \
+                                          section, `[package]` is ignored"));
+        }
         for warning in warnings {
             manifest.add_warning(warning.clone());
         }
@@ -639,47 +1033,156 @@ fn process_dependencies<F>(cx: &mut Context,
         None => return Ok(())
     };
     for (n, v) in dependencies.iter() {
-        let details = match *v {
-            TomlDependency::Simple(ref version) => {
-                let mut d: DetailedTomlDependency = Default::default();
-                d.version = Some(version.clone());
-                d
-            }
-            TomlDependency::Detailed(ref details) => details.clone(),
-        };
-        let reference = details.branch.clone().map(GitReference::Branch)
-            .or_else(|| details.tag.clone().map(GitReference::Tag))
-            .or_else(|| details.rev.clone().map(GitReference::Rev))
-            .unwrap_or_else(|| GitReference::Branch("master".to_string()));
-
-        let new_source_id = match details.git {
-            Some(ref git) => {
-                let loc = try!(git.to_url().map_err(|e| {
-                    human(e)
-                }));
-                Some(SourceId::for_git(&loc, reference))
-            }
-            None => {
-                details.path.as_ref().map(|path| {
-                    cx.nested_paths.push(PathBuf::from(path));
-                    cx.source_id.clone()
-                })
-            }
-        }.unwrap_or(try!(SourceId::for_central(cx.config)));
+        let dep = try!(to_dependency(cx, n, v));
+        cx.deps.push(f(dep).into_dependency());
+    }
+
+    Ok(())
+}
+
+/// Fills in a dependency specified with `workspace = true` from the
+/// `[workspace.dependencies]` table of the workspace root. Members may only
+/// add features or make the dependency optional.
+fn inherit_dependency(cx: &Context, name: &str,
+                      member: DetailedTomlDependency)
+                      -> CargoResult<DetailedTomlDependency> {
+    let overridden = [
+        ("version", member.version.is_some()),
+        ("path", member.path.is_some()),
+        ("git", member.git.is_some()),
+        ("branch", member.branch.is_some()),
+        ("tag", member.tag.is_some()),
+        ("rev", member.rev.is_some()),
+        ("default-features", member.default_features.is_some()),
+        ("package", member.package.is_some()),
+        ("registry", member.registry.is_some()),
+        ("registry-index", member.registry_index.is_some()),
+    ];
+    if let Some(&(key, _)) = overridden.iter().find(|&&(_, set)| set) {
+        return Err(human(format!("dependency `{}` is inherited from the \
+                                  workspace and cannot also specify `{}`",
+                                 name, key)))
+    }
+
+    let inherited = match cx.inherited {
+        Some(inherited) => inherited,
+        None => {
+            return Err(human(format!("dependency `{}` is inherited from the \
+                                      workspace, but no workspace root was \
+                                      found", name)))
+        }
+    };
+    let mut details = match inherited.deps.get(name) {
+        Some(&TomlDependency::Simple(ref version)) => {
+            let mut d: DetailedTomlDependency = Default::default();
+            d.version = Some(version.clone());
+            d
+        }
+        Some(&TomlDependency::Detailed(ref details)) => details.clone(),
+        None => {
+            return Err(human(format!("dependency `{}` is inherited from the \
+                                      workspace, but it isn't listed in \
+                                      `[workspace.dependencies]` of `{}`",
+                                     name,
+                                     inherited.root.join("Cargo.toml")
+                                              .display())))
+        }
+    };
+    if details.workspace.is_some() {
+        return Err(human(format!("dependency `{}` in \
+                                  `[workspace.dependencies]` cannot itself \
+                                  specify `workspace`", name)))
+    }
+
+    // Paths in the workspace root are relative to the root, not the member.
+    if let Some(path) = details.path.take() {
+        let path = inherited.root.join(path);
+        details.path = Some(path.to_string_lossy().into_owned());
+    }
+    if let Some(features) = member.features {
+        let mut all = details.features.take().unwrap_or(Vec::new());
+        all.extend(features.into_iter());
+        details.features = Some(all);
+    }
+    if member.optional.is_some() {
+        details.optional = member.optional;
+    }
+    Ok(details)
+}
+
+fn to_dependency(cx: &mut Context, name: &str, dep: &TomlDependency)
+                 -> CargoResult<DependencyInner> {
+    let mut details = match *dep {
+        TomlDependency::Simple(ref version) => {
+            let mut d: DetailedTomlDependency = Default::default();
+            d.version = Some(version.clone());
+            d
+        }
+        TomlDependency::Detailed(ref details) => details.clone(),
+    };
+    if details.workspace == Some(true) {
+        details = try!(inherit_dependency(cx, name, details));
+    } else if details.workspace.is_some() {
+        return Err(human(format!("`workspace` cannot be false for dependency \
+                                  `{}`, either set it to true or remove it",
+                                 name)))
+    }
+    let reference = details.branch.clone().map(GitReference::Branch)
+        .or_else(|| details.tag.clone().map(GitReference::Tag))
+        .or_else(|| details.rev.clone().map(GitReference::Rev))
+        .unwrap_or_else(|| GitReference::Branch("master".to_string()));
+
+    if details.registry.is_some() &&
+       (details.git.is_some() || details.path.is_some()) {
+        return Err(human(format!("dependency `{}` cannot specify a \
+                                  `registry` together with `git` or `path`",
+                                 name)))
+    }
+    if details.registry_index.is_some() &&
+       (details.registry.is_some() || details.git.is_some() ||
+        details.path.is_some()) {
+        return Err(human(format!("dependency `{}` cannot specify a \
+                                  `registry-index` together with `registry`, \
+                                  `git` or `path`", name)))
+    }
 
-        let dep = try!(DependencyInner::parse(&n,
+    let new_source_id = match details.git {
+        Some(ref git) => {
+            let loc = try!(git.to_url().map_err(|e| {
+                human(e)
+            }));
+            Some(SourceId::for_git(&loc, reference))
+        }
+        None => {
+            details.path.as_ref().map(|path| {
+                cx.nested_paths.push(PathBuf::from(path));
+                cx.source_id.clone()
+            })
+        }
+    };
+    let new_source_id = match (new_source_id, details.registry.as_ref(),
+                               details.registry_index.as_ref()) {
+        (Some(id), _, _) => id,
+        (None, Some(registry), _) => {
+            try!(SourceId::for_alt_registry(registry, cx.config))
+        }
+        (None, None, Some(index)) => {
+            SourceId::for_registry(&try!(index.to_url().map_err(human)))
+        }
+        (None, None, None) => try!(SourceId::for_central(cx.config)),
+    };
+
+    let package = details.package.as_ref().map(|s| &s[..]).unwrap_or(name);
+    let mut dep = try!(DependencyInner::parse(package,
                                               details.version.as_ref()
                                                   .map(|v| &v[..]),
                                               &new_source_id));
-        let dep = f(dep)
-                     .set_features(details.features.unwrap_or(Vec::new()))
-                     .set_default_features(details.default_features.unwrap_or(true))
-                     .set_optional(details.optional.unwrap_or(false))
-                     .into_dependency();
-        cx.deps.push(dep);
+    if details.package.is_some() {
+        dep = dep.set_rename(name);
     }
-
-    Ok(())
+    Ok(dep.set_features(details.features.unwrap_or(Vec::new()))
+          .set_default_features(details.default_features.unwrap_or(true))
+          .set_optional(details.optional.unwrap_or(false)))
 }
 
 #[derive(RustcDecodable, Debug, Clone)]
@@ -693,6 +1196,7 @@ struct TomlTarget {
     doc: Option<bool>,
     plugin: Option<bool>,
     harness: Option<bool>,
+    required_features: Option<Vec<String>>,
 }
 
 #[derive(RustcDecodable, Clone)]
@@ -765,7 +1269,8 @@ fn normalize(lib: &Option<TomlLibTarget>,
               .set_doctest(toml.doctest.unwrap_or(t2.doctested()))
               .set_benched(toml.bench.unwrap_or(t2.benched()))
               .set_harness(toml.harness.unwrap_or(t2.harness()))
-              .set_for_host(toml.plugin.unwrap_or(t2.for_host()));
+              .set_for_host(toml.plugin.unwrap_or(t2.for_host()))
+              .set_required_features(toml.required_features.clone());
     }
 
     fn lib_target(dst: &mut Vec<Target>,
@@ -926,6 +1431,14 @@ fn build_profiles(profiles: &Option<TomlProfiles>) -> Profiles {
                      profiles.and_then(|p| p.bench.as_ref())),
         doc: merge(Profile::default_doc(),
                    profiles.and_then(|p| p.doc.as_ref())),
+        check: merge(Profile::default_check(),
+                     profiles.and_then(|p| p.dev.as_ref())),
+        check_test: merge(Profile::default_check_test(),
+                          profiles.and_then(|p| p.dev.as_ref())),
+        release_check: merge(Profile::default_release_check(),
+                             profiles.and_then(|p| p.release.as_ref())),
+        release_check_test: merge(Profile::default_release_check_test(),
+                                  profiles.and_then(|p| p.release.as_ref())),
     };
 
     fn merge(profile: Profile, toml: Option<&TomlProfile>) -> Profile {
@@ -940,11 +1453,13 @@ fn build_profiles(profiles: &Option<TomlProfiles>) -> Profiles {
             lto: lto.unwrap_or(profile.lto),
             codegen_units: codegen_units,
             rustc_args: None,
+            rustdoc_args: None,
             debuginfo: debug.unwrap_or(profile.debuginfo),
             debug_assertions: debug_assertions.unwrap_or(profile.debug_assertions),
             rpath: rpath.unwrap_or(profile.rpath),
             test: profile.test,
             doc: profile.doc,
+            check: profile.check,
         }
     }
 }
@@ -72,6 +72,12 @@ pub struct NewCrateDependency {
     pub version_req: String,
     pub target: Option<String>,
     pub kind: String,
+    /// The name the dependency is known by in the manifest and the crate,
+    /// if it's renamed from `name`, the name of the package.
+    pub explicit_name_in_toml: Option<String>,
+    /// The index of the registry the dependency comes from, if it's not the
+    /// one the crate is published to.
+    pub registry: Option<String>,
 }
 
 #[derive(RustcDecodable)]
@@ -88,7 +94,8 @@ pub struct User {
 #[derive(RustcDecodable)] struct ApiError { detail: String }
 #[derive(RustcEncodable)] struct OwnersReq<'a> { users: &'a [&'a str] }
 #[derive(RustcDecodable)] struct Users { users: Vec<User> }
-#[derive(RustcDecodable)] struct Crates { crates: Vec<Crate> }
+#[derive(RustcDecodable)] struct TotalCrates { total: u32 }
+#[derive(RustcDecodable)] struct Crates { crates: Vec<Crate>, meta: TotalCrates }
 
 impl Registry {
     pub fn new(host: String, token: Option<String>) -> Registry {
@@ -170,11 +177,16 @@ impl Registry {
         Ok(())
     }
 
-    pub fn search(&mut self, query: &str) -> Result<Vec<Crate>> {
-        let body = try!(self.req(format!("/crates?q={}", query), None, Get,
-                                 Auth::Unauthorized));
+    /// Returns at most `limit` crates matching `query`, along with the total
+    /// number of crates which matched.
+    pub fn search(&mut self, query: &str, limit: u32)
+                  -> Result<(Vec<Crate>, u32)> {
+        let body = try!(self.req(format!("/crates?q={}&per_page={}", query,
+                                         limit),
+                                 None, Get, Auth::Unauthorized));
 
-        Ok(json::decode::<Crates>(&body).unwrap().crates)
+        let crates = json::decode::<Crates>(&body).unwrap();
+        Ok((crates.crates, crates.meta.total))
     }
 
     pub fn yank(&mut self, krate: &str, version: &str) -> Result<()> {
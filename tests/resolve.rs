@@ -16,7 +16,7 @@ fn resolve<R: Registry>(pkg: PackageId, deps: Vec<Dependency>,
                         -> CargoResult<Vec<PackageId>> {
     let summary = Summary::new(pkg, deps, HashMap::new()).unwrap();
     let method = Method::Everything;
-    Ok(try!(resolver::resolve(&summary, &method, registry)).iter().map(|p| {
+    Ok(try!(resolver::resolve(&[(summary, method)], &[], registry, None)).iter().map(|p| {
         p.clone()
     }).collect())
 }
@@ -235,6 +235,50 @@ fn resolving_with_specific_version() {
                                        ("foo", "1.0.1")])));
 }
 
+#[test]
+fn resolving_skips_prereleases_by_default() {
+    let mut reg = registry(vec!(
+        pkg!(("foo", "1.0.0")),
+        pkg!(("foo", "1.0.1-alpha.1")),
+    ));
+
+    let res = resolve(pkg_id("root"), vec![dep_req("foo", "1")],
+                      &mut reg).unwrap();
+
+    assert_that(&res, contains(names(&[("root", "1.0.0"),
+                                       ("foo", "1.0.0")])));
+}
+
+#[test]
+fn resolving_prerelease_when_requested() {
+    let mut reg = registry(vec!(
+        pkg!(("foo", "1.0.0")),
+        pkg!(("foo", "1.0.1-alpha.2")),
+        pkg!(("foo", "1.0.1-alpha.10")),
+    ));
+
+    let res = resolve(pkg_id("root"), vec![dep_req("foo", "1.0.1-alpha.2")],
+                      &mut reg).unwrap();
+
+    assert_that(&res, contains(names(&[("root", "1.0.0"),
+                                       ("foo", "1.0.1-alpha.10")])));
+}
+
+#[test]
+fn resolving_exact_prerelease() {
+    let mut reg = registry(vec!(
+        pkg!(("foo", "1.0.1-alpha.2")),
+        pkg!(("foo", "1.0.1-alpha.10")),
+        pkg!(("foo", "1.0.1")),
+    ));
+
+    let res = resolve(pkg_id("root"), vec![dep_req("foo", "=1.0.1-alpha.2")],
+                      &mut reg).unwrap();
+
+    assert_that(&res, contains(names(&[("root", "1.0.0"),
+                                       ("foo", "1.0.1-alpha.2")])));
+}
+
 #[test]
 fn resolving_incompat_versions() {
     let mut reg = registry(vec!(
@@ -137,6 +137,7 @@ impl ProjectBuilder {
         p.cwd(&self.root())
          .env("HOME", &paths::home())
          .env_remove("CARGO_HOME")  // make sure we don't pick up an outer one
+         .env_remove("RUSTFLAGS")   // same for extra compiler flags
          .env_remove("MSYSTEM");    // assume cmd.exe everywhere on windows
         return p;
     }
@@ -530,3 +531,4 @@ pub static DOWNLOADING: &'static str = " Downloading";
 pub static UPLOADING:   &'static str = "   Uploading";
 pub static VERIFYING:   &'static str = "   Verifying";
 pub static ARCHIVING:   &'static str = "   Archiving";
+pub static INSTALLING:  &'static str = "  Installing";
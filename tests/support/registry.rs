@@ -16,6 +16,10 @@ use cargo::util::Sha256;
 
 pub fn registry_path() -> PathBuf { paths::root().join("registry") }
 pub fn registry() -> Url { Url::from_file_path(&*registry_path()).ok().unwrap() }
+pub fn alt_registry_path() -> PathBuf { paths::root().join("alt-registry") }
+pub fn alt_registry() -> Url {
+    Url::from_file_path(&*alt_registry_path()).ok().unwrap()
+}
 pub fn dl_path() -> PathBuf { paths::root().join("dl") }
 pub fn dl_url() -> Url { Url::from_file_path(&*dl_path()).ok().unwrap() }
 
@@ -36,6 +40,15 @@ pub fn init() {
         .build();
 }
 
+/// Creates a second registry, sharing its downloads with the first one.
+pub fn init_alt() {
+    repo(&alt_registry_path())
+        .file("config.json", &format!(r#"
+            {{"dl":"{}","api":""}}
+        "#, dl_url()))
+        .build();
+}
+
 pub fn mock_archive(name: &str, version: &str, deps: &[(&str, &str, &str)]) {
     let mut manifest = format!(r#"
         [package]
@@ -53,19 +66,25 @@ pub fn mock_archive(name: &str, version: &str, deps: &[(&str, &str, &str)]) {
             _ => ""
         }, dep, req));
     }
-    let p = project(name)
-        .file("Cargo.toml", &manifest)
-        .file("src/lib.rs", "");
+    mock_archive_files(name, version, &[("Cargo.toml", &manifest),
+                                        ("src/lib.rs", "")]);
+}
+
+pub fn mock_archive_files(name: &str, version: &str, files: &[(&str, &str)]) {
+    let mut p = project(name);
+    for &(path, contents) in files.iter() {
+        p = p.file(path, contents);
+    }
     p.build();
 
     let dst = mock_archive_dst(name, version);
     fs::create_dir_all(dst.parent().unwrap()).unwrap();
     let f = File::create(&dst).unwrap();
     let a = Archive::new(GzEncoder::new(f, Default));
-    a.append_file(&format!("{}-{}/Cargo.toml", name, version),
-                  &mut File::open(&p.root().join("Cargo.toml")).unwrap()).unwrap();
-    a.append_file(&format!("{}-{}/src/lib.rs", name, version),
-                  &mut File::open(&p.root().join("src/lib.rs")).unwrap()).unwrap();
+    for &(path, _) in files.iter() {
+        a.append_file(&format!("{}-{}/{}", name, version, path),
+                      &mut File::open(&p.root().join(path)).unwrap()).unwrap();
+    }
     a.finish().unwrap();
 }
 
@@ -80,6 +99,24 @@ pub fn mock_pkg(name: &str, version: &str, deps: &[(&str, &str, &str)]) {
 pub fn mock_pkg_yank(name: &str, version: &str, deps: &[(&str, &str, &str)],
                      yanked: bool) {
     mock_archive(name, version, deps);
+    publish_archive(&registry_path(), name, version, deps, yanked);
+}
+
+/// Like `mock_pkg`, but publishes to the registry created by `init_alt`.
+pub fn mock_alt_pkg(name: &str, version: &str, deps: &[(&str, &str, &str)]) {
+    mock_archive(name, version, deps);
+    publish_archive(&alt_registry_path(), name, version, deps, false);
+}
+
+/// Publishes a package consisting of exactly `files`, which should include
+/// its `Cargo.toml`.
+pub fn mock_pkg_files(name: &str, version: &str, files: &[(&str, &str)]) {
+    mock_archive_files(name, version, files);
+    publish_archive(&registry_path(), name, version, &[], false);
+}
+
+fn publish_archive(registry: &Path, name: &str, version: &str,
+                   deps: &[(&str, &str, &str)], yanked: bool) {
     let mut c = Vec::new();
     File::open(&mock_archive_dst(name, version)).unwrap()
          .read_to_end(&mut c).unwrap();
@@ -91,14 +128,18 @@ pub fn mock_pkg_yank(name: &str, version: &str, deps: &[(&str, &str, &str)],
         3 => format!("3/{}/{}", &name[..1], name),
         _ => format!("{}/{}/{}", &name[0..2], &name[2..4], name),
     };
-    publish(&file, &line);
+    publish_to(registry, &file, &line);
 }
 
 pub fn publish(file: &str, line: &str) {
-    let repo = git2::Repository::open(&registry_path()).unwrap();
+    publish_to(&registry_path(), file, line)
+}
+
+fn publish_to(registry: &Path, file: &str, line: &str) {
+    let repo = git2::Repository::open(registry).unwrap();
     let mut index = repo.index().unwrap();
     {
-        let dst = registry_path().join(file);
+        let dst = registry.join(file);
         let mut prev = String::new();
         let _ = File::open(&dst).and_then(|mut f| f.read_to_string(&mut prev));
         fs::create_dir_all(dst.parent().unwrap()).unwrap();
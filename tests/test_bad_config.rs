@@ -74,6 +74,47 @@ expected a string, but found a boolean in [..]config
 "));
 });
 
+test!(bad_http_cainfo {
+    let foo = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file(".cargo/config", r#"
+            [http]
+              cainfo = true
+        "#);
+    assert_that(foo.cargo_process("publish").arg("-v"),
+                execs().with_status(101).with_stderr("\
+invalid configuration for key `http.cainfo`
+expected a string, but found a boolean in [..]config
+"));
+});
+
+test!(bad_http_ssl_version {
+    let foo = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file(".cargo/config", r#"
+            [http]
+              ssl-version = "sslv3"
+        "#);
+    assert_that(foo.cargo_process("publish").arg("-v"),
+                execs().with_status(101).with_stderr("\
+unsupported TLS version `sslv3` for `http.ssl-version` in [..]config, \
+expected one of `default`, `tlsv1`, `tlsv1.0`, `tlsv1.1`, `tlsv1.2` or \
+`tlsv1.3`
+"));
+});
+
 test!(bad4 {
     let foo = project("foo")
         .file(".cargo/config", r#"
@@ -258,3 +299,25 @@ test!(bad_crate_type {
 warning: crate-type \"bad_type\" was not one of lib|rlib|dylib|staticlib
 "));
 });
+
+test!(both_package_and_project {
+    let foo = project("foo")
+    .file("Cargo.toml", r#"
+        [package]
+        name = "foo"
+        version = "0.0.0"
+        authors = []
+
+        [project]
+        name = "foo"
+        version = "0.0.0"
+        authors = []
+    "#)
+    .file("src/lib.rs", "");
+
+    assert_that(foo.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stderr("\
+warning: manifest has both a `[package]` and a `[project]` section, \
+`[package]` is ignored
+"));
+});
@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::prelude::*;
+
+use support::{project, execs, ProjectBuilder};
+use support::COMPILING;
+use support::paths::CargoPathExt;
+use hamcrest::{assert_that, existing_dir, existing_file, is_not};
+
+fn setup() {
+}
+
+fn foo_with_dep() -> ProjectBuilder {
+    project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", r#"
+            extern crate bar;
+            fn main() { bar::bar() }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+}
+
+test!(check_success {
+    let p = foo_with_dep();
+
+    assert_that(p.cargo_process("check"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} bar v0.0.1 ({dir}/bar)
+{compiling} foo v0.0.1 ({dir})
+",
+        compiling = COMPILING, dir = p.url())));
+    assert_that(&p.bin("foo"), is_not(existing_file()));
+});
+
+test!(check_fail {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", r#"
+            fn main() { let _x: u32 = "not a number"; }
+        "#);
+
+    assert_that(p.cargo_process("check"),
+                execs().with_status(101));
+});
+
+test!(check_and_build_are_fresh_independently {
+    let p = foo_with_dep();
+
+    assert_that(p.cargo_process("check"), execs().with_status(0));
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} bar v0.0.1 ({dir}/bar)
+{compiling} foo v0.0.1 ({dir})
+",
+        compiling = COMPILING, dir = p.url())));
+    assert_that(&p.bin("foo"), existing_file());
+
+    assert_that(p.cargo("check"), execs().with_status(0).with_stdout(""));
+    assert_that(p.cargo("build"), execs().with_status(0).with_stdout(""));
+});
+
+test!(check_all_targets_checks_tests {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("tests/t.rs", r#"
+            #[test]
+            fn t() { let _x: u32 = "not a number"; }
+        "#);
+
+    assert_that(p.cargo_process("check"), execs().with_status(0));
+    assert_that(p.cargo("check").arg("--all-targets"),
+                execs().with_status(101));
+    assert_that(&p.bin("foo"), is_not(existing_file()));
+});
+
+test!(check_lib_and_bin_with_the_same_name {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn foo() {}")
+        .file("src/main.rs", r#"
+            extern crate foo;
+            fn main() { foo::foo() }
+        "#);
+
+    assert_that(p.cargo_process("check"), execs().with_status(0));
+    assert_that(&p.root().join("target/debug/libfoo.rmeta"), existing_file());
+
+    // Checking the binary again needs the library's metadata to still be
+    // the library's.
+    p.root().move_into_the_past().unwrap();
+    File::create(&p.root().join("src/main.rs")).unwrap().write_all(br#"
+        extern crate foo;
+        fn main() { foo::foo(); foo::foo() }
+    "#).unwrap();
+    assert_that(p.cargo("check"), execs().with_status(0));
+});
+
+test!(check_release {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(debug_assertions)]
+            fn main() { let _x: u32 = "not a number"; }
+            #[cfg(not(debug_assertions))]
+            fn main() {}
+        "#);
+
+    assert_that(p.cargo_process("check").arg("--release"),
+                execs().with_status(0));
+    assert_that(&p.root().join("target/release"), existing_dir());
+    assert_that(p.cargo("check"), execs().with_status(101));
+});
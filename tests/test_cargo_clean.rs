@@ -1,5 +1,5 @@
 use support::{project, execs, main_file, basic_bin_manifest};
-use hamcrest::{assert_that, existing_dir, is_not};
+use hamcrest::{assert_that, existing_dir, existing_file, is_not};
 
 fn setup() {
 }
@@ -30,3 +30,33 @@ test!(different_dir {
                 execs().with_status(0).with_stdout(""));
     assert_that(&p.build_dir(), is_not(existing_dir()));
 });
+
+test!(clean_release {
+    let p = project("foo")
+              .file("Cargo.toml", &basic_bin_manifest("foo"))
+              .file("src/foo.rs", &main_file(r#""i am foo""#, &[]));
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.cargo("build").arg("--release"), execs().with_status(0));
+    assert_that(&p.release_bin("foo"), existing_file());
+
+    assert_that(p.cargo("clean").arg("--release"),
+                execs().with_status(0).with_stdout(""));
+    assert_that(&p.release_bin("foo"), is_not(existing_file()));
+    assert_that(&p.bin("foo"), existing_file());
+});
+
+test!(clean_release_package {
+    let p = project("foo")
+              .file("Cargo.toml", &basic_bin_manifest("foo"))
+              .file("src/foo.rs", &main_file(r#""i am foo""#, &[]));
+
+    assert_that(p.cargo_process("build").arg("--release"),
+                execs().with_status(0));
+    assert_that(p.cargo("build"), execs().with_status(0));
+
+    assert_that(p.cargo("clean").arg("-p").arg("foo").arg("--release"),
+                execs().with_status(0));
+    assert_that(&p.release_bin("foo"), is_not(existing_file()));
+    assert_that(&p.bin("foo"), existing_file());
+});
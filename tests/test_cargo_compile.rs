@@ -726,6 +726,28 @@ test!(crate_version_env_vars {
                 execs().with_status(0));
 });
 
+test!(crate_name_and_authors_env_vars {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.1"
+            authors = ["wycats@example.com", "neikos@example.com"]
+        "#)
+        .file("src/main.rs", r#"
+            fn main() {
+                println!("{} by {}", env!("CARGO_PKG_NAME"),
+                         env!("CARGO_PKG_AUTHORS"));
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(process(&p.bin("foo")).unwrap(),
+                execs().with_stdout("\
+foo by wycats@example.com:neikos@example.com
+"));
+});
+
 // this is testing that src/<pkg-name>.rs still works (for now)
 test!(many_crate_types_old_style_lib_location {
     let mut p = project("foo");
@@ -780,6 +802,38 @@ test!(many_crate_types_correct {
     assert_that(&p.root().join("target/debug").join(&fname), existing_file());
 });
 
+test!(rust_version_too_new {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            rust-version = "100.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+package `foo v0.0.1 ([..])` requires rustc 100.0.0 or newer, while the \
+currently active rustc version is [..]
+"));
+});
+
+test!(rust_version_satisfied {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            rust-version = "1.0.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
+
 test!(unused_keys {
     let mut p = project("foo");
     p = p
@@ -800,7 +854,8 @@ test!(unused_keys {
         "#);
     assert_that(p.cargo_process("build"),
                 execs().with_status(0)
-                       .with_stderr("unused manifest key: project.bulid\n"));
+                       .with_stderr("unused manifest key: project.bulid, \
+                                     did you mean `build`?\n"));
 
     let mut p = project("bar");
     p = p
@@ -824,6 +879,96 @@ test!(unused_keys {
                        .with_stderr("unused manifest key: lib.build\n"));
 });
 
+test!(misspelled_dependency_key {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+            optinal = true
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  unknown key `optinal` in dependency `bar`, did you mean `optional`?
+"));
+});
+
+test!(unknown_dependency_key {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = { verson = "0.1" }
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  unknown key `verson` in dependency `bar`, did you mean `version`?
+"));
+});
+
+test!(misspelled_registry_index_key {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = { version = "0.1", registry-indx = "file:///index" }
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  unknown key `registry-indx` in dependency `bar`, did you mean `registry-index`?
+"));
+});
+
+test!(misspelled_project_section {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [projcet]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  no `[package]` section found, but found `[projcet]`, did you mean \
+`[project]`?
+"));
+});
+
 test!(self_dependency {
     let mut p = project("foo");
     p = p
@@ -847,6 +992,7 @@ test!(self_dependency {
                 execs().with_status(101)
                        .with_stderr("\
 cyclic package dependency: package `test v0.0.0 ([..])` depends on itself
+cycle: test v0.0.0 => test v0.0.0
 "));
 });
 
@@ -1724,6 +1870,39 @@ test!(cyclic_deps_rejected {
                 execs().with_status(101)
                        .with_stderr("\
 cyclic package dependency: package `foo v0.0.1 ([..])` depends on itself
+cycle: foo v0.0.1 => a v0.0.1 => foo v0.0.1
+"));
+});
+
+test!(cyclic_dev_deps_rejected {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dev-dependencies.a]
+            path = "a"
+        "#)
+        .file("src/lib.rs", "")
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.foo]
+            path = ".."
+        "#)
+        .file("a/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101)
+                       .with_stderr("\
+cyclic package dependency: package `foo v0.0.1 ([..])` depends on itself
+cycle: foo v0.0.1 => a v0.0.1 => foo v0.0.1
+note: `a v0.0.1` is a dev-dependency of `foo v0.0.1`
 "));
 });
 
@@ -1808,6 +1987,76 @@ Caused by:
     assert_that(&p.bin("a"), is_not(existing_file()));
 });
 
+test!(rustc_wrapper_env_var {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    // The real compiler is still queried directly, only the compilation
+    // itself goes through the wrapper
+    assert_that(p.cargo("build")
+                 .env("RUSTC_WRAPPER", "wrapper-that-does-not-exist"),
+                execs().with_status(101)
+                       .with_stderr("\
+Could not compile `foo`.
+
+Caused by:
+  Could not execute process `wrapper-that-does-not-exist rustc src[..]lib.rs [..]` ([..])
+[..]
+"));
+});
+
+test!(message_format_json {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "fn dead() {}");
+    p.build();
+
+    assert_that(p.cargo("build").arg("--message-format").arg("json"),
+                execs().with_status(0).with_stdout("\
+{\"message\":{[..]},\"package_id\":\"foo 0.5.0 ([..])\",\
+\"reason\":\"compiler-message\",\"target\":{[..]\"name\":\"foo\"[..]}}
+{\"features\":[],\"filenames\":[\"[..]libfoo.rlib\"],\
+\"package_id\":\"foo 0.5.0 ([..])\",\"profile\":{[..]},\
+\"reason\":\"compiler-artifact\",\"target\":{[..]\"name\":\"foo\"[..]}}
+"));
+
+    // Fresh crates still report their warnings and artifacts
+    assert_that(p.cargo("build").arg("--message-format").arg("json"),
+                execs().with_status(0).with_stdout("\
+{\"message\":{[..]},\"package_id\":\"foo 0.5.0 ([..])\",\
+\"reason\":\"compiler-message\",\"target\":{[..]\"name\":\"foo\"[..]}}
+{\"features\":[],\"filenames\":[\"[..]libfoo.rlib\"],\
+\"package_id\":\"foo 0.5.0 ([..])\",\"profile\":{[..]},\
+\"reason\":\"compiler-artifact\",\"target\":{[..]\"name\":\"foo\"[..]}}
+"));
+});
+
+test!(message_format_invalid {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--message-format").arg("xml"),
+                execs().with_status(1));
+});
+
 test!(filtering {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -1911,6 +2160,39 @@ test!(custom_target_dir {
                 existing_file());
 });
 
+test!(target_dir_flag {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [build]
+            target-dir = "bar/target"
+        "#);
+    p.build();
+
+    let exe_name = format!("foo{}", env::consts::EXE_SUFFIX);
+
+    assert_that(p.cargo("build").arg("--target-dir").arg("baz/target")
+                 .env("CARGO_TARGET_DIR", "foo/target"),
+                execs().with_status(0));
+    assert_that(&p.root().join("baz/target/debug").join(&exe_name),
+                existing_file());
+    assert_that(&p.root().join("bar/target/debug").join(&exe_name),
+                is_not(existing_file()));
+    assert_that(&p.root().join("foo/target/debug").join(&exe_name),
+                is_not(existing_file()));
+
+    assert_that(p.cargo("clean").arg("--target-dir").arg("baz/target"),
+                execs().with_status(0));
+    assert_that(&p.root().join("baz/target/debug").join(&exe_name),
+                is_not(existing_file()));
+});
+
 test!(rustc_no_trans {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -1925,3 +2207,57 @@ test!(rustc_no_trans {
     assert_that(p.cargo("rustc").arg("-v").arg("--").arg("-Zno-trans"),
                 execs().with_status(0));
 });
+
+test!(build_all_targets {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "fn main() {}")
+        .file("examples/ex.rs", "fn main() {}")
+        .file("tests/t.rs", "");
+
+    let example = p.build_dir().join("debug").join("examples")
+                   .join(&format!("ex{}", env::consts::EXE_SUFFIX));
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(&example, is_not(existing_file()));
+
+    assert_that(p.cargo("build").arg("--all-targets"),
+                execs().with_status(0));
+    assert_that(&p.bin("foo"), existing_file());
+    assert_that(&example, existing_file());
+});
+
+test!(missing_target_lists_available {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[bin]]
+            name = "a"
+            path = "src/a.rs"
+
+            [[bin]]
+            name = "b"
+            path = "src/b.rs"
+        "#)
+        .file("src/a.rs", "fn main() {}")
+        .file("src/b.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--bin").arg("c"),
+                execs().with_status(101).with_stderr("\
+no bin target named `c`
+
+available bin targets:
+    a
+    b
+"));
+});
@@ -1366,3 +1366,28 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
 ", compiling = COMPILING, running = RUNNING, fresh = FRESH)));
 });
+
+test!(jobserver_passed_to_build_scripts {
+    if cfg!(windows) { return }
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("build.rs", r#"
+            use std::env;
+
+            fn main() {
+                let flags = env::var("CARGO_MAKEFLAGS").unwrap();
+                assert!(flags.contains("--jobserver-fds="), "{}", flags);
+                assert!(env::var("MAKEFLAGS").unwrap().contains("--jobserver"));
+            }
+        "#);
+    assert_that(p.cargo_process("build").arg("-j2"),
+                execs().with_status(0));
+});
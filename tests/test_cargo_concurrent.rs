@@ -0,0 +1,170 @@
+use std::thread;
+use git2;
+
+use support::{project, execs, git, basic_bin_manifest, main_file};
+use hamcrest::{assert_that, existing_file};
+
+fn setup() {}
+
+test!(same_project {
+    let p = project("foo")
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/foo.rs", &main_file(r#""i am foo""#, &[]));
+    p.build();
+
+    let a = p.cargo("build").build_command().spawn().unwrap();
+    let b = p.cargo("build").build_command().spawn().unwrap();
+    let a = thread::spawn(move || a.wait_with_output().unwrap());
+    let b = b.wait_with_output().unwrap();
+    let a = a.join().unwrap();
+
+    assert!(a.status.success(), "{}", String::from_utf8_lossy(&a.stderr));
+    assert!(b.status.success(), "{}", String::from_utf8_lossy(&b.stderr));
+    assert_that(&p.bin("foo"), existing_file());
+    assert_that(p.cargo("build"), execs().with_status(0).with_stdout(""));
+});
+
+test!(same_git_dependency {
+    let dep = git::new("dep", |project| {
+        project.file("Cargo.toml", r#"
+            [package]
+            name = "dep"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn dep() {}")
+    }).unwrap();
+
+    let manifest = |name: &str| format!(r#"
+        [package]
+        name = "{}"
+        version = "0.5.0"
+        authors = []
+
+        [dependencies.dep]
+        git = '{}'
+    "#, name, dep.url());
+    let a = project("a")
+        .file("Cargo.toml", &manifest("a"))
+        .file("src/main.rs", "extern crate dep; fn main() { dep::dep() }");
+    a.build();
+    let b = project("b")
+        .file("Cargo.toml", &manifest("b"))
+        .file("src/main.rs", "extern crate dep; fn main() { dep::dep() }");
+    b.build();
+
+    let a_build = a.cargo("build").build_command().spawn().unwrap();
+    let b_build = b.cargo("build").build_command().spawn().unwrap();
+    let a_build = thread::spawn(move || a_build.wait_with_output().unwrap());
+    let b_out = b_build.wait_with_output().unwrap();
+    let a_out = a_build.join().unwrap();
+
+    assert!(a_out.status.success(), "{}", String::from_utf8_lossy(&a_out.stderr));
+    assert!(b_out.status.success(), "{}", String::from_utf8_lossy(&b_out.stderr));
+    assert_that(&a.bin("a"), existing_file());
+    assert_that(&b.bin("b"), existing_file());
+});
+
+test!(git_dependencies_in_opposite_order {
+    let x = git::new("x", |project| {
+        project.file("Cargo.toml", r#"
+            [package]
+            name = "x"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn x() {}")
+    }).unwrap();
+    let y = git::new("y", |project| {
+        project.file("Cargo.toml", r#"
+            [package]
+            name = "y"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn y() {}")
+    }).unwrap();
+
+    // The two builds load the git sources in opposite orders, which mustn't
+    // leave each of them waiting on a lock the other one holds.
+    let x_dep = format!("x = {{ git = '{}' }}", x.url());
+    let y_dep = format!("y = {{ git = '{}' }}", y.url());
+    let manifest = |name: &str, first: &str, second: &str| format!(r#"
+        [package]
+        name = "{}"
+        version = "0.5.0"
+        authors = []
+
+        [dependencies]
+        {}
+        {}
+    "#, name, first, second);
+    let main = "extern crate x; extern crate y; fn main() { x::x(); y::y() }";
+    let a = project("a")
+        .file("Cargo.toml", &manifest("a", &x_dep, &y_dep))
+        .file("src/main.rs", main);
+    a.build();
+    let b = project("b")
+        .file("Cargo.toml", &manifest("b", &y_dep, &x_dep))
+        .file("src/main.rs", main);
+    b.build();
+
+    let a_build = a.cargo("build").build_command().spawn().unwrap();
+    let b_build = b.cargo("build").build_command().spawn().unwrap();
+    let a_build = thread::spawn(move || a_build.wait_with_output().unwrap());
+    let b_out = b_build.wait_with_output().unwrap();
+    let a_out = a_build.join().unwrap();
+
+    assert!(a_out.status.success(), "{}", String::from_utf8_lossy(&a_out.stderr));
+    assert!(b_out.status.success(), "{}", String::from_utf8_lossy(&b_out.stderr));
+    assert_that(&a.bin("a"), existing_file());
+    assert_that(&b.bin("b"), existing_file());
+});
+
+test!(two_references_of_one_git_repository {
+    let dep = git::new("dep", |project| {
+        project.file("Cargo.toml", r#"
+            [package]
+            name = "dep"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn dep() {}")
+    }).unwrap();
+    let repo = git2::Repository::open(&dep.root()).unwrap();
+    let head = repo.head().unwrap().target().unwrap();
+    repo.tag("v0.5.0", &repo.find_object(head, None).unwrap(),
+             &repo.signature().unwrap(), "tag", false).unwrap();
+
+    // Both references point at the same revision and so share a checkout,
+    // which mustn't keep the second git source waiting on the first.
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.dep]
+            git = '{}'
+
+            [dependencies.bar]
+            path = "bar"
+        "#, dep.url()))
+        .file("src/main.rs", "extern crate dep; extern crate bar; \
+                              fn main() { dep::dep(); bar::bar() }")
+        .file("bar/Cargo.toml", &format!(r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.dep]
+            git = '{}'
+            tag = "v0.5.0"
+        "#, dep.url()))
+        .file("bar/src/lib.rs", "extern crate dep; pub fn bar() { dep::dep() }");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(&p.bin("foo"), existing_file());
+});
@@ -315,6 +315,29 @@ test!(linker_and_ar {
                             )));
 });
 
+test!(target_rustflags {
+    if disabled() { return }
+
+    let target = alternate();
+    let p = project("foo")
+        .file(".cargo/config", &format!(r#"
+            [target.{}]
+            rustflags = ["--cfg", "from_config"]
+        "#, target))
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/foo.rs", r#"
+            #[cfg(from_config)]
+            fn main() {}
+        "#);
+
+    assert_that(p.cargo_process("build").arg("--target").arg(&target),
+                execs().with_status(0));
+    assert_that(&p.target_bin(&target, "foo"), existing_file());
+
+    // The flags only apply to builds for that very target
+    assert_that(p.cargo("build"), execs().with_status(101));
+});
+
 test!(plugin_with_extra_dylib_dep {
     if disabled() { return }
     if !::is_nightly() { return }
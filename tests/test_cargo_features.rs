@@ -4,7 +4,7 @@ use std::io::prelude::*;
 use support::{project, execs};
 use support::{COMPILING, FRESH};
 use support::paths::CargoPathExt;
-use hamcrest::assert_that;
+use hamcrest::{assert_that, existing_file, is_not};
 
 fn setup() {
 }
@@ -807,3 +807,121 @@ test!(activating_feature_activates_dep {
     assert_that(p.cargo_process("build").arg("--features").arg("a").arg("-v"),
                 execs().with_status(0));
 });
+
+test!(required_features_skip_bin {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            extra = []
+
+            [[bin]]
+            name = "foo"
+            path = "src/main.rs"
+
+            [[bin]]
+            name = "extra"
+            path = "src/extra.rs"
+            required-features = ["extra"]
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("src/extra.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+note: skipping target `extra` as it requires the features: `extra`
+"));
+    assert_that(&p.bin("foo"), existing_file());
+    assert_that(&p.bin("extra"), is_not(existing_file()));
+
+    assert_that(p.cargo("build").arg("--features").arg("extra"),
+                execs().with_status(0));
+    assert_that(&p.bin("extra"), existing_file());
+});
+
+test!(required_features_explicit_bin {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            extra = []
+
+            [[bin]]
+            name = "extra"
+            path = "src/extra.rs"
+            required-features = ["extra"]
+        "#)
+        .file("src/extra.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--bin").arg("extra"),
+                execs().with_status(101).with_stderr("\
+target `extra` requires the features: `extra`
+consider enabling them by passing e.g. `--features`
+"));
+});
+
+test!(misspelled_feature_suggestion {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            serde = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--features").arg("sedre"),
+                execs().with_status(101).with_stderr("\
+Package `foo v0.0.1 ([..])` does not have these features: `sedre`
+`sedre` may be a misspelling of `serde`
+"));
+});
+
+test!(all_features_flag {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            a = []
+
+            [dependencies.bar]
+            path = "bar"
+            optional = true
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(feature = "bar")]
+            extern crate bar;
+
+            #[cfg(all(feature = "a", feature = "bar"))]
+            fn main() { bar::bar() }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(101));
+    assert_that(p.cargo("build").arg("--all-features"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 ({dir}/bar)
+{compiling} foo v0.0.1 ({dir})
+", compiling = COMPILING, dir = p.url())));
+});
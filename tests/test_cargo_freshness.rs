@@ -203,3 +203,82 @@ test!(rebuild_tests_if_lib_changes {
     assert_that(p.cargo("test").arg("-v"),
                 execs().with_status(101));
 });
+
+test!(fresh_crates_replay_warnings {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("src/lib.rs", "fn dead() {}");
+    p.build();
+
+    let first = p.cargo("build").exec_with_output().unwrap();
+    let first = String::from_utf8(first.stderr).unwrap();
+    assert!(first.contains("dead"), "{}", first);
+
+    // Nothing is compiled the second time, but the warning shows up again
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stdout("").with_stderr(&first));
+
+    p.root().move_into_the_past().unwrap();
+    p.root().join("target").move_into_the_past().unwrap();
+    File::create(&p.root().join("src/lib.rs")).unwrap()
+         .write_all(b"pub fn dead() {}").unwrap();
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+", compiling = COMPILING, dir = path2url(p.root())))
+                       .with_stderr(""));
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stdout("").with_stderr(""));
+});
+
+test!(replayed_warnings_are_not_colored {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("src/lib.rs", "fn dead() {}");
+    p.build();
+
+    let first = p.cargo("build").arg("--color").arg("always")
+                 .exec_with_output().unwrap();
+    let first = String::from_utf8(first.stderr).unwrap();
+    assert!(first.contains("dead") && first.contains("\x1b["), "{}", first);
+
+    let second = p.cargo("build").arg("--color").arg("never")
+                  .exec_with_output().unwrap();
+    let second = String::from_utf8(second.stderr).unwrap();
+    assert!(second.contains("dead") && !second.contains("\x1b"), "{}", second);
+});
+
+test!(warnings_of_older_builds_are_not_replayed {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("src/lib.rs", "fn dead() {}");
+    p.build();
+
+    assert_that(p.cargo("build"), execs().with_status(0));
+
+    // Fixing the warning in a build with JSON messages mustn't leave the
+    // warning saved for human readable builds around.
+    p.root().move_into_the_past().unwrap();
+    p.root().join("target").move_into_the_past().unwrap();
+    File::create(&p.root().join("src/lib.rs")).unwrap()
+         .write_all(b"pub fn dead() {}").unwrap();
+    assert_that(p.cargo("build").arg("--message-format").arg("json"),
+                execs().with_status(0));
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stdout("").with_stderr(""));
+});
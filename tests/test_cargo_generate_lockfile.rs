@@ -122,3 +122,71 @@ foo = "bar"
     File::open(&lockfile).unwrap().read_to_string(&mut lock).unwrap();
     assert!(lock.contains(metadata.trim()), "{}", lock);
 });
+
+test!(locked_up_to_date {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("generate-lockfile"),
+                execs().with_status(0));
+    assert_that(p.cargo("--locked").arg("build"),
+                execs().with_status(0));
+});
+
+test!(locked_needs_update {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("generate-lockfile"),
+                execs().with_status(0));
+
+    File::create(&p.root().join("Cargo.toml")).unwrap().write_all(br#"
+        [package]
+        name = "foo"
+        authors = []
+        version = "0.0.1"
+
+        [dependencies]
+        bar = { path = "bar" }
+    "#).unwrap();
+
+    assert_that(p.cargo("--locked").arg("build"),
+                execs().with_status(101).with_stderr("\
+the lock file [..]Cargo.lock needs to be updated but --locked was passed to \
+prevent this
+"));
+    assert_that(p.cargo("--frozen").arg("build"),
+                execs().with_status(101).with_stderr("\
+the lock file [..]Cargo.lock needs to be updated but --frozen was passed to \
+prevent this
+"));
+});
@@ -0,0 +1,306 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use cargo::util::{process, ProcessBuilder};
+use hamcrest::{assert_that, existing_file, is_not};
+
+use support::{project, execs, cargo_dir};
+use support::{UPDATING, DOWNLOADING, COMPILING, INSTALLING, REMOVING};
+use support::git;
+use support::paths;
+use support::registry as r;
+
+fn setup() {
+    r::init();
+}
+
+fn pkg(name: &str, vers: &str) {
+    r::mock_pkg_files(name, vers, &[
+        ("Cargo.toml", &format!(r#"
+            [package]
+            name = "{}"
+            version = "{}"
+            authors = []
+        "#, name, vers)),
+        ("src/main.rs", &format!("fn main() {{ println!(\"{}\") }}", vers)),
+    ]);
+}
+
+fn cargo_process(s: &str) -> ProcessBuilder {
+    let mut p = process(&cargo_dir().join("cargo")).unwrap();
+    p.arg(s).cwd(&paths::root())
+     .env("HOME", &paths::home())
+     .env_remove("CARGO_HOME")
+     .env_remove("CARGO_INSTALL_ROOT");
+    p
+}
+
+fn cargo_home() -> PathBuf {
+    paths::home().join(".cargo")
+}
+
+fn exe(name: &str) -> String {
+    format!("{}{}", name, env::consts::EXE_SUFFIX)
+}
+
+test!(simple {
+    pkg("foo", "0.0.1");
+
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `[..]`
+{downloading} foo v0.0.1 (registry file://[..])
+{installing} foo v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 (registry file://[..])
+{installing} {home}[..]bin[..]foo[..]
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        installing = INSTALLING,
+        compiling = COMPILING,
+        home = cargo_home().display())));
+    assert_that(&cargo_home().join("bin").join(exe("foo")), existing_file());
+
+    let mut contents = String::new();
+    File::open(&cargo_home().join(".crates.toml")).unwrap()
+        .read_to_string(&mut contents).unwrap();
+    assert!(contents.contains("foo 0.0.1 (registry+"), "{}", contents);
+});
+
+test!(no_lockfile_in_registry_source {
+    pkg("foo", "0.0.1");
+
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(0));
+
+    // The unpacked sources in the registry cache are left untouched
+    let src = cargo_home().join("registry/src");
+    let dirs = fs::read_dir(&src).unwrap().map(|d| d.unwrap().path())
+                                  .collect::<Vec<_>>();
+    assert_eq!(dirs.len(), 1);
+    let dir = dirs[0].join("foo-0.0.1");
+    assert_that(&dir.join("Cargo.toml"), existing_file());
+    assert_that(&dir.join("Cargo.lock"), is_not(existing_file()));
+});
+
+test!(pick_max_version {
+    pkg("foo", "0.0.1");
+    pkg("foo", "0.0.2");
+
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `[..]`
+{downloading} foo v0.0.2 (registry file://[..])
+{installing} foo v0.0.2 (registry file://[..])
+{compiling} foo v0.0.2 (registry file://[..])
+{installing} [..]
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        installing = INSTALLING,
+        compiling = COMPILING)));
+    assert_that(&cargo_home().join("bin").join(exe("foo")), existing_file());
+});
+
+test!(specific_version {
+    pkg("foo", "0.0.1");
+    pkg("foo", "0.0.2");
+
+    assert_that(cargo_process("install").arg("foo").arg("--vers").arg("=0.0.1"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `[..]`
+{downloading} foo v0.0.1 (registry file://[..])
+{installing} foo v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 (registry file://[..])
+{installing} [..]
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        installing = INSTALLING,
+        compiling = COMPILING)));
+});
+
+test!(missing {
+    pkg("foo", "0.0.1");
+    assert_that(cargo_process("install").arg("bar"),
+                execs().with_status(101).with_stderr("\
+could not find `bar` in registry file://[..]
+"));
+});
+
+test!(bad_version {
+    pkg("foo", "0.0.1");
+    assert_that(cargo_process("install").arg("foo").arg("--vers=0.2.0"),
+                execs().with_status(101).with_stderr("\
+could not find `foo` in registry file://[..] with version `0.2.0`
+"));
+});
+
+test!(no_crate {
+    assert_that(cargo_process("install"),
+                execs().with_status(101).with_stderr("\
+must specify a crate to install from registry file://[..]
+"));
+});
+
+test!(install_location_precedence {
+    pkg("foo", "0.0.1");
+
+    let root = paths::root();
+    let t1 = root.join("t1");
+    let t2 = root.join("t2");
+    let t3 = root.join("t3");
+
+    File::create(&cargo_home().join("config")).unwrap().write_all(format!("\
+        [install]
+        root = '{}'
+    ", t3.display()).as_bytes()).unwrap();
+
+    assert_that(cargo_process("install").arg("foo")
+                                        .arg("--root").arg(&t1)
+                                        .env("CARGO_INSTALL_ROOT", &t2),
+                execs().with_status(0));
+    assert_that(&t1.join("bin").join(exe("foo")), existing_file());
+    assert_that(&t2.join("bin").join(exe("foo")), is_not(existing_file()));
+
+    assert_that(cargo_process("install").arg("foo")
+                                        .env("CARGO_INSTALL_ROOT", &t2),
+                execs().with_status(0));
+    assert_that(&t2.join("bin").join(exe("foo")), existing_file());
+    assert_that(&t3.join("bin").join(exe("foo")), is_not(existing_file()));
+
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(0));
+    assert_that(&t3.join("bin").join(exe("foo")), existing_file());
+    assert_that(&cargo_home().join("bin").join(exe("foo")),
+                is_not(existing_file()));
+});
+
+test!(install_path {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(cargo_process("install").arg("--path").arg(p.root()),
+                execs().with_status(0));
+    assert_that(&cargo_home().join("bin").join(exe("foo")), existing_file());
+});
+
+test!(install_git {
+    let p = git::repo(&paths::root().join("foo"))
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(cargo_process("install").arg("--git").arg(p.url().to_string())
+                                        .arg("foo"),
+                execs().with_status(0));
+    assert_that(&cargo_home().join("bin").join(exe("foo")), existing_file());
+});
+
+test!(no_binaries {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    assert_that(cargo_process("install").arg("--path").arg(p.root()),
+                execs().with_status(101).with_stderr("\
+specified package `foo v0.1.0 ([..])` has no binaries
+"));
+});
+
+test!(reinstall_requires_force {
+    pkg("foo", "0.0.1");
+
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(0));
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(101).with_stderr(&format!("\
+binary `{}` already exists in destination as part of `foo v0.0.1 ([..])`
+Add --force to overwrite
+", exe("foo"))));
+    assert_that(cargo_process("install").arg("foo").arg("--force"),
+                execs().with_status(0));
+});
+
+test!(overwrite_untracked_binary {
+    pkg("foo", "0.0.1");
+
+    fs::create_dir_all(&cargo_home().join("bin")).unwrap();
+    File::create(&cargo_home().join("bin").join(exe("foo"))).unwrap();
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(101).with_stderr(&format!("\
+binary `{}` already exists in destination
+Add --force to overwrite
+", exe("foo"))));
+});
+
+test!(uninstall_pkg {
+    pkg("foo", "0.0.1");
+
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(0));
+    assert_that(cargo_process("uninstall").arg("foo"),
+                execs().with_status(0).with_stdout(&format!("\
+{removing} {home}[..]bin[..]foo[..]
+",
+        removing = REMOVING,
+        home = cargo_home().display())));
+    assert_that(&cargo_home().join("bin").join(exe("foo")),
+                is_not(existing_file()));
+
+    assert_that(cargo_process("uninstall").arg("foo"),
+                execs().with_status(101).with_stderr("\
+package id specification `foo` matched no packages
+"));
+});
+
+test!(uninstall_bin_not_installed {
+    pkg("foo", "0.0.1");
+
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(0));
+    assert_that(cargo_process("uninstall").arg("foo").arg("--bin").arg("bar"),
+                execs().with_status(101).with_stderr(&format!("\
+binary `{}` is not installed as part of `foo v0.0.1 ([..])`
+", exe("bar"))));
+});
+
+test!(list {
+    pkg("foo", "0.0.1");
+    pkg("bar", "0.2.1");
+
+    assert_that(cargo_process("install").arg("--list"),
+                execs().with_status(0).with_stdout(""));
+
+    assert_that(cargo_process("install").arg("bar"),
+                execs().with_status(0));
+    assert_that(cargo_process("install").arg("foo"),
+                execs().with_status(0));
+    assert_that(cargo_process("install").arg("--list"),
+                execs().with_status(0).with_stdout("\
+bar v0.2.1 (registry [..]):
+    bar[..]
+foo v0.0.1 (registry [..]):
+    foo[..]
+"));
+});
@@ -0,0 +1,152 @@
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use support::{project, execs, COMPILING};
+use support::paths;
+use support::registry as r;
+
+use hamcrest::assert_that;
+
+fn setup() {
+    r::init();
+}
+
+// Copies what has been published to the test registry so far into a local
+// registry, removing the original so it can't be used by accident.
+fn local_registry(pkgs: &[(&str, &str)]) -> PathBuf {
+    let root = paths::root().join("local");
+    for &(name, vers) in pkgs.iter() {
+        let file = match name.len() {
+            1 => format!("1/{}", name),
+            2 => format!("2/{}", name),
+            3 => format!("3/{}/{}", &name[..1], name),
+            _ => format!("{}/{}/{}", &name[0..2], &name[2..4], name),
+        };
+        let dst = root.join("index").join(&file);
+        fs::create_dir_all(dst.parent().unwrap()).unwrap();
+        fs::copy(&r::registry_path().join(&file), &dst).unwrap();
+        fs::copy(&r::mock_archive_dst(name, vers),
+                 &root.join(&format!("{}-{}.crate", name, vers))).unwrap();
+    }
+    fs::remove_dir_all(&r::registry_path()).unwrap();
+    root
+}
+
+fn config(root: &PathBuf) -> String {
+    format!(r#"
+        [source.crates-io]
+        replace-with = "my-local"
+
+        [source.my-local]
+        local-registry = '{}'
+    "#, root.display())
+}
+
+test!(simple {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let root = local_registry(&[("bar", "0.0.1")]);
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file(".cargo/config", &config(&root));
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        compiling = COMPILING,
+        dir = p.url())));
+});
+
+test!(relative_path {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    local_registry(&[("bar", "0.0.1")]);
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file(".cargo/config", r#"
+            [source.crates-io]
+            replace-with = "my-local"
+
+            [source.my-local]
+            local-registry = '../local'
+        "#);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
+
+test!(missing_package {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let root = local_registry(&[("bar", "0.0.1")]);
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", &config(&root));
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+no matching package named `baz` found (required by `foo`)
+location searched: registry file://[..]
+version required: ^0.0.1
+"));
+});
+
+test!(bad_checksum {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let root = local_registry(&[("bar", "0.0.1")]);
+    File::create(&root.join("bar-0.0.1.crate")).unwrap()
+         .write_all(b"not a crate").unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", &config(&root));
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+unable to get packages from source
+
+Caused by:
+  failed to download replaced source `registry file://[..]`
+
+Caused by:
+  Failed to verify the checksum of `bar v0.0.1 (local registry file://[..])`
+"));
+});
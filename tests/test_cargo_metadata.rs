@@ -0,0 +1,163 @@
+use support::{project, execs, basic_bin_manifest, basic_lib_manifest, main_file};
+use hamcrest::assert_that;
+
+fn setup() {}
+
+test!(cargo_metadata_simple {
+    let p = project("foo")
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/foo.rs", &main_file(r#""i am foo""#, &[]));
+
+    assert_that(p.cargo_process("metadata"), execs().with_status(0).with_stdout("\
+{\
+    \"packages\":[{\
+        \"name\":\"foo\",\
+        \"version\":\"0.5.0\",\
+        \"id\":\"foo[..]\",\
+        \"dependencies\":[],\
+        \"targets\":[{\
+            \"kind\":[\"bin\"],\
+            \"name\":\"foo\",\
+            \"src_path\":\"src[..]foo.rs\",\
+            \"metadata\":null\
+        }],\
+        \"manifest_path\":\"[..]Cargo.toml\",\
+        \"metadata\":null\
+    }],\
+    \"workspace_members\":[\"foo 0.5.0 (path+file:[..]foo)\"],\
+    \"resolve\":{\
+        \"root\":\"foo 0.5.0 (path+file:[..]foo)\",\
+        \"nodes\":[{\
+            \"id\":\"foo 0.5.0 (path+file:[..]foo)\",\
+            \"dependencies\":[]\
+        }]\
+    },\
+    \"version\":1\
+}"));
+});
+
+test!(cargo_metadata_with_deps {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_lib_manifest("bar"))
+        .file("bar/src/bar.rs", "");
+
+    assert_that(p.cargo_process("metadata"), execs().with_status(0).with_stdout("\
+{[..]\
+    \"workspace_members\":[\"foo 0.5.0 (path+file:[..]foo)\"],\
+    \"resolve\":{\
+        \"root\":\"foo 0.5.0 (path+file:[..]foo)\",\
+        \"nodes\":[{\
+            \"id\":\"bar 0.5.0 (path+file:[..]foo)\",\
+            \"dependencies\":[]\
+        },{\
+            \"id\":\"foo 0.5.0 (path+file:[..]foo)\",\
+            \"dependencies\":[\"bar 0.5.0 (path+file:[..]foo)\"]\
+        }]\
+    },\
+    \"version\":1\
+}"));
+});
+
+test!(cargo_metadata_no_deps {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("metadata").arg("--no-deps"),
+                execs().with_status(0).with_stdout("\
+{[..]\
+    \"workspace_members\":[\"foo 0.5.0 (path+file:[..]foo)\"],\
+    \"resolve\":null,\
+    \"version\":1\
+}"));
+});
+
+test!(cargo_metadata_workspace_members {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [workspace]
+            members = ["bar"]
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+            workspace = ".."
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("metadata"), execs().with_status(0).with_stdout("\
+{[..]\
+    \"workspace_members\":[\"[..]\",\"[..]\"],\
+    \"resolve\":{\
+        \"root\":\"foo 0.5.0 (path+file:[..]foo)\",\
+        \"nodes\":[{\
+            \"id\":\"bar 0.5.0 (path+file:[..]bar)\",\
+            \"dependencies\":[]\
+        },{\
+            \"id\":\"foo 0.5.0 (path+file:[..]foo)\",\
+            \"dependencies\":[]\
+        }]\
+    },\
+    \"version\":1\
+}"));
+});
+
+test!(cargo_metadata_virtual_workspace {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["bar"]
+        "#)
+        .file("bar/Cargo.toml", &basic_lib_manifest("bar"))
+        .file("bar/src/bar.rs", "");
+
+    assert_that(p.cargo_process("metadata"), execs().with_status(0).with_stdout("\
+{[..]\
+    \"workspace_members\":[\"bar 0.5.0 (path+file:[..]bar)\"],\
+    \"resolve\":{\
+        \"root\":null,\
+        \"nodes\":[{\
+            \"id\":\"bar 0.5.0 (path+file:[..]bar)\",\
+            \"dependencies\":[]\
+        }]\
+    },\
+    \"version\":1\
+}"));
+});
+
+test!(cargo_metadata_bad_version {
+    let p = project("foo")
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/foo.rs", &main_file(r#""i am foo""#, &[]));
+
+    assert_that(p.cargo_process("metadata").arg("--format-version").arg("2"),
+                execs().with_status(101).with_stderr("\
+metadata version 2 not supported, only 1 is currently supported
+"));
+});
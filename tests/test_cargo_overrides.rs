@@ -0,0 +1,162 @@
+use support::{project, execs};
+use support::git;
+use support::registry as r;
+
+use hamcrest::assert_that;
+
+fn setup() {
+    r::init();
+}
+
+test!(override_with_path {
+    r::mock_pkg("foo", "0.1.0", &[]);
+
+    let p = project("local")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "local"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1.0"
+
+            [replace]
+            "foo:0.1.0" = { path = "foo" }
+        "#)
+        .file("src/lib.rs", "
+            extern crate foo;
+            pub fn bar() {
+                foo::foo();
+            }
+        ")
+        .file("foo/Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("foo/src/lib.rs", "pub fn foo() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
+
+test!(override_with_git {
+    r::mock_pkg("foo", "0.1.0", &[]);
+
+    let foo = git::new("override", |project| {
+        project.file("Cargo.toml", r#"
+                    [package]
+                    name = "foo"
+                    version = "0.1.0"
+                    authors = []
+                "#)
+               .file("src/lib.rs", "pub fn foo() {}")
+    }).unwrap();
+
+    let p = project("local")
+        .file("Cargo.toml", &format!(r#"
+            [package]
+            name = "local"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1.0"
+
+            [replace]
+            "foo:0.1.0" = {{ git = '{}' }}
+        "#, foo.url()))
+        .file("src/lib.rs", "
+            extern crate foo;
+            pub fn bar() {
+                foo::foo();
+            }
+        ");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
+
+test!(override_wrong_version {
+    r::mock_pkg("foo", "0.1.0", &[]);
+
+    let p = project("local")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "local"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1.0"
+
+            [replace]
+            "foo:0.1.0" = { path = "foo" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("foo/Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.2.0"
+            authors = []
+        "#)
+        .file("foo/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+no matching package for override `foo:0.1.0` found
+location searched: [..]
+version required: [..]0.1.0
+"));
+});
+
+test!(missing_version {
+    let p = project("local")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "local"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1.0"
+
+            [replace]
+            foo = { path = "foo" }
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  replacements must specify a version to replace, but `foo` does not
+"));
+});
+
+test!(version_in_replacement {
+    let p = project("local")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "local"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1.0"
+
+            [replace]
+            "foo:0.1.0" = { path = "foo", version = "0.1.0" }
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  replacements cannot specify a version requirement, but found one for \
+`foo:0.1.0`
+"));
+});
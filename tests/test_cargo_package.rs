@@ -1,7 +1,7 @@
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Cursor;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use cargo::util::process;
 use flate2::read::GzDecoder;
@@ -9,6 +9,7 @@ use git2;
 use tar::Archive;
 
 use support::{project, execs, cargo_dir, paths, git, path2url};
+use support::registry as r;
 use support::{PACKAGING, VERIFYING, COMPILING, ARCHIVING};
 use hamcrest::{assert_that, existing_file};
 
@@ -247,6 +248,48 @@ test!(include {
 ", packaging = PACKAGING, archiving = ARCHIVING)));
 });
 
+test!(readme_always_included {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            readme = "README.md"
+            include = ["**/*.rs", "Cargo.toml"]
+        "#)
+        .file("README.md", "# foo")
+        .file("src/main.rs", r#"
+            fn main() { println!("hello"); }
+        "#);
+
+    assert_that(p.cargo_process("package").arg("--list"),
+                execs().with_status(0).with_stdout("\
+Cargo.toml
+README.md
+src[..]main.rs
+"));
+});
+
+test!(missing_readme {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            readme = "README.md"
+        "#)
+        .file("src/main.rs", r#"
+            fn main() { println!("hello"); }
+        "#);
+
+    assert_that(p.cargo_process("package").arg("--no-verify"),
+                execs().with_status(101).with_stderr("\
+the file `README.md` specified by `readme` does not exist
+"));
+});
+
 test!(package_lib_with_bin {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -371,3 +414,219 @@ src[..]main.rs
                 "unexpected filename: {:?}", f.header().path())
     }
 });
+
+test!(path_dependencies_normalized {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies.bar]
+            path = "bar"
+            version = "0.1.0"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("package").arg("--no-verify"),
+                execs().with_status(0));
+
+    let f = File::open(&p.root().join("target/package/foo-0.0.1.crate")).unwrap();
+    let mut rdr = GzDecoder::new(f).unwrap();
+    let mut contents = Vec::new();
+    rdr.read_to_end(&mut contents).unwrap();
+    let ar = Archive::new(Cursor::new(contents));
+    let mut manifest = None;
+    for f in ar.files().unwrap() {
+        let mut f = f.unwrap();
+        if &*f.header().path_bytes() == b"foo-0.0.1/Cargo.toml" {
+            let mut contents = String::new();
+            f.read_to_string(&mut contents).unwrap();
+            manifest = Some(contents);
+        }
+    }
+    let manifest = manifest.unwrap();
+    assert!(manifest.contains("version = \"0.1.0\""), "{}", manifest);
+    assert!(!manifest.contains("path = "), "{}", manifest);
+});
+
+test!(reproducible_tarball {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("src/bar.rs", "");
+
+    let crate_file = p.root().join("target/package/foo-0.0.1.crate");
+    let read = |path: &Path| {
+        let mut contents = Vec::new();
+        File::open(path).unwrap().read_to_end(&mut contents).unwrap();
+        contents
+    };
+
+    assert_that(p.cargo_process("package").arg("--no-verify"),
+                execs().with_status(0));
+    let first = read(&crate_file);
+    fs::remove_file(&crate_file).unwrap();
+
+    // Touching the sources doesn't change the tarball
+    File::create(&p.root().join("src/bar.rs")).unwrap();
+    assert_that(p.cargo("package").arg("--no-verify"),
+                execs().with_status(0));
+    assert!(first == read(&crate_file));
+});
+
+test!(uncommitted_changes {
+    let p = git::new("foo", |p| {
+        p.file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+    }).unwrap();
+    File::create(&p.root().join("src/main.rs")).unwrap()
+        .write_all(b"fn main() { println!(\"changed\"); }").unwrap();
+
+    assert_that(p.cargo("package").arg("--no-verify"),
+                execs().with_status(101).with_stderr("\
+1 files in the working directory contain changes that were not yet \
+committed into git:
+
+src[..]main.rs
+
+to proceed despite this, pass the `--allow-dirty` flag
+"));
+
+    assert_that(p.cargo("package").arg("--no-verify").arg("--allow-dirty"),
+                execs().with_status(0));
+});
+
+// Unpacks `crate_file` into a directory outside of any project, like a
+// package downloaded from a registry.
+fn unpack(crate_file: &Path) -> PathBuf {
+    let dst = paths::root().join("unpacked");
+    let f = GzDecoder::new(File::open(crate_file).unwrap()).unwrap();
+    let mut archive = Archive::new(f);
+    archive.unpack(&dst).unwrap();
+    dst
+}
+
+test!(inherited_dependency_builds_outside_workspace {
+    r::init();
+    r::mock_pkg_files("bar", "0.1.0", &[
+        ("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+
+            [features]
+            fast = []
+        "#),
+        ("src/lib.rs", r#"
+            #[cfg(feature = "fast")]
+            pub fn fast() {}
+        "#),
+    ]);
+
+    let p = project("ws")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["foo"]
+
+            [workspace.dependencies]
+            bar = { version = "0.1.0", features = ["fast"] }
+        "#)
+        .file("foo/Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+            workspace = ".."
+
+            [dependencies]
+            bar = { workspace = true }
+        "#)
+        .file("foo/src/lib.rs", r#"
+            extern crate bar;
+            pub fn foo() { bar::fast() }
+        "#);
+    p.build();
+
+    assert_that(p.cargo("package").cwd(p.root().join("foo")),
+                execs().with_status(0));
+
+    let dst = unpack(&p.root().join("target/package/foo-0.0.1.crate"))
+                  .join("foo-0.0.1");
+    let mut manifest = String::new();
+    File::open(&dst.join("Cargo.toml")).unwrap()
+        .read_to_string(&mut manifest).unwrap();
+    assert!(!manifest.contains("workspace"), "{}", manifest);
+    assert!(manifest.contains("version = \"0.1.0\""), "{}", manifest);
+    assert!(manifest.contains("\"fast\""), "{}", manifest);
+
+    assert_that(p.cargo("build").cwd(&dst),
+                execs().with_status(0));
+});
+
+test!(alt_registry_dependency_builds_outside_workspace {
+    r::init();
+    r::init_alt();
+    r::mock_alt_pkg("bar", "0.0.1", &[]);
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies]
+            bar = { version = "0.0.1", registry = "alternative" }
+        "#)
+        .file("src/lib.rs", "extern crate bar;")
+        .file(".cargo/config", &format!(r#"
+            [registries.alternative]
+            index = "{}"
+        "#, r::alt_registry()));
+
+    assert_that(p.cargo_process("package"),
+                execs().with_status(0));
+
+    // The unpacked package no longer sees the `[registries]` table above
+    let dst = unpack(&p.root().join("target/package/foo-0.0.1.crate"))
+                  .join("foo-0.0.1");
+    let mut manifest = String::new();
+    File::open(&dst.join("Cargo.toml")).unwrap()
+        .read_to_string(&mut manifest).unwrap();
+    assert!(manifest.contains(&format!("registry-index = \"{}\"",
+                                       r::alt_registry())), "{}", manifest);
+    assert!(!manifest.contains("registry = "), "{}", manifest);
+
+    assert_that(p.cargo("build").cwd(&dst),
+                execs().with_status(0));
+});
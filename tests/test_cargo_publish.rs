@@ -4,11 +4,12 @@ use std::io::{Cursor, SeekFrom};
 use std::path::PathBuf;
 
 use flate2::read::GzDecoder;
+use rustc_serialize::json::Json;
 use tar::Archive;
 use url::Url;
 
 use support::{project, execs};
-use support::{UPDATING, PACKAGING, UPLOADING};
+use support::{UPDATING, PACKAGING, UPLOADING, VERIFYING, COMPILING};
 use support::paths;
 use support::git::repo;
 
@@ -37,6 +38,20 @@ fn setup() {
         .build();
 }
 
+// The metadata sent along with the last uploaded package
+fn upload_json() -> Json {
+    let mut f = File::open(&upload_path().join("api/v1/crates/new")).unwrap();
+    let mut sz = [0; 4];
+    assert_eq!(f.read(&mut sz).unwrap(), 4);
+    let sz = ((sz[0] as u32) <<  0) |
+             ((sz[1] as u32) <<  8) |
+             ((sz[2] as u32) << 16) |
+             ((sz[3] as u32) << 24);
+    let mut json = String::new();
+    f.take(sz as u64).read_to_string(&mut json).unwrap();
+    Json::from_str(&json).unwrap()
+}
+
 test!(simple {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -138,3 +153,165 @@ all path dependencies must have a version specified when publishing.
 dependency `bar` does not specify a version
 "));
 });
+
+test!(unpublishable_crate {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+            publish = false
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify"),
+                execs().with_status(101).with_stderr("\
+some crates cannot be published.
+`foo` is marked as unpublishable
+"));
+});
+
+test!(publish_to_unlisted_registry {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+            publish = ["internal"]
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify"),
+                execs().with_status(101).with_stderr("\
+`foo` cannot be published to `crates-io`, it may only be published to: \
+internal
+"));
+});
+
+test!(dry_run {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--dry-run"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `[..]`
+{packaging} foo v0.0.1 ({dir})
+{verifying} foo v0.0.1 ({dir})
+{compiling} foo v0.0.1 [..]
+",
+        updating = UPDATING,
+        packaging = PACKAGING,
+        verifying = VERIFYING,
+        compiling = COMPILING,
+        dir = p.url()))
+                       .with_stderr("\
+warning: aborting upload due to dry run
+"));
+
+    // Skipped publishing, so there's nothing uploaded
+    assert!(fs::metadata(&upload_path().join("api/v1/crates/new")).is_err());
+});
+
+test!(renamed_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies]
+            baz = { package = "bar", version = "0.1" }
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify"),
+                execs().with_status(0));
+
+    let json = upload_json();
+    let dep = &json.find("deps").unwrap().as_array().unwrap()[0];
+    assert_eq!(dep.find("name").unwrap().as_string(), Some("bar"));
+    assert_eq!(dep.find("explicit_name_in_toml").unwrap().as_string(),
+               Some("baz"));
+});
+
+test!(alt_registry_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies]
+            bar = { version = "0.1", registry = "alternative" }
+            baz = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [registries.alternative]
+            index = "file:///path/to/alternative"
+        "#);
+
+    assert_that(p.cargo_process("publish").arg("--no-verify"),
+                execs().with_status(0));
+
+    let json = upload_json();
+    let deps = json.find("deps").unwrap().as_array().unwrap();
+    let registry = |name: &str| {
+        let dep = deps.iter().find(|d| {
+            d.find("name").unwrap().as_string() == Some(name)
+        }).unwrap();
+        dep.find("registry").unwrap().as_string().map(|s| s.to_string())
+    };
+    assert_eq!(registry("bar"),
+               Some("file:///path/to/alternative".to_string()));
+    assert_eq!(registry("baz"), None);
+});
+
+test!(publish_to_allowed_registry {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+            publish = ["internal"]
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", &format!(r#"
+            [registries.internal]
+            index = "{}"
+        "#, registry()));
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--registry").arg("internal"),
+                execs().with_status(0));
+    fs::remove_file(&upload_path().join("api/v1/crates/new")).unwrap();
+
+    // The index of `internal` goes by that name when given with `--host`
+    assert_that(p.cargo("publish").arg("--no-verify")
+                 .arg("--host").arg(registry().to_string()),
+                execs().with_status(0));
+    assert!(fs::metadata(&upload_path().join("api/v1/crates/new")).is_ok());
+});
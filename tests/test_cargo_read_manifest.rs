@@ -8,6 +8,7 @@ fn read_manifest_output() -> String {
 {\
     \"name\":\"foo\",\
     \"version\":\"0.5.0\",\
+    \"id\":\"foo[..]0.5.0[..](path+file://[..]/foo)\",\
     \"dependencies\":[],\
     \"targets\":[{\
         \"kind\":[\"bin\"],\
@@ -15,7 +16,8 @@ fn read_manifest_output() -> String {
         \"src_path\":\"src[..]foo.rs\",\
         \"metadata\":null\
     }],\
-    \"manifest_path\":\"[..]Cargo.toml\"\
+    \"manifest_path\":\"[..]Cargo.toml\",\
+    \"metadata\":null\
 }".into()
 }
 
@@ -77,3 +79,21 @@ test!(cargo_read_manifest_cwd {
                 execs().with_status(0)
                        .with_stdout(read_manifest_output()));
 });
+
+test!(cargo_read_manifest_custom_metadata {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [package.metadata.deb]
+            section = "utils"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("read-manifest").cwd(p.root()),
+                execs().with_status(0).with_stdout("\
+{[..]\"metadata\":{\"deb\":{\"section\":\"utils\"}}}"));
+});
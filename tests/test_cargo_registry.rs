@@ -4,6 +4,7 @@ use cargo::util::process;
 
 use support::{project, execs, cargo_dir};
 use support::{UPDATING, DOWNLOADING, COMPILING, PACKAGING, VERIFYING, ADDING, REMOVING};
+use support::RUNNING;
 use support::paths::{self, CargoPathExt};
 use support::registry as r;
 use support::git;
@@ -564,6 +565,33 @@ test!(login_with_no_cargo_dir {
                 execs().with_status(0));
 });
 
+test!(login_writes_credentials {
+    let home = paths::home().join("new-home");
+    fs::create_dir(&home).unwrap();
+    assert_that(process(&cargo_dir().join("cargo")).unwrap()
+                       .arg("login").arg("my-token")
+                       .cwd(&paths::root())
+                       .env("HOME", &home),
+                execs().with_status(0));
+
+    let credentials = home.join(".cargo/credentials");
+    let mut contents = String::new();
+    File::open(&credentials).unwrap().read_to_string(&mut contents).unwrap();
+    assert!(contents.contains("token = \"my-token\""), "{}", contents);
+    assert!(fs::metadata(home.join(".cargo/config")).is_err());
+    check_permissions(&credentials);
+
+    #[cfg(unix)]
+    fn check_permissions(path: &::std::path::Path) {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[cfg(not(unix))]
+    fn check_permissions(_path: &::std::path::Path) {}
+});
+
 test!(bad_license_file {
     let p = project("all")
         .file("Cargo.toml", r#"
@@ -829,3 +857,639 @@ test!(update_backtracking_ok {
 {updating} registry `[..]`
 ", updating = UPDATING)));
 });
+
+test!(alt_registry_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { version = "0.0.1", registry = "alternative" }
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", &format!(r#"
+            [registries.alternative]
+            index = "{}"
+        "#, r::registry()));
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `{reg}`
+{downloading} bar v0.0.1 (registry file://[..])
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        compiling = COMPILING,
+        dir = p.url(),
+        reg = r::registry())));
+});
+
+test!(alt_registry_dependency_from_index {
+    r::init_alt();
+    r::mock_alt_pkg("bar", "0.0.1", &[]);
+
+    // `foo` lives in the main registry and depends on `bar` from the other
+    r::mock_archive_files("foo", "0.0.1", &[
+        ("Cargo.toml", &format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = {{ version = "0.0.1", registry-index = "{}" }}
+        "#, r::alt_registry())),
+        ("src/lib.rs", "extern crate bar;"),
+    ]);
+    let mut archive = Vec::new();
+    File::open(&r::mock_archive_dst("foo", "0.0.1")).unwrap()
+         .read_to_end(&mut archive).unwrap();
+    r::publish("3/f/foo", &format!(r#"{{"name":"foo","vers":"0.0.1",
+        "deps":[{{"name":"bar","req":"^0.0.1","features":[],
+                  "default_features":true,"target":null,"optional":false,
+                  "kind":"normal","registry":"{}"}}],
+        "cksum":"{}","features":{{}},"yanked":false}}"#,
+        r::alt_registry(), r::cksum(&archive)).replace("\n", ""));
+
+    let p = project("local")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "local"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.0.1"
+        "#)
+        .file("src/main.rs", "extern crate foo; fn main() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    let mut lock = String::new();
+    File::open(&p.root().join("Cargo.lock")).unwrap()
+         .read_to_string(&mut lock).unwrap();
+    assert!(lock.contains(&format!("\"bar 0.0.1 (registry+{})\"",
+                                   r::alt_registry())), "{}", lock);
+});
+
+test!(unknown_alt_registry {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { version = "0.0.1", registry = "missing" }
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  no index found for registry `missing`, it can be configured with \
+`registries.missing.index` in .cargo/config
+"));
+});
+
+test!(conflict_shows_requirement_chain {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = "1"
+            b = "1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    r::mock_pkg("log", "1.0.0", &[]);
+    r::mock_pkg("log", "1.0.1", &[]);
+    r::mock_pkg("a", "1.0.0", &[("log", "=1.0.0", "normal")]);
+    r::mock_pkg("b", "1.0.0", &[("log", "=1.0.1", "normal")]);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to select a version for `log` (required by `[..]`):
+all possible versions conflict with previously selected versions of `log`
+  version 1.0.[..] in use by:
+    `[..] v1.0.0` requires `log [..]1.0.[..]`
+    `foo v0.0.1` requires `[..] ^1`
+  but `[..] v1.0.0` requires `log [..]1.0.[..]`
+    `foo v0.0.1` requires `[..] ^1`
+  possible versions to select: 1.0.[..]
+"));
+});
+
+test!(minimal_versions {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            a = "0.1.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("a", "0.1.0", &[]);
+    r::mock_pkg("a", "0.1.1", &[]);
+    r::mock_pkg("a", "0.1.2", &[]);
+
+    assert_that(p.cargo("-Z").arg("minimal-versions").arg("generate-lockfile"),
+                execs().with_status(0));
+
+    let mut lock = String::new();
+    File::open(&p.root().join("Cargo.lock")).unwrap()
+         .read_to_string(&mut lock).unwrap();
+    assert!(lock.contains("\"a 0.1.1 "), "{}", lock);
+});
+
+test!(minimal_versions_joined_flag {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            a = "0.1.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("a", "0.1.0", &[]);
+    r::mock_pkg("a", "0.1.1", &[]);
+    r::mock_pkg("a", "0.1.2", &[]);
+
+    assert_that(p.cargo("-Zminimal-versions").arg("generate-lockfile"),
+                execs().with_status(0));
+
+    let mut lock = String::new();
+    File::open(&p.root().join("Cargo.lock")).unwrap()
+         .read_to_string(&mut lock).unwrap();
+    assert!(lock.contains("\"a 0.1.1 "), "{}", lock);
+});
+
+test!(unknown_unstable_flag {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(p.cargo("-Z").arg("bogus").arg("build"),
+                execs().with_status(101).with_stderr("\
+unknown `-Z` flag specified: bogus
+"));
+});
+
+test!(offline_uses_cached_packages {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo("fetch"), execs().with_status(0));
+
+    assert_that(p.cargo("--offline").arg("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} foo v0.5.0 ({dir})
+", compiling = COMPILING, dir = p.url())));
+});
+
+test!(offline_after_other_global_flags {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo("fetch"), execs().with_status(0));
+
+    assert_that(p.cargo("-v").arg("--offline").arg("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} bar v0.0.1 (registry file://[..])
+{running} `rustc [..]`
+{compiling} foo v0.5.0 ({dir})
+{running} `rustc [..]`
+", compiling = COMPILING, running = RUNNING, dir = p.url())));
+});
+
+test!(offline_missing_package {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [net]
+            offline = true
+        "#);
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+package `bar` was not found in the cached index of `[..]`, and the index \
+cannot be updated in offline mode
+"));
+});
+
+test!(sparse_registry_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { version = "0.0.1", registry = "sparse" }
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", &format!(r#"
+            [registries.sparse]
+            index = "sparse+{}"
+        "#, r::registry()));
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `sparse+{reg}`
+{downloading} bar v0.0.1 (registry sparse+file://[..])
+{compiling} bar v0.0.1 (registry sparse+file://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        compiling = COMPILING,
+        dir = p.url(),
+        reg = r::registry())));
+});
+
+test!(replaced_source {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { version = "0.0.1", registry = "alternative" }
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", &format!(r#"
+            [registries.alternative]
+            index = "https://example.com/nowhere"
+
+            [source.alternative]
+            registry = "https://example.com/nowhere"
+            replace-with = "mirror"
+
+            [source.mirror]
+            registry = "{}"
+        "#, r::registry()));
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `{reg}`
+{downloading} bar v0.0.1 ([..])
+{compiling} bar v0.0.1 (registry https://example.com/nowhere)
+{compiling} foo v0.0.1 ({dir})
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        compiling = COMPILING,
+        dir = p.url(),
+        reg = r::registry())));
+});
+
+test!(replaced_source_missing {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [source.crates-io]
+            replace-with = "bogus"
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+Unable to update registry [..]
+
+Caused by:
+  could not find a configured source with the name `bogus` when attempting to \
+lookup `crates-io` (configuration in `[..]`)
+"));
+});
+
+test!(checksums_in_lockfile {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let mut c = Vec::new();
+    File::open(&r::mock_archive_dst("bar", "0.0.1")).unwrap()
+         .read_to_end(&mut c).unwrap();
+    let mut lock = String::new();
+    File::open(&p.root().join("Cargo.lock")).unwrap()
+         .read_to_string(&mut lock).unwrap();
+    let line = format!("\"checksum bar 0.0.1 (registry+{})\" = \"{}\"",
+                       r::registry(), r::cksum(&c));
+    assert!(lock.contains(&line), "{}", lock);
+
+    // Building again leaves the lock file alone
+    assert_that(p.cargo("--locked").arg("build"), execs().with_status(0));
+});
+
+test!(checksum_changed_in_lockfile {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("generate-lockfile"), execs().with_status(0));
+
+    let mut c = Vec::new();
+    File::open(&r::mock_archive_dst("bar", "0.0.1")).unwrap()
+         .read_to_end(&mut c).unwrap();
+    let lockfile = p.root().join("Cargo.lock");
+    let mut lock = String::new();
+    File::open(&lockfile).unwrap().read_to_string(&mut lock).unwrap();
+    let lock = lock.replace(&r::cksum(&c), &r::cksum(b"something else"));
+    File::create(&lockfile).unwrap().write_all(lock.as_bytes()).unwrap();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr("\
+checksum for `bar v0.0.1 (registry file://[..])` changed between lock files
+
+this could be indicative of a few possible errors:
+
+    * the lock file is corrupt
+    * a replacement source in use (e.g. a mirror) returned a different checksum
+    * the source itself may be corrupt in one way or another
+
+unable to verify that `bar v0.0.1 (registry file://[..])` is the same as when \
+the lockfile was generated
+"));
+});
+
+test!(parallel_downloads {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = "0.0.1"
+            b = "0.0.1"
+            c = "0.0.1"
+            d = "0.0.1"
+            e = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [net]
+            download-jobs = 2
+        "#);
+
+    for name in ["a", "b", "c", "d", "e"].iter() {
+        r::mock_pkg(name, "0.0.1", &[]);
+    }
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `{reg}`
+{downloading} [..] v0.0.1 (registry file://[..])
+{downloading} [..] v0.0.1 (registry file://[..])
+{downloading} [..] v0.0.1 (registry file://[..])
+{downloading} [..] v0.0.1 (registry file://[..])
+{downloading} [..] v0.0.1 (registry file://[..])
+{compiling} [..] v0.0.1 (registry file://[..])
+{compiling} [..] v0.0.1 (registry file://[..])
+{compiling} [..] v0.0.1 (registry file://[..])
+{compiling} [..] v0.0.1 (registry file://[..])
+{compiling} [..] v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        compiling = COMPILING,
+        dir = p.url(),
+        reg = r::registry())));
+});
+
+test!(stale_partial_download {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    // Pretend that a previous download was interrupted, leaving behind an
+    // incomplete file
+    let registry = paths::home().join(".cargo/registry");
+    let cache = fs::read_dir(&registry.join("cache")).unwrap()
+                   .next().unwrap().unwrap().path();
+    fs::remove_file(&cache.join("bar-0.0.1.crate")).unwrap();
+    File::create(&cache.join("bar-0.0.1.crate-part")).unwrap()
+         .write_all(b"garbage").unwrap();
+    fs::remove_dir_all(&registry.join("src")).unwrap();
+    fs::remove_dir_all(&p.root().join("target")).unwrap();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{downloading} bar v0.0.1 (registry file://[..])
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        downloading = DOWNLOADING,
+        compiling = COMPILING,
+        dir = p.url())));
+    assert!(fs::metadata(&cache.join("bar-0.0.1.crate")).is_ok());
+    assert!(fs::metadata(&cache.join("bar-0.0.1.crate-part")).is_err());
+});
+
+test!(artifact_cache_shared_between_projects {
+    let manifest = |name: &str| format!(r#"
+        [project]
+        name = "{}"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        bar = "0.0.1"
+    "#, name);
+    let config = r#"
+        [build]
+        artifact-cache = true
+    "#;
+    let foo = project("foo")
+        .file("Cargo.toml", &manifest("foo"))
+        .file(".cargo/config", config)
+        .file("src/main.rs", "extern crate bar; fn main() {}");
+    let baz = project("baz")
+        .file("Cargo.toml", &manifest("baz"))
+        .file(".cargo/config", config)
+        .file("src/main.rs", "extern crate bar; fn main() {}");
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(foo.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `[..]`
+{downloading} bar v0.0.1 (registry file://[..])
+{compiling} bar v0.0.1 (registry file://[..])
+{running} `rustc [..]`
+{compiling} foo v0.0.1 ({dir})
+{running} `rustc [..]`
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        compiling = COMPILING,
+        running = RUNNING,
+        dir = foo.url())));
+    let cache = paths::home().join(".cargo/artifacts");
+    assert_eq!(fs::read_dir(&cache).unwrap().count(), 1);
+
+    // `bar` isn't compiled a second time for another project
+    assert_that(baz.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `[..]`
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} baz v0.0.1 ({dir})
+{running} `rustc [..]`
+",
+        updating = UPDATING,
+        compiling = COMPILING,
+        running = RUNNING,
+        dir = baz.url())));
+    assert_that(baz.process(&baz.bin("baz")), execs().with_status(0));
+});
+
+test!(artifact_cache_skips_dependents_of_build_scripts {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file(".cargo/config", r#"
+            [build]
+            artifact-cache = true
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}");
+
+    r::mock_pkg_files("baz", "0.0.1", &[
+        ("Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+        "#),
+        ("build.rs", "fn main() {}"),
+        ("src/lib.rs", ""),
+    ]);
+    r::mock_pkg("bar", "0.0.1", &[("baz", "0.0.1", "normal")]);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    let cache = paths::home().join(".cargo/artifacts");
+    assert_eq!(fs::read_dir(&cache).map(|d| d.count()).unwrap_or(0), 0);
+});
@@ -0,0 +1,176 @@
+use std::fs::File;
+use std::io::prelude::*;
+
+use support::{project, execs};
+use support::registry as r;
+
+use hamcrest::assert_that;
+
+fn setup() {
+    r::init();
+}
+
+test!(rename_path_dep {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { package = "bar", path = "bar" }
+        "#)
+        .file("src/main.rs", r#"
+            extern crate baz;
+
+            fn main() { baz::bar(); }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
+
+test!(rename_with_different_versions {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.2"
+            old_bar = { package = "bar", version = "0.1" }
+        "#)
+        .file("src/main.rs", r#"
+            extern crate bar;
+            extern crate old_bar;
+
+            fn main() {}
+        "#);
+
+    r::mock_pkg("bar", "0.1.0", &[]);
+    r::mock_pkg("bar", "0.2.0", &[]);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
+
+test!(rename_optional_dep_feature {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { package = "bar", path = "bar", optional = true }
+
+            [features]
+            default = ["baz"]
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(feature = "baz")]
+            extern crate baz;
+
+            #[cfg(feature = "baz")]
+            fn main() { baz::bar(); }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
+
+test!(rename_unknown_in_features {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { package = "bar", path = "bar", optional = true }
+
+            [features]
+            default = ["bar"]
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  Feature `default` includes `bar` which is neither a dependency nor another \
+feature
+"));
+});
+
+test!(rename_in_registry_package {
+    // `foo` is published depending on `bar` under the name `baz`
+    r::mock_pkg_files("bar", "0.1.0", &[
+        ("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#),
+        ("src/lib.rs", "pub fn bar() {}"),
+    ]);
+    r::mock_archive_files("foo", "0.1.0", &[
+        ("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            baz = { package = "bar", version = "0.1" }
+        "#),
+        ("src/lib.rs", "extern crate baz; pub fn foo() { baz::bar() }"),
+    ]);
+    let mut archive = Vec::new();
+    File::open(&r::mock_archive_dst("foo", "0.1.0")).unwrap()
+         .read_to_end(&mut archive).unwrap();
+    r::publish("3/f/foo", &format!(r#"{{"name":"foo","vers":"0.1.0",
+        "deps":[{{"name":"baz","package":"bar","req":"^0.1","features":[],
+                  "default_features":true,"target":null,"optional":false,
+                  "kind":"normal"}}],
+        "cksum":"{}","features":{{}},"yanked":false}}"#,
+        r::cksum(&archive)).replace("\n", ""));
+
+    let p = project("local")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "local"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1"
+        "#)
+        .file("src/main.rs", "extern crate foo; fn main() { foo::foo() }");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
@@ -182,6 +182,63 @@ hello b.rs
         sep = SEP)));
 });
 
+test!(default_run {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            default-run = "b"
+        "#)
+        .file("src/bin/a.rs", r#"
+            fn main() { println!("hello a.rs"); }
+        "#)
+        .file("src/bin/b.rs", r#"
+            fn main() { println!("hello b.rs"); }
+        "#);
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} foo v0.0.1 ([..])
+{running} `target{sep}debug{sep}b[..]`
+hello b.rs
+",
+        compiling = COMPILING,
+        running = RUNNING,
+        sep = SEP)));
+
+    assert_that(p.cargo("run").arg("--bin").arg("a"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} foo v0.0.1 ([..])
+{running} `target{sep}debug{sep}a[..]`
+hello a.rs
+",
+        compiling = COMPILING,
+        running = RUNNING,
+        sep = SEP)));
+});
+
+test!(default_run_unknown_bin {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            default-run = "c"
+        "#)
+        .file("src/bin/a.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+
+Caused by:
+  `default-run` target `c` is not one of the binaries of this package
+"));
+});
+
 test!(run_example {
     let p = project("foo")
         .file("Cargo.toml", r#"
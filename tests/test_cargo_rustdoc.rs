@@ -0,0 +1,54 @@
+use support::{execs, project};
+use support::{COMPILING, RUNNING};
+use hamcrest::{assert_that};
+
+fn setup() {
+}
+
+test!(rustdoc_simple {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", r#"
+            #[cfg(not(from_rustdoc))]
+            pub fn only_with_the_flag() { missing() }
+        "#);
+
+    assert_that(p.cargo_process("rustdoc").arg("-v")
+                 .arg("--").arg("--cfg").arg("from_rustdoc"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} foo v0.0.1 ({url})
+{running} `rustdoc src[..]lib.rs [..]--cfg from_rustdoc`
+",
+            running = RUNNING, compiling = COMPILING, url = p.url())));
+});
+
+test!(rustdoc_selects_one_target {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("rustdoc").arg("--").arg("--cfg").arg("a"),
+                execs().with_status(101).with_stderr("\
+extra arguments to `rustdoc` can only be passed to one target, consider filtering
+the package by passing e.g. `--lib` or `--bin NAME` to specify a single target
+"));
+
+    assert_that(p.cargo("rustdoc").arg("--lib").arg("-v")
+                 .arg("--").arg("--cfg").arg("a"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} foo v0.0.1 ({url})
+{running} `rustdoc src[..]lib.rs [..]--cfg a`
+",
+            running = RUNNING, compiling = COMPILING, url = p.url())));
+});
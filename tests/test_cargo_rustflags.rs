@@ -0,0 +1,63 @@
+use support::{project, execs, ProjectBuilder};
+use support::COMPILING;
+use hamcrest::assert_that;
+
+fn setup() {
+}
+
+fn needs_cfg() -> ProjectBuilder {
+    project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(from_flags)]
+            fn main() {}
+        "#)
+}
+
+test!(env_rustflags {
+    let p = needs_cfg();
+
+    assert_that(p.cargo_process("build"), execs().with_status(101));
+    assert_that(p.cargo("build").env("RUSTFLAGS", "--cfg from_flags"),
+                execs().with_status(0));
+});
+
+test!(build_rustflags_config {
+    let p = needs_cfg()
+        .file(".cargo/config", r#"
+            [build]
+            rustflags = ["--cfg", "from_flags"]
+        "#);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+});
+
+test!(env_overrides_config {
+    let p = needs_cfg()
+        .file(".cargo/config", r#"
+            [build]
+            rustflags = ["--cfg", "from_flags"]
+        "#);
+
+    assert_that(p.cargo_process("build").env("RUSTFLAGS", "--cfg other"),
+                execs().with_status(101));
+});
+
+test!(changing_rustflags_rebuilds {
+    let p = needs_cfg();
+
+    assert_that(p.cargo_process("build").env("RUSTFLAGS", "--cfg from_flags"),
+                execs().with_status(0));
+    assert_that(p.cargo("build").env("RUSTFLAGS", "--cfg from_flags"),
+                execs().with_status(0).with_stdout(""));
+    assert_that(p.cargo("build")
+                 .env("RUSTFLAGS", "--cfg from_flags --cfg also"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} foo v0.0.1 ({url})
+", compiling = COMPILING, url = p.url())));
+});
@@ -76,14 +76,65 @@ test!(simple {
     // from source there anyway!
     File::create(&base).unwrap().write_all(contents.as_bytes()).unwrap();
     if !cfg!(windows) {
-        File::create(&base.with_file_name("crates?q=postgres")).unwrap()
+        File::create(&base.with_file_name("crates?q=postgres&per_page=10")).unwrap()
              .write_all(contents.as_bytes()).unwrap();
     }
 
     assert_that(cargo_process("search").arg("postgres"),
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
-hoare (0.1.1)    Design by contract style assertions for Rust", updating = UPDATING)));
+hoare = \"0.1.1\"    # Design by contract style assertions for Rust", updating = UPDATING)));
+});
+
+test!(limit {
+    let contents = r#"{
+        "crates": [{
+            "description": null,
+            "max_version": "0.1.1",
+            "name": "hoare"
+        }],
+        "meta": {
+            "total": 3
+        }
+    }"#;
+    let base = api_path().join("api/v1/crates");
+
+    File::create(&base).unwrap().write_all(contents.as_bytes()).unwrap();
+    if !cfg!(windows) {
+        File::create(&base.with_file_name("crates?q=postgres&per_page=1")).unwrap()
+             .write_all(contents.as_bytes()).unwrap();
+    }
+
+    assert_that(cargo_process("search").arg("postgres").arg("--limit").arg("1"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+hoare = \"0.1.1\"
+... and 2 crates more (use --limit N to see more)", updating = UPDATING)));
+});
+
+test!(limit_is_capped {
+    let contents = r#"{
+        "crates": [{
+            "description": null,
+            "max_version": "0.1.1",
+            "name": "hoare"
+        }],
+        "meta": {
+            "total": 1
+        }
+    }"#;
+    let base = api_path().join("api/v1/crates");
+
+    File::create(&base).unwrap().write_all(contents.as_bytes()).unwrap();
+    if !cfg!(windows) {
+        File::create(&base.with_file_name("crates?q=postgres&per_page=100")).unwrap()
+             .write_all(contents.as_bytes()).unwrap();
+    }
+
+    assert_that(cargo_process("search").arg("postgres").arg("--limit").arg("1000"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+hoare = \"0.1.1\"", updating = UPDATING)));
 });
 
 test!(help {
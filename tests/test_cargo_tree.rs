@@ -0,0 +1,261 @@
+use support::{project, execs, ProjectBuilder};
+use hamcrest::assert_that;
+
+fn setup() {}
+
+fn diamond() -> ProjectBuilder {
+    project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = { path = "a" }
+            b = { path = "b" }
+
+            [target.some-other-triple.dependencies]
+            e = { path = "e" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            c = { path = "../c" }
+        "#)
+        .file("a/src/lib.rs", "")
+        .file("b/Cargo.toml", r#"
+            [package]
+            name = "b"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            c = { path = "../c" }
+        "#)
+        .file("b/src/lib.rs", "")
+        .file("c/Cargo.toml", r#"
+            [package]
+            name = "c"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            d = { path = "../d" }
+        "#)
+        .file("c/src/lib.rs", "")
+        .file("d/Cargo.toml", r#"
+            [package]
+            name = "d"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("d/src/lib.rs", "")
+        .file("e/Cargo.toml", r#"
+            [package]
+            name = "e"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("e/src/lib.rs", "")
+}
+
+test!(simple_tree {
+    let p = diamond();
+
+    assert_that(p.cargo_process("tree"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+|-- a v0.0.1 ([..])
+|   `-- c v0.0.1 ([..])
+|       `-- d v0.0.1 ([..])
+|-- b v0.0.1 ([..])
+|   `-- c v0.0.1 ([..]) (*)
+`-- e v0.0.1 ([..])
+"));
+});
+
+test!(filter_by_target {
+    let p = diamond();
+
+    assert_that(p.cargo_process("tree")
+                 .arg("--target").arg("x86_64-unknown-linux-gnu"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+|-- a v0.0.1 ([..])
+|   `-- c v0.0.1 ([..])
+|       `-- d v0.0.1 ([..])
+`-- b v0.0.1 ([..])
+    `-- c v0.0.1 ([..]) (*)
+"));
+});
+
+test!(filter_cfg_by_target {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [target.'cfg(target_os = "linux")'.dependencies]
+            a = { path = "a" }
+
+            [target.'cfg(windows)'.dependencies]
+            b = { path = "b" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("a/src/lib.rs", "")
+        .file("b/Cargo.toml", r#"
+            [package]
+            name = "b"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("b/src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree")
+                 .arg("--target").arg("x86_64-unknown-linux-gnu"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+`-- a v0.0.1 ([..])
+"));
+});
+
+test!(inverted_tree {
+    let p = diamond();
+
+    assert_that(p.cargo_process("tree").arg("-i").arg("d"),
+                execs().with_status(0).with_stdout("\
+d v0.0.1 ([..])
+`-- c v0.0.1 ([..])
+    |-- a v0.0.1 ([..])
+    |   `-- foo v0.0.1 ([..])
+    `-- b v0.0.1 ([..])
+        `-- foo v0.0.1 ([..])
+"));
+});
+
+test!(tree_with_features {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = { path = "a", optional = true }
+        "#)
+        .file("src/lib.rs", "")
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("a/src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+"));
+    assert_that(p.cargo("tree").arg("--features").arg("a"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+`-- a v0.0.1 ([..])
+"));
+});
+
+test!(duplicates {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = { path = "a" }
+            bar = { path = "bar2" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "../bar1" }
+        "#)
+        .file("a/src/lib.rs", "")
+        .file("bar1/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("bar1/src/lib.rs", "")
+        .file("bar2/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.2.0"
+            authors = []
+        "#)
+        .file("bar2/src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree").arg("--duplicates"),
+                execs().with_status(0).with_stdout("\
+bar v0.1.0 ([..])
+`-- a v0.0.1 ([..])
+    `-- foo v0.0.1 ([..])
+
+bar v0.2.0 ([..])
+`-- foo v0.0.1 ([..])
+"));
+});
+
+test!(virtual_workspace {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["a", "b"]
+        "#)
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            b = { path = "../b" }
+        "#)
+        .file("a/src/lib.rs", "")
+        .file("b/Cargo.toml", r#"
+            [package]
+            name = "b"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("b/src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree"),
+                execs().with_status(0).with_stdout("\
+a v0.0.1 ([..])
+`-- b v0.0.1 ([..])
+
+b v0.0.1 ([..])
+"));
+});
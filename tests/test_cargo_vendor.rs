@@ -0,0 +1,184 @@
+use std::fs::{self, File};
+use std::io::prelude::*;
+
+use support::{project, execs, UPDATING, DOWNLOADING, COMPILING};
+use support::git;
+use support::paths;
+use support::registry as r;
+
+use hamcrest::assert_that;
+
+fn setup() {
+    r::init();
+}
+
+static VENDORING: &'static str = "   Vendoring";
+
+static CONFIG: &'static str = r#"
+[source.crates-io]
+replace-with = "vendored-sources"
+
+[source.vendored-sources]
+directory = "vendor"
+"#;
+
+test!(vendor_and_build {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}");
+
+    r::mock_pkg("baz", "0.0.1", &[]);
+    r::mock_pkg("bar", "0.0.1", &[("baz", "*", "normal")]);
+
+    assert_that(p.cargo_process("vendor"),
+                execs().with_status(0).with_stdout(&format!("\
+{updating} registry `{reg}`
+{downloading} [..] v0.0.1 (registry file://[..])
+{downloading} [..] v0.0.1 (registry file://[..])
+{vendoring} bar v0.0.1 (registry file://[..])
+{vendoring} baz v0.0.1 (registry file://[..])
+To use vendored sources, add this to your .cargo/config for this project:
+
+[source.crates-io]
+replace-with = \"vendored-sources\"
+
+[source.vendored-sources]
+directory = \"vendor\"
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        vendoring = VENDORING,
+        reg = r::registry())));
+
+    assert!(fs::metadata(&p.root().join("vendor/bar-0.0.1/Cargo.toml")).is_ok());
+    assert!(fs::metadata(&p.root().join("vendor/baz-0.0.1/.cargo-checksum.json")).is_ok());
+
+    // Neither the registry nor anything downloaded from it is needed anymore.
+    fs::remove_dir_all(&r::registry_path()).unwrap();
+    fs::remove_dir_all(&paths::home().join(".cargo/registry")).unwrap();
+    fs::create_dir_all(&p.root().join(".cargo")).unwrap();
+    File::create(&p.root().join(".cargo/config")).unwrap()
+         .write_all(CONFIG.as_bytes()).unwrap();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stdout(&format!("\
+{compiling} baz v0.0.1 (registry file://[..])
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        compiling = COMPILING,
+        dir = p.url())));
+});
+
+test!(removes_stale_packages {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+    r::mock_pkg("bar", "0.0.2", &[]);
+
+    assert_that(p.cargo_process("vendor"), execs().with_status(0));
+    assert!(fs::metadata(&p.root().join("vendor/bar-0.0.1")).is_ok());
+
+    File::create(&p.root().join("Cargo.toml")).unwrap().write_all(br#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        bar = "0.0.2"
+    "#).unwrap();
+
+    assert_that(p.cargo("vendor"), execs().with_status(0));
+    assert!(fs::metadata(&p.root().join("vendor/bar-0.0.1")).is_err());
+    assert!(fs::metadata(&p.root().join("vendor/bar-0.0.2")).is_ok());
+});
+
+test!(modified_vendored_file {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("vendor"), execs().with_status(0));
+    fs::create_dir_all(&p.root().join(".cargo")).unwrap();
+    File::create(&p.root().join(".cargo/config")).unwrap()
+         .write_all(CONFIG.as_bytes()).unwrap();
+    File::create(&p.root().join("vendor/bar-0.0.1/src/lib.rs")).unwrap()
+         .write_all(b"pub fn changed() {}").unwrap();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr("\
+unable to get packages from source
+
+Caused by:
+  failed to download replaced source `registry file://[..]`
+
+Caused by:
+  the listed checksum of `[..]lib.rs` has changed:
+expected: [..]
+actual:   [..]
+
+directory sources are not intended to be edited, if modifications are \
+required then it is recommended to use a path dependency on a copy of the \
+package instead
+"));
+});
+
+test!(git_dependency_rejected {
+    let bar = git::new("bar", |project| {
+        project.file("Cargo.toml", r#"
+                    [package]
+                    name = "bar"
+                    version = "0.0.1"
+                    authors = []
+                "#)
+               .file("src/lib.rs", "")
+    }).unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = {{ git = '{}' }}
+        "#, bar.url()))
+        .file("src/main.rs", "extern crate bar; fn main() {}");
+
+    assert_that(p.cargo_process("vendor"),
+                execs().with_status(101).with_stderr(&format!("\
+failed to vendor `bar v0.0.1 ({}#[..])`, only packages from a registry can be \
+vendored
+", bar.url())));
+    assert!(fs::metadata(&p.root().join("vendor")).is_err());
+});
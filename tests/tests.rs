@@ -36,31 +36,44 @@ mod test_cargo;
 mod test_cargo_bench;
 mod test_cargo_build_auth;
 mod test_cargo_build_lib;
+mod test_cargo_cfg;
+mod test_cargo_check;
 mod test_cargo_clean;
 mod test_cargo_compile;
 mod test_cargo_compile_custom_build;
 mod test_cargo_compile_git_deps;
 mod test_cargo_compile_path_deps;
 mod test_cargo_compile_plugins;
+mod test_cargo_concurrent;
 mod test_cargo_cross_compile;
 mod test_cargo_doc;
 mod test_cargo_features;
 mod test_cargo_fetch;
 mod test_cargo_freshness;
 mod test_cargo_generate_lockfile;
+mod test_cargo_install;
+mod test_cargo_local_registry;
+mod test_cargo_metadata;
 mod test_cargo_new;
+mod test_cargo_overrides;
 mod test_cargo_package;
 mod test_cargo_profiles;
 mod test_cargo_publish;
 mod test_cargo_read_manifest;
 mod test_cargo_registry;
+mod test_cargo_rename_deps;
 mod test_cargo_run;
 mod test_cargo_rustc;
+mod test_cargo_rustdoc;
+mod test_cargo_rustflags;
 mod test_cargo_search;
 mod test_cargo_test;
+mod test_cargo_tree;
 mod test_cargo_tool_paths;
+mod test_cargo_vendor;
 mod test_cargo_verify_project;
 mod test_cargo_version;
+mod test_cargo_workspaces;
 mod test_shell;
 
 thread_local!(static RUSTC: Rustc = Rustc::new("rustc").unwrap());